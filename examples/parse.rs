@@ -1,7 +1,16 @@
-use rustvision::{load_image, save_pnm_p3};
+use rustvision::{
+    error::Result,
+    image::{Image, ImageFormat, SaveOptions},
+};
 
-fn main() {
-    let image = load_image!("assets/mona_lisa_small.ppm");
+fn main() -> Result<()> {
+    let image = Image::open("assets/mona_lisa_small.ppm")?;
 
-    save_pnm_p3!("assets/mona_lisa_small_2.ppm", image);
+    image.save_with(
+        "assets/mona_lisa_small_2.ppm",
+        &SaveOptions {
+            format: Some(ImageFormat::Ppm3),
+            ..Default::default()
+        },
+    )
 }
@@ -1,11 +1,12 @@
 use rustvision::{
-    image::Image,
-    rgb, save_pnm_p3, save_pnm_p6,
+    error::Result,
+    image::{Image, ImageFormat, SaveOptions},
+    rgb,
     shapes::{Line, Polygon, Rectangle},
     vec2,
 };
 
-fn main() {
+fn main() -> Result<()> {
     let mut img = Image::new(400, 400);
 
     let rect = Rectangle::new(vec2![50.0, 40.0], 100, 70, rgb!(255, 0, 0));
@@ -38,6 +39,12 @@ fn main() {
     img.draw(&polygon);
     img.draw(&quat);
 
-    save_pnm_p3!("assets/simple2.ppm", img);
-    save_pnm_p6!("assets/simple3.ppm", img);
+    img.save_with(
+        "assets/simple2.ppm",
+        &SaveOptions {
+            format: Some(ImageFormat::Ppm3),
+            ..Default::default()
+        },
+    )?;
+    img.save("assets/simple3.ppm")
 }
@@ -0,0 +1,637 @@
+//! Reading and writing images in the BMP format.
+
+use crate::{
+    bytes::{read_bytes, read_u16_le, read_u32_le, read_u8},
+    color::Color,
+    error::{checked_samples, Error, Result},
+    image::Image,
+};
+
+const FILE_HEADER_SIZE: usize = 14;
+const CORE_HEADER_SIZE: usize = 12;
+const INFO_HEADER_SIZE: usize = 40;
+/// Upper limit for the number of pixels of RLE compressed images, whose size is not bounded by the
+/// length of the file.
+const MAX_RLE_PIXELS: usize = 1 << 30;
+
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+/// Resolution of written files in pixels per meter (i.e., 72 DPI).
+const PIXELS_PER_METER: u32 = 2835;
+
+/// Trait for transforming any values into their BMP representation.
+pub trait BMP {
+    /// Convert a value into its 24bit BMP representation.
+    fn to_bmp24(&self) -> Vec<u8>;
+
+    /// Convert a value into its 32bit BMP representation.
+    fn to_bmp32(&self) -> Vec<u8>;
+}
+
+/// Macro for saving an image in its 24bit BMP representation to the disk.
+#[macro_export]
+macro_rules! save_bmp24 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::bmp::BMP::to_bmp24(&$img))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its 32bit BMP representation to the disk.
+#[macro_export]
+macro_rules! save_bmp32 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::bmp::BMP::to_bmp32(&$img))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its 24bit BMP representation to the disk. Returns an error, if
+/// the file could not be written.
+#[macro_export]
+macro_rules! try_save_bmp24 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::bmp::BMP::to_bmp24(&$img))
+    };
+}
+
+/// Macro for saving an image in its 32bit BMP representation to the disk. Returns an error, if
+/// the file could not be written.
+#[macro_export]
+macro_rules! try_save_bmp32 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::bmp::BMP::to_bmp32(&$img))
+    };
+}
+
+/// Encode an image as uncompressed bottom-up BMP with the given number of bits per pixel (i.e.,
+/// 24 or 32).
+fn encode_bmp(image: &Image, bits_per_pixel: u16) -> Vec<u8> {
+    let bytes_per_pixel = bits_per_pixel as usize / 8;
+    // every row is padded to a multiple of 4 bytes
+    let row_len = (image.cols() * bytes_per_pixel).div_ceil(4) * 4;
+    let data_len = row_len * image.rows();
+    let data_start = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+
+    let mut bmp = Vec::with_capacity(data_start + data_len);
+
+    // file header
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&((data_start + data_len) as u32).to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&(data_start as u32).to_le_bytes());
+
+    // info header
+    bmp.extend_from_slice(&(INFO_HEADER_SIZE as u32).to_le_bytes());
+    bmp.extend_from_slice(&(image.cols() as i32).to_le_bytes());
+    bmp.extend_from_slice(&(image.rows() as i32).to_le_bytes());
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&bits_per_pixel.to_le_bytes());
+    bmp.extend_from_slice(&BI_RGB.to_le_bytes());
+    bmp.extend_from_slice(&(data_len as u32).to_le_bytes());
+    bmp.extend_from_slice(&PIXELS_PER_METER.to_le_bytes());
+    bmp.extend_from_slice(&PIXELS_PER_METER.to_le_bytes());
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+
+    // rows are stored bottom-up
+    for y in (0..image.rows()).rev() {
+        let row_start = bmp.len();
+        for x in 0..image.cols() {
+            let color = image.get(x, y).unwrap_or_default();
+            bmp.extend_from_slice(&[color.b, color.g, color.r]);
+            if bytes_per_pixel == 4 {
+                bmp.push(u8::MAX);
+            }
+        }
+        bmp.resize(row_start + row_len, 0);
+    }
+    bmp
+}
+
+impl BMP for Image {
+    fn to_bmp24(&self) -> Vec<u8> {
+        encode_bmp(self, 24)
+    }
+
+    fn to_bmp32(&self) -> Vec<u8> {
+        encode_bmp(self, 32)
+    }
+}
+
+/// Header of a BMP file.
+struct BmpHeader {
+    width: usize,
+    height: usize,
+    top_down: bool,
+    bits_per_pixel: u16,
+    compression: u32,
+    /// Bit masks for the red, green and blue channel of 16bit and 32bit images.
+    masks: [u32; 3],
+    palette: Vec<Color>,
+    /// Offset of the pixel data.
+    data_start: usize,
+}
+
+/// Parse the file header, the DIB header and the color table of a BMP file.
+fn parse_bmp_header(inp: &[u8]) -> Result<BmpHeader> {
+    if inp.get(0..2) != Some(b"BM") {
+        return Err(Error::InvalidMagicConstant(
+            "No valid magic constant".to_string(),
+        ));
+    }
+
+    let data_start = read_u32_le(inp, 10)? as usize;
+    let header_size = read_u32_le(inp, FILE_HEADER_SIZE)? as usize;
+
+    let (width, height, top_down, bits_per_pixel, compression, colors_used, entry_size) =
+        match header_size {
+            CORE_HEADER_SIZE => (
+                read_u16_le(inp, 18)? as usize,
+                read_u16_le(inp, 20)? as usize,
+                false,
+                read_u16_le(inp, 24)?,
+                BI_RGB,
+                0,
+                3,
+            ),
+            size if size >= INFO_HEADER_SIZE => {
+                let width = read_u32_le(inp, 18)? as i32;
+                let height = read_u32_le(inp, 22)? as i32;
+                if width <= 0 || height == 0 || height == i32::MIN {
+                    return Err(Error::InvalidHeader(format!(
+                        "Dimensions do not have a valid format! ({}x{})",
+                        width, height
+                    )));
+                }
+                (
+                    width as usize,
+                    height.unsigned_abs() as usize,
+                    height < 0,
+                    read_u16_le(inp, 28)?,
+                    read_u32_le(inp, 30)?,
+                    read_u32_le(inp, 46)? as usize,
+                    4,
+                )
+            }
+            size => {
+                return Err(Error::InvalidHeader(format!(
+                    "Unsupported DIB header size ({})",
+                    size
+                )))
+            }
+        };
+
+    match (compression, bits_per_pixel) {
+        (BI_RGB, 1 | 4 | 8 | 16 | 24 | 32)
+        | (BI_RLE8, 8)
+        | (BI_RLE4, 4)
+        | (BI_BITFIELDS | BI_ALPHABITFIELDS, 16 | 32) => {}
+        (compression, bits_per_pixel) => {
+            return Err(Error::InvalidHeader(format!(
+                "Unsupported compression {} with {} bits per pixel",
+                compression, bits_per_pixel
+            )))
+        }
+    }
+
+    if top_down && matches!(compression, BI_RLE8 | BI_RLE4) {
+        return Err(Error::InvalidHeader(
+            "Compressed images can not be stored top-down".to_string(),
+        ));
+    }
+
+    // the masks directly follow the info header, either as part of a newer header or as an
+    // additional field
+    let masks_start = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+    let (masks, masks_len) = match (compression, bits_per_pixel) {
+        (BI_BITFIELDS | BI_ALPHABITFIELDS, _) => {
+            let masks = [
+                read_u32_le(inp, masks_start)?,
+                read_u32_le(inp, masks_start + 4)?,
+                read_u32_le(inp, masks_start + 8)?,
+            ];
+            (masks, if compression == BI_BITFIELDS { 12 } else { 16 })
+        }
+        (_, 16) => ([0x7c00, 0x03e0, 0x001f], 0),
+        _ => ([0x00ff_0000, 0x0000_ff00, 0x0000_00ff], 0),
+    };
+
+    let palette_start = if header_size == INFO_HEADER_SIZE {
+        masks_start + masks_len
+    } else {
+        FILE_HEADER_SIZE + header_size
+    };
+
+    let palette = if bits_per_pixel <= 8 {
+        let max_colors = 1 << bits_per_pixel;
+        let colors = if colors_used == 0 || colors_used > max_colors {
+            max_colors
+        } else {
+            colors_used
+        };
+        // some writers store fewer colors than announced, so only read up to the pixel data
+        let colors = colors.min(data_start.saturating_sub(palette_start) / entry_size);
+        (0..colors)
+            .map(|i| {
+                let entry = read_bytes(inp, palette_start + i * entry_size, 3)?;
+                Ok(Color::new(entry[2], entry[1], entry[0]))
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        vec![]
+    };
+
+    Ok(BmpHeader {
+        width,
+        height,
+        top_down,
+        bits_per_pixel,
+        compression,
+        masks,
+        palette,
+        data_start,
+    })
+}
+
+/// Extract a channel from a pixel value with the given bit mask and scale it to 8bit.
+fn apply_mask(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let shift = mask.trailing_zeros();
+    let max = (mask >> shift) as u64;
+    let value = ((value & mask) >> shift) as u64;
+    ((value * u8::MAX as u64 + max / 2) / max) as u8
+}
+
+/// Look up a color in the palette.
+fn palette_color(palette: &[Color], index: u8) -> Result<Color> {
+    palette.get(index as usize).copied().ok_or_else(|| {
+        Error::InvalidData(format!(
+            "Color index {} exceeds palette of size {}",
+            index,
+            palette.len()
+        ))
+    })
+}
+
+/// Decode the uncompressed pixel data of a BMP file.
+fn decode_uncompressed(inp: &[u8], header: &BmpHeader) -> Result<Image> {
+    let bits_per_row = checked_samples(header.width, header.bits_per_pixel as usize, 1)?;
+    // every row is padded to a multiple of 4 bytes
+    let row_len = bits_per_row.div_ceil(32) * 4;
+    let data_len = checked_samples(row_len, header.height, 1)?;
+    let data = read_bytes(inp, header.data_start, data_len)?;
+
+    let mut image = Image::new(header.width, header.height);
+
+    for (i, row) in data.chunks_exact(row_len).enumerate() {
+        let y = if header.top_down {
+            i
+        } else {
+            header.height - 1 - i
+        };
+
+        for x in 0..header.width {
+            let color = match header.bits_per_pixel {
+                1 | 4 | 8 => {
+                    let bits = header.bits_per_pixel as usize;
+                    let bit = x * bits;
+                    let index = (row[bit / 8] >> (8 - bits - bit % 8)) & ((1 << bits) - 1) as u8;
+                    palette_color(&header.palette, index)?
+                }
+                24 => Color::new(row[3 * x + 2], row[3 * x + 1], row[3 * x]),
+                bits => {
+                    let value = match bits {
+                        16 => u16::from_le_bytes([row[2 * x], row[2 * x + 1]]) as u32,
+                        _ => u32::from_le_bytes([
+                            row[4 * x],
+                            row[4 * x + 1],
+                            row[4 * x + 2],
+                            row[4 * x + 3],
+                        ]),
+                    };
+                    let [r, g, b] = header.masks;
+                    Color::new(
+                        apply_mask(value, r),
+                        apply_mask(value, g),
+                        apply_mask(value, b),
+                    )
+                }
+            };
+            image.set(x, y, color);
+        }
+    }
+    Ok(image)
+}
+
+/// Decode the RLE4 or RLE8 compressed pixel data of a BMP file.
+fn decode_rle(inp: &[u8], header: &BmpHeader) -> Result<Image> {
+    let rle4 = header.compression == BI_RLE4;
+    let pixels = checked_samples(header.width, header.height, 1)?;
+    // escapes skip pixels without any data, so even tiny files may describe large images
+    if pixels > MAX_RLE_PIXELS {
+        return Err(Error::DimensionOverflow {
+            width: header.width,
+            height: header.height,
+        });
+    }
+    let mut indices = vec![0u8; pixels];
+
+    let mut pos = header.data_start;
+    let mut x = 0;
+    // RLE images are always stored bottom-up
+    let mut row = 0;
+
+    let mut put = |x: usize, row: usize, index: u8| {
+        if x < header.width && row < header.height {
+            indices[(header.height - 1 - row) * header.width + x] = index;
+        }
+    };
+
+    while row < header.height {
+        let count = read_u8(inp, pos)? as usize;
+        let value = read_u8(inp, pos + 1)?;
+        pos += 2;
+
+        if count > 0 {
+            // encoded mode: repeat the value (or the two nibbles of it)
+            for i in 0..count {
+                let index = match rle4 {
+                    true if i % 2 == 0 => value >> 4,
+                    true => value & 0x0f,
+                    false => value,
+                };
+                put(x, row, index);
+                x += 1;
+            }
+            continue;
+        }
+
+        match value {
+            // end of line
+            0 => {
+                x = 0;
+                row += 1;
+            }
+            // end of bitmap
+            1 => break,
+            // delta
+            2 => {
+                x += read_u8(inp, pos)? as usize;
+                row += read_u8(inp, pos + 1)? as usize;
+                pos += 2;
+            }
+            // absolute mode: copy the following values, padded to a 16bit boundary
+            count => {
+                let count = count as usize;
+                let len = if rle4 { count.div_ceil(2) } else { count };
+                let values = read_bytes(inp, pos, len)?;
+                for i in 0..count {
+                    let index = if rle4 {
+                        (values[i / 2] >> (4 * (1 - i % 2))) & 0x0f
+                    } else {
+                        values[i]
+                    };
+                    put(x, row, index);
+                    x += 1;
+                }
+                pos += len + len % 2;
+            }
+        }
+    }
+
+    let mut image = Image::new(header.width, header.height);
+    for (i, index) in indices.iter().enumerate() {
+        image.set(
+            i % header.width,
+            i / header.width,
+            palette_color(&header.palette, *index)?,
+        );
+    }
+    Ok(image)
+}
+
+/// Parse a vector of u8 into a valid BMP image. This function will panic, if the format is not
+/// valid. See `try_parse_bmp` for a fallible variant.
+pub fn parse_bmp(inp: Vec<u8>) -> Image {
+    try_parse_bmp(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid BMP image. Supported are paletted images with 1, 4
+/// and 8 bits per pixel (optionally RLE compressed), as well as 16, 24 and 32 bit images. Alpha
+/// channels are dropped.
+pub fn try_parse_bmp(inp: Vec<u8>) -> Result<Image> {
+    let header = parse_bmp_header(&inp)?;
+
+    match header.compression {
+        BI_RLE8 | BI_RLE4 => decode_rle(&inp, &header),
+        _ => decode_uncompressed(&inp, &header),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgb;
+
+    /// Build a BMP file with an info header.
+    fn build_bmp(
+        width: i32,
+        height: i32,
+        bits_per_pixel: u16,
+        compression: u32,
+        extra: &[u8],
+        data: &[u8],
+    ) -> Vec<u8> {
+        let data_start = (FILE_HEADER_SIZE + INFO_HEADER_SIZE + extra.len()) as u32;
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&(data_start + data.len() as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&data_start.to_le_bytes());
+        bmp.extend_from_slice(&(INFO_HEADER_SIZE as u32).to_le_bytes());
+        bmp.extend_from_slice(&width.to_le_bytes());
+        bmp.extend_from_slice(&height.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&bits_per_pixel.to_le_bytes());
+        bmp.extend_from_slice(&compression.to_le_bytes());
+        bmp.extend_from_slice(&[0; 20]);
+        bmp.extend_from_slice(extra);
+        bmp.extend_from_slice(data);
+        bmp
+    }
+
+    const PALETTE: [u8; 16] = [0, 0, 0, 0, 255, 255, 255, 0, 0, 0, 255, 0, 255, 0, 0, 0];
+
+    #[test]
+    fn test_parse_bmp_1bit() {
+        let bmp = build_bmp(
+            3,
+            2,
+            1,
+            BI_RGB,
+            &PALETTE[..8],
+            &[0b0100_0000, 0, 0, 0, 0b1010_0000, 0, 0, 0],
+        );
+        let image = parse_bmp(bmp);
+        assert_eq!(image.get(0, 0), Some(rgb!(255)));
+        assert_eq!(image.get(1, 0), Some(rgb!(0)));
+        assert_eq!(image.get(2, 0), Some(rgb!(255)));
+        assert_eq!(image.get(1, 1), Some(rgb!(255)));
+        assert_eq!(image.get(0, 1), Some(rgb!(0)));
+    }
+
+    #[test]
+    fn test_parse_bmp_4bit_and_8bit() {
+        let bmp = build_bmp(3, 1, 4, BI_RGB, &PALETTE, &[0x23, 0x10, 0, 0]);
+        let image = parse_bmp(bmp);
+        assert_eq!(image.get(0, 0), Some(rgb!(255, 0, 0)));
+        assert_eq!(image.get(1, 0), Some(rgb!(0, 0, 255)));
+        assert_eq!(image.get(2, 0), Some(rgb!(255)));
+
+        let bmp = build_bmp(2, 1, 8, BI_RGB, &PALETTE, &[3, 2, 0, 0]);
+        let image = parse_bmp(bmp);
+        assert_eq!(image.get(0, 0), Some(rgb!(0, 0, 255)));
+        assert_eq!(image.get(1, 0), Some(rgb!(255, 0, 0)));
+    }
+
+    #[test]
+    fn test_parse_bmp_top_down() {
+        let bmp = build_bmp(1, -2, 24, BI_RGB, &[], &[1, 2, 3, 0, 4, 5, 6, 0]);
+        let image = parse_bmp(bmp);
+        assert_eq!(image.get(0, 0), Some(rgb!(3, 2, 1)));
+        assert_eq!(image.get(0, 1), Some(rgb!(6, 5, 4)));
+    }
+
+    #[test]
+    fn test_parse_bmp_bitfields() {
+        // 32bit with RGBA masks in a custom order
+        let masks = [
+            0x0000_00ffu32.to_le_bytes(),
+            0x0000_ff00u32.to_le_bytes(),
+            0x00ff_0000u32.to_le_bytes(),
+        ]
+        .concat();
+        let bmp = build_bmp(1, 1, 32, BI_BITFIELDS, &masks, &[1, 2, 3, 4]);
+        assert_eq!(parse_bmp(bmp).get(0, 0), Some(rgb!(1, 2, 3)));
+
+        // 16bit with the default 5-5-5 layout
+        let bmp = build_bmp(1, 1, 16, BI_RGB, &[], &[0x1f, 0x7c, 0, 0]);
+        assert_eq!(parse_bmp(bmp).get(0, 0), Some(rgb!(255, 0, 255)));
+    }
+
+    #[test]
+    fn test_parse_bmp_rle8() {
+        let data = [
+            // bottom row: 2x index 1, delta to next row
+            2, 1, 0, 0, //
+            // top row: absolute run of 3 values (padded)
+            0, 3, 2, 3, 1, 0, //
+            0, 1,
+        ];
+        let bmp = build_bmp(3, 2, 8, BI_RLE8, &PALETTE, &data);
+        let image = parse_bmp(bmp);
+        assert_eq!(image.get(0, 1), Some(rgb!(255)));
+        assert_eq!(image.get(1, 1), Some(rgb!(255)));
+        assert_eq!(image.get(2, 1), Some(rgb!(0)));
+        assert_eq!(image.get(0, 0), Some(rgb!(255, 0, 0)));
+        assert_eq!(image.get(1, 0), Some(rgb!(0, 0, 255)));
+        assert_eq!(image.get(2, 0), Some(rgb!(255)));
+    }
+
+    #[test]
+    fn test_parse_bmp_rle4() {
+        let data = [
+            // bottom row: alternating 1 and 2
+            3, 0x12, 0, 0, //
+            // delta (1, 0), then absolute run of 3 values (the last one is clipped)
+            0, 2, 1, 0, 0, 3, 0x31, 0x10, //
+            0, 1,
+        ];
+        let bmp = build_bmp(3, 2, 4, BI_RLE4, &PALETTE, &data);
+        let image = parse_bmp(bmp);
+        assert_eq!(image.get(0, 1), Some(rgb!(255)));
+        assert_eq!(image.get(1, 1), Some(rgb!(255, 0, 0)));
+        assert_eq!(image.get(2, 1), Some(rgb!(255)));
+        assert_eq!(image.get(0, 0), Some(rgb!(0)));
+        assert_eq!(image.get(1, 0), Some(rgb!(0, 0, 255)));
+        assert_eq!(image.get(2, 0), Some(rgb!(255)));
+    }
+
+    #[test]
+    fn test_parse_bmp_errors() {
+        assert!(matches!(
+            try_parse_bmp(b"BA".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+        assert!(matches!(
+            try_parse_bmp(build_bmp(2, 2, 24, BI_RGB, &[], &[0; 8])),
+            Err(Error::TruncatedData { .. })
+        ));
+        assert!(matches!(
+            try_parse_bmp(build_bmp(1, 1, 8, BI_RGB, &PALETTE[..8], &[5, 0, 0, 0])),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            try_parse_bmp(build_bmp(1, 1, 24, BI_RLE8, &[], &[])),
+            Err(Error::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_bmp_rle_escapes() {
+        // end of bitmap right away: all pixels use the first color
+        let bmp = build_bmp(600, 600, 8, BI_RLE8, &PALETTE, &[0, 1]);
+        let image = parse_bmp(bmp);
+        assert_eq!((image.cols(), image.rows()), (600, 600));
+        assert!(image.pixels().all(|pixel| pixel == rgb!(0)));
+
+        // a single pixel per row, which is ended by an end of line
+        let data: Vec<u8> = (0..4).flat_map(|_| [1, 1, 0, 0]).chain([0, 1]).collect();
+        let image = parse_bmp(build_bmp(1000, 4, 8, BI_RLE8, &PALETTE, &data));
+        for y in 0..4 {
+            assert_eq!(image.get(0, y), Some(rgb!(255)));
+            assert_eq!(image.get(999, y), Some(rgb!(0)));
+        }
+    }
+
+    #[test]
+    fn test_parse_bmp_rle_huge_dimensions() {
+        let bmp = build_bmp(120000, 90000, 8, BI_RLE8, &PALETTE, &[0, 1]);
+        assert!(matches!(
+            try_parse_bmp(bmp),
+            Err(Error::DimensionOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bmp_roundtrip() {
+        let mut image = Image::new(5, 3);
+        image.set(0, 0, rgb!(1, 2, 3));
+        image.set(4, 2, rgb!(255, 128, 0));
+        image.set(2, 1, rgb!(17, 42, 99));
+
+        for bmp in [image.to_bmp24(), image.to_bmp32()] {
+            let parsed = parse_bmp(bmp);
+            assert_eq!(parsed.cols(), 5);
+            assert_eq!(parsed.rows(), 3);
+            for y in 0..3 {
+                for x in 0..5 {
+                    assert_eq!(parsed.get(x, y), image.get(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bmp24_padding() {
+        let image = Image::new(1, 2);
+        let bmp = image.to_bmp24();
+        assert_eq!(bmp.len(), FILE_HEADER_SIZE + INFO_HEADER_SIZE + 8);
+    }
+}
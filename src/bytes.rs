@@ -0,0 +1,71 @@
+//! Utilities for reading integers from byte buffers.
+
+use crate::error::{Error, Result};
+
+/// Get `len` bytes starting at the given offset, or an error, if the buffer is too short.
+pub(crate) fn read_bytes(inp: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(len)
+        .and_then(|end| inp.get(offset..end))
+        .ok_or(Error::TruncatedData {
+            expected: offset.saturating_add(len),
+            actual: inp.len(),
+        })
+}
+
+/// Read a single byte at the given offset.
+pub(crate) fn read_u8(inp: &[u8], offset: usize) -> Result<u8> {
+    Ok(read_bytes(inp, offset, 1)?[0])
+}
+
+/// Read a little endian u16 at the given offset.
+pub(crate) fn read_u16_le(inp: &[u8], offset: usize) -> Result<u16> {
+    let bytes = read_bytes(inp, offset, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Read a big endian u16 at the given offset.
+pub(crate) fn read_u16_be(inp: &[u8], offset: usize) -> Result<u16> {
+    let bytes = read_bytes(inp, offset, 2)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Read a little endian u32 at the given offset.
+pub(crate) fn read_u32_le(inp: &[u8], offset: usize) -> Result<u32> {
+    let bytes = read_bytes(inp, offset, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Read a big endian u32 at the given offset.
+pub(crate) fn read_u32_be(inp: &[u8], offset: usize) -> Result<u32> {
+    let bytes = read_bytes(inp, offset, 4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_integers() {
+        let inp = [0x01, 0x02, 0x03, 0x04, 0x05];
+        assert_eq!(read_u8(&inp, 4).unwrap(), 0x05);
+        assert_eq!(read_u16_le(&inp, 0).unwrap(), 0x0201);
+        assert_eq!(read_u16_be(&inp, 0).unwrap(), 0x0102);
+        assert_eq!(read_u32_le(&inp, 1).unwrap(), 0x05040302);
+        assert_eq!(read_u32_be(&inp, 1).unwrap(), 0x02030405);
+    }
+
+    #[test]
+    fn test_read_out_of_bounds() {
+        let inp = [0x01, 0x02];
+        assert!(matches!(
+            read_u32_le(&inp, 0),
+            Err(Error::TruncatedData {
+                expected: 4,
+                actual: 2
+            })
+        ));
+        assert!(read_bytes(&inp, usize::MAX, 2).is_err());
+    }
+}
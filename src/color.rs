@@ -2,7 +2,7 @@
 use crate::ppm::PNM;
 
 /// Struct for representing a regular 8bit RGB color.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -28,11 +28,161 @@ impl Color {
         assert_eq!(vals.len(), 3);
         Self::new(vals[0], vals[1], vals[2])
     }
+
+    /// Calculate the luma (i.e., the perceived brightness) of this color according to ITU-R BT.601.
+    pub fn luma(&self) -> u8 {
+        ((299 * self.r as u32 + 587 * self.g as u32 + 114 * self.b as u32 + 500) / 1000) as u8
+    }
+}
+
+/// Struct for representing an RGB color with 16bit per channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Color16 {
+    pub r: u16,
+    pub g: u16,
+    pub b: u16,
+}
+
+impl Color16 {
+    pub fn new(r: u16, g: u16, b: u16) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Create a color where all values (i.e., r, g & b) have the same value.
+    pub fn splat(val: u16) -> Self {
+        Self {
+            r: val,
+            g: val,
+            b: val,
+        }
+    }
+
+    /// Calculate the luma (i.e., the perceived brightness) of this color according to ITU-R BT.601.
+    pub fn luma(&self) -> u16 {
+        ((299 * self.r as u64 + 587 * self.g as u64 + 114 * self.b as u64 + 500) / 1000) as u16
+    }
+}
+
+/// Convert a 8bit sample to 16bit by replicating it in both bytes.
+pub(crate) fn u8_to_u16(val: u8) -> u16 {
+    val as u16 * 257
+}
+
+/// Convert a 16bit sample to 8bit with correct rounding.
+pub(crate) fn u16_to_u8(val: u16) -> u8 {
+    ((val as u32 + 128) / 257) as u8
+}
+
+impl From<Color> for Color16 {
+    fn from(color: Color) -> Self {
+        Self::new(u8_to_u16(color.r), u8_to_u16(color.g), u8_to_u16(color.b))
+    }
+}
+
+impl From<Color16> for Color {
+    fn from(color: Color16) -> Self {
+        Self::new(u16_to_u8(color.r), u16_to_u8(color.g), u16_to_u8(color.b))
+    }
+}
+
+/// Struct for representing an 8bit RGB color with an alpha channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Get the color of this value without the alpha channel.
+    pub fn rgb(&self) -> Color {
+        Color::new(self.r, self.g, self.b)
+    }
+}
+
+impl From<Color> for Rgba {
+    /// Convert a color into a fully opaque RGBA color.
+    fn from(color: Color) -> Self {
+        Self::new(color.r, color.g, color.b, u8::MAX)
+    }
+}
+
+/// Struct for representing an 8bit luminance value with an alpha channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GrayAlpha {
+    /// Luminance of the color.
+    pub l: u8,
+    pub a: u8,
+}
+
+impl GrayAlpha {
+    pub fn new(l: u8, a: u8) -> Self {
+        Self { l, a }
+    }
+}
+
+/// Struct for representing a (linear) RGB color with floating point samples, which may exceed the
+/// range from 0 to 1 (e.g., for high dynamic range images).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ColorF32 {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl ColorF32 {
+    pub fn new(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Create a color where all values (i.e., r, g & b) have the same value.
+    pub fn splat(val: f32) -> Self {
+        Self {
+            r: val,
+            g: val,
+            b: val,
+        }
+    }
+
+    /// Calculate the relative luminance of this color according to ITU-R BT.709.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// Multiply all samples of this color with a factor.
+    pub fn scale(&self, factor: f32) -> Self {
+        Self::new(self.r * factor, self.g * factor, self.b * factor)
+    }
 }
 
-impl ToString for Color {
-    fn to_string(&self) -> String {
-        format!("({},{},{})", self.r, self.g, self.b)
+impl From<Color> for ColorF32 {
+    /// Convert a color into floating point samples from 0 to 1.
+    fn from(color: Color) -> Self {
+        Self::new(
+            color.r as f32 / 255.0,
+            color.g as f32 / 255.0,
+            color.b as f32 / 255.0,
+        )
+    }
+}
+
+impl From<ColorF32> for Color {
+    /// Convert floating point samples into a color, where samples outside of the range from 0 to 1
+    /// are clamped.
+    fn from(color: ColorF32) -> Self {
+        let sample = |val: f32| (val.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self::new(sample(color.r), sample(color.g), sample(color.b))
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({},{},{})", self.r, self.g, self.b)
     }
 }
 
@@ -90,6 +240,40 @@ macro_rules! rgb {
     };
 }
 
+/// Utility macro for creating an RGBA color.
+///
+/// # Examples
+///
+/// Calling this macro with three arguments creates a fully opaque color:
+///
+/// ```rust
+/// use rustvision::{rgba, color::Rgba};
+///
+/// let color = rgba!(42, 17, 129);
+/// assert_eq!(color, Rgba { r: 42, g: 17, b: 129, a: 255 });
+/// ```
+///
+/// The alpha channel can be provided as fourth argument:
+///
+/// ```rust
+/// use rustvision::{rgba, color::Rgba};
+///
+/// let color = rgba!(42, 17, 129, 64);
+/// assert_eq!(color, Rgba { r: 42, g: 17, b: 129, a: 64 });
+/// ```
+#[macro_export]
+macro_rules! rgba {
+    () => {
+        $crate::color::Rgba::default()
+    };
+    ($r:expr, $g:expr, $b:expr) => {
+        $crate::color::Rgba::new($r, $g, $b, u8::MAX)
+    };
+    ($r:expr, $g:expr, $b:expr, $a:expr) => {
+        $crate::color::Rgba::new($r, $g, $b, $a)
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,6 +323,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_color_luma() {
+        assert_eq!(Color::splat(0).luma(), 0);
+        assert_eq!(Color::splat(255).luma(), 255);
+        assert_eq!(Color::new(0, 255, 0).luma(), 150);
+    }
+
+    #[test]
+    fn test_color16_conversion() {
+        let color = Color::new(0, 128, 255);
+        let color16 = Color16::from(color);
+        assert_eq!(color16, Color16::new(0, 32896, 65535));
+        assert_eq!(Color::from(color16), color);
+        assert_eq!(Color::from(Color16::splat(0x8140)), Color::splat(129));
+    }
+
+    #[test]
+    fn test_color_f32_conversion() {
+        let color = Color::new(0, 51, 255);
+        assert_eq!(ColorF32::from(color), ColorF32::new(0.0, 0.2, 1.0));
+        assert_eq!(Color::from(ColorF32::from(color)), color);
+        assert_eq!(
+            Color::from(ColorF32::new(-1.0, 0.5, 7.0)),
+            Color::new(0, 128, 255)
+        );
+        assert!((ColorF32::splat(2.0).luminance() - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rgba_from_color() {
+        assert_eq!(Rgba::from(Color::new(1, 2, 3)), Rgba::new(1, 2, 3, 255));
+        assert_eq!(Rgba::new(1, 2, 3, 4).rgb(), Color::new(1, 2, 3));
+    }
+
     #[test]
     fn test_macro_empty() {
         assert_eq!(rgb!(), Color { r: 0, g: 0, b: 0 });
@@ -0,0 +1,896 @@
+//! Self-contained implementation of the DEFLATE compression format (RFC 1951) and the zlib
+//! wrapper around it (RFC 1950).
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::error::{Error, Result};
+
+/// Base lengths of the length symbols 257..=285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+
+/// Number of extra bits of the length symbols 257..=285.
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distances of the distance symbols 0..=29.
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// Number of extra bits of the distance symbols 0..=29.
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order in which the code lengths of the code length alphabet are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const END_OF_BLOCK: usize = 256;
+const MAX_BITS: u8 = 15;
+const WINDOW_SIZE: usize = 1 << 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// Reader for the LSB-first bit stream of DEFLATE.
+struct BitReader<'a> {
+    inp: &'a [u8],
+    pos: usize,
+    bits: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(inp: &'a [u8]) -> Self {
+        Self {
+            inp,
+            pos: 0,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        while self.count <= 56 && self.pos < self.inp.len() {
+            self.bits |= (self.inp[self.pos] as u64) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+    }
+
+    /// Get the next `n` bits without consuming them. Missing bits at the end of the input are
+    /// filled with zeros.
+    fn peek(&mut self, n: u32) -> u64 {
+        if self.count < n {
+            self.refill();
+        }
+        self.bits & ((1 << n) - 1)
+    }
+
+    fn consume(&mut self, n: u32) -> Result<()> {
+        if n > self.count {
+            return Err(Error::TruncatedData {
+                expected: self.inp.len() + 1,
+                actual: self.inp.len(),
+            });
+        }
+        self.bits >>= n;
+        self.count -= n;
+        Ok(())
+    }
+
+    fn read(&mut self, n: u32) -> Result<u32> {
+        let value = self.peek(n);
+        self.consume(n)?;
+        Ok(value as u32)
+    }
+
+    /// Skip the remaining bits of the current byte.
+    fn align_to_byte(&mut self) {
+        let skip = self.count % 8;
+        self.bits >>= skip;
+        self.count -= skip;
+    }
+
+    /// Get the offset of the first byte, which has not been (partially) consumed yet.
+    fn byte_pos(&self) -> usize {
+        self.pos - (self.count / 8) as usize
+    }
+}
+
+/// Reverse the lowest `len` bits of a code.
+fn reverse_bits(code: u16, len: u8) -> u16 {
+    code.reverse_bits() >> (16 - len as u32)
+}
+
+/// Calculate the canonical Huffman codes for the given code lengths.
+fn canonical_codes(lengths: &[u8]) -> Result<Vec<u16>> {
+    let mut count = [0u16; MAX_BITS as usize + 1];
+    for len in lengths {
+        count[*len as usize] += 1;
+    }
+    count[0] = 0;
+
+    // the code must not be over-subscribed (incomplete codes are allowed)
+    let mut left = 1i32;
+    for len_count in &count[1..] {
+        left = (left << 1) - *len_count as i32;
+        if left < 0 {
+            return Err(Error::InvalidData(
+                "Over-subscribed Huffman code".to_string(),
+            ));
+        }
+    }
+
+    let mut next_code = [0u16; MAX_BITS as usize + 2];
+    for len in 1..=MAX_BITS as usize {
+        next_code[len + 1] = (next_code[len] + count[len]) << 1;
+    }
+
+    Ok(lengths
+        .iter()
+        .map(|len| {
+            let code = next_code[*len as usize];
+            next_code[*len as usize] += 1;
+            code
+        })
+        .collect())
+}
+
+/// Lookup table for decoding Huffman codes. Every entry contains the symbol in the upper and the
+/// code length in the lower 4 bits.
+struct Huffman {
+    table: Vec<u16>,
+    max_len: u32,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let codes = canonical_codes(lengths)?;
+        let max_len = lengths.iter().copied().max().unwrap_or(0);
+        let mut table = vec![0u16; 1 << max_len];
+
+        for (symbol, (len, code)) in lengths.iter().zip(codes).enumerate() {
+            if *len == 0 {
+                continue;
+            }
+            let reversed = reverse_bits(code, *len) as usize;
+            let entry = ((symbol as u16) << 4) | *len as u16;
+            for index in (reversed..table.len()).step_by(1 << len) {
+                table[index] = entry;
+            }
+        }
+
+        Ok(Self {
+            table,
+            max_len: max_len as u32,
+        })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<usize> {
+        let entry = self.table[reader.peek(self.max_len) as usize];
+        let len = (entry & 0x0f) as u32;
+        if len == 0 {
+            return Err(Error::InvalidData("Invalid Huffman code".to_string()));
+        }
+        reader.consume(len)?;
+        Ok((entry >> 4) as usize)
+    }
+}
+
+/// Code lengths of the fixed literal/length and distance codes.
+fn fixed_lengths() -> (Vec<u8>, Vec<u8>) {
+    let mut lit_len = vec![8u8; 288];
+    lit_len[144..256].fill(9);
+    lit_len[256..280].fill(7);
+    (lit_len, vec![5u8; 30])
+}
+
+/// Read the code lengths of a block with dynamic Huffman codes.
+fn read_dynamic_lengths(reader: &mut BitReader) -> Result<(Vec<u8>, Vec<u8>)> {
+    let hlit = reader.read(5)? as usize + 257;
+    let hdist = reader.read(5)? as usize + 1;
+    let hclen = reader.read(4)? as usize + 4;
+
+    let mut code_length_lengths = [0u8; 19];
+    for symbol in &CODE_LENGTH_ORDER[..hclen] {
+        code_length_lengths[*symbol] = reader.read(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_length_lengths)?;
+
+    let mut lengths = Vec::with_capacity(hlit + hdist);
+    while lengths.len() < hlit + hdist {
+        let (value, repeat) = match code_length_code.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let Some(previous) = lengths.last() else {
+                    return Err(Error::InvalidData(
+                        "Repeated code length without previous length".to_string(),
+                    ));
+                };
+                (*previous, 3 + reader.read(2)? as usize)
+            }
+            17 => (0, 3 + reader.read(3)? as usize),
+            _ => (0, 11 + reader.read(7)? as usize),
+        };
+        if lengths.len() + repeat > hlit + hdist {
+            return Err(Error::InvalidData("Too many code lengths".to_string()));
+        }
+        lengths.extend(std::iter::repeat_n(value, repeat));
+    }
+
+    if lengths[END_OF_BLOCK] == 0 {
+        return Err(Error::InvalidData("Missing end of block code".to_string()));
+    }
+
+    let dist = lengths.split_off(hlit);
+    Ok((lengths, dist))
+}
+
+/// Decode the symbols of a compressed block until the end of the block.
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit_len: &Huffman,
+    dist: &Huffman,
+    limit: usize,
+) -> Result<()> {
+    loop {
+        let symbol = lit_len.decode(reader)?;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            END_OF_BLOCK => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                if index >= LENGTH_BASE.len() {
+                    return Err(Error::InvalidData(format!(
+                        "Invalid length symbol ({})",
+                        symbol
+                    )));
+                }
+                let len =
+                    LENGTH_BASE[index] as usize + reader.read(LENGTH_EXTRA[index] as u32)? as usize;
+
+                let index = dist.decode(reader)?;
+                if index >= DIST_BASE.len() {
+                    return Err(Error::InvalidData(format!(
+                        "Invalid distance symbol ({})",
+                        index
+                    )));
+                }
+                let distance =
+                    DIST_BASE[index] as usize + reader.read(DIST_EXTRA[index] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(Error::InvalidData(format!(
+                        "Distance {} exceeds output of {} bytes",
+                        distance,
+                        out.len()
+                    )));
+                }
+
+                // the source may overlap with the copied bytes
+                let start = out.len() - distance;
+                for i in 0..len {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+
+        if out.len() > limit {
+            return Err(Error::InvalidData(format!(
+                "Decompressed data exceeds the limit of {} bytes",
+                limit
+            )));
+        }
+    }
+}
+
+/// Decompress a raw DEFLATE stream. Returns the decompressed data and the number of consumed
+/// bytes. Decompression fails, if the output exceeds `limit` bytes.
+pub(crate) fn inflate(inp: &[u8], limit: usize) -> Result<(Vec<u8>, usize)> {
+    let mut reader = BitReader::new(inp);
+    let mut out = vec![];
+
+    loop {
+        let last = reader.read(1)? == 1;
+
+        match reader.read(2)? {
+            // stored
+            0 => {
+                reader.align_to_byte();
+                let len = reader.read(16)? as usize;
+                let nlen = reader.read(16)? as usize;
+                if len != !nlen & 0xffff {
+                    return Err(Error::InvalidData(
+                        "Stored block length does not match its complement".to_string(),
+                    ));
+                }
+                if out.len() + len > limit {
+                    return Err(Error::InvalidData(format!(
+                        "Decompressed data exceeds the limit of {} bytes",
+                        limit
+                    )));
+                }
+                for _ in 0..len {
+                    out.push(reader.read(8)? as u8);
+                }
+            }
+            // fixed Huffman codes
+            1 => {
+                let (lit_len, dist) = fixed_lengths();
+                let lit_len = Huffman::new(&lit_len)?;
+                let dist = Huffman::new(&dist)?;
+                inflate_block(&mut reader, &mut out, &lit_len, &dist, limit)?;
+            }
+            // dynamic Huffman codes
+            2 => {
+                let (lit_len, dist) = read_dynamic_lengths(&mut reader)?;
+                let lit_len = Huffman::new(&lit_len)?;
+                let dist = Huffman::new(&dist)?;
+                inflate_block(&mut reader, &mut out, &lit_len, &dist, limit)?;
+            }
+            _ => {
+                return Err(Error::InvalidData("Invalid block type".to_string()));
+            }
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    reader.align_to_byte();
+    Ok((out, reader.byte_pos()))
+}
+
+/// Calculate the Adler-32 checksum of the given data.
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let mut a = 1u32;
+    let mut b = 0u32;
+    // 5552 is the largest number of bytes, which can be summed up without overflowing
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Decompress a zlib stream. Decompression fails, if the output exceeds `limit` bytes.
+pub(crate) fn zlib_decompress(inp: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let Some(&[cmf, flg]) = inp.first_chunk::<2>() else {
+        return Err(Error::TruncatedData {
+            expected: 2,
+            actual: inp.len(),
+        });
+    };
+    let data = &inp[2..];
+
+    if cmf & 0x0f != 8 || cmf >> 4 > 7 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err(Error::InvalidData("Invalid zlib header".to_string()));
+    }
+    if flg & 0x20 != 0 {
+        return Err(Error::InvalidData(
+            "Preset dictionaries are not supported".to_string(),
+        ));
+    }
+
+    let (out, consumed) = inflate(data, limit)?;
+
+    let Some(checksum) = data.get(consumed..consumed + 4) else {
+        return Err(Error::TruncatedData {
+            expected: consumed + 6,
+            actual: inp.len(),
+        });
+    };
+    let checksum = u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+    if checksum != adler32(&out) {
+        return Err(Error::InvalidData("Adler-32 checksum mismatch".to_string()));
+    }
+
+    Ok(out)
+}
+
+/// Writer for the LSB-first bit stream of DEFLATE.
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            out: vec![],
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, len: u32) {
+        self.bits |= (value as u64) << self.count;
+        self.count += len;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// Calculate length-limited Huffman code lengths for the given symbol frequencies.
+pub(crate) fn huffman_lengths(freqs: &[u32], max_len: u8) -> Vec<u8> {
+    let mut lengths = vec![0u8; freqs.len()];
+
+    let mut symbols: Vec<usize> = (0..freqs.len()).filter(|i| freqs[*i] > 0).collect();
+    match symbols.len() {
+        0 => return lengths,
+        1 => {
+            lengths[symbols[0]] = 1;
+            return lengths;
+        }
+        _ => {}
+    }
+
+    // build the Huffman tree and calculate the depth of every leaf
+    let mut parents = vec![0usize; 2 * symbols.len() - 1];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = symbols
+        .iter()
+        .enumerate()
+        .map(|(node, symbol)| Reverse((freqs[*symbol] as u64, node)))
+        .collect();
+    let mut next_node = symbols.len();
+    while heap.len() > 1 {
+        let Reverse((freq_a, a)) = heap.pop().unwrap();
+        let Reverse((freq_b, b)) = heap.pop().unwrap();
+        parents[a] = next_node;
+        parents[b] = next_node;
+        heap.push(Reverse((freq_a + freq_b, next_node)));
+        next_node += 1;
+    }
+    let root = next_node - 1;
+
+    let mut count = vec![0u32; symbols.len().max(max_len as usize) + 1];
+    for leaf in 0..symbols.len() {
+        let mut depth = 0;
+        let mut node = leaf;
+        while node != root {
+            node = parents[node];
+            depth += 1;
+        }
+        count[depth.min(max_len as usize)] += 1;
+    }
+
+    // clamping the depths might over-subscribe the code, so move leaves down until the Kraft
+    // inequality holds again
+    let max_len = max_len as usize;
+    let mut total: u64 = (1..=max_len)
+        .map(|len| (count[len] as u64) << (max_len - len))
+        .sum();
+    while total > 1 << max_len {
+        count[max_len] -= 1;
+        for len in (1..max_len).rev() {
+            if count[len] > 0 {
+                count[len] -= 1;
+                count[len + 1] += 2;
+                break;
+            }
+        }
+        total -= 1;
+    }
+
+    // assign the shortest lengths to the most frequent symbols
+    symbols.sort_by_key(|symbol| Reverse(freqs[*symbol]));
+    let mut symbols = symbols.into_iter();
+    for (len, len_count) in count.iter().enumerate().take(max_len + 1).skip(1) {
+        for symbol in symbols.by_ref().take(*len_count as usize) {
+            lengths[symbol] = len as u8;
+        }
+    }
+    lengths
+}
+
+/// Huffman code for encoding symbols.
+struct Encoder {
+    codes: Vec<u16>,
+    lengths: Vec<u8>,
+}
+
+impl Encoder {
+    fn new(freqs: &[u32], max_len: u8) -> Self {
+        // ensure at least two symbols, so that the code is always complete
+        let mut freqs = freqs.to_vec();
+        for symbol in 0..2 {
+            if freqs.iter().filter(|freq| **freq > 0).count() < 2 && freqs[symbol] == 0 {
+                freqs[symbol] = 1;
+            }
+        }
+
+        let lengths = huffman_lengths(&freqs, max_len);
+        let codes = canonical_codes(&lengths)
+            .expect("Huffman lengths are always valid")
+            .iter()
+            .zip(&lengths)
+            .map(|(code, len)| {
+                if *len > 0 {
+                    reverse_bits(*code, *len)
+                } else {
+                    0
+                }
+            })
+            .collect();
+        Self { codes, lengths }
+    }
+
+    fn write(&self, writer: &mut BitWriter, symbol: usize) {
+        writer.write(self.codes[symbol] as u32, self.lengths[symbol] as u32);
+    }
+}
+
+/// Token of the LZ77 stage.
+#[derive(Clone, Copy)]
+enum Token {
+    Literal(u8),
+    Match { len: u16, distance: u16 },
+}
+
+/// Get the symbol index (relative to 257) and the number of extra bits for a match length.
+fn length_symbol(len: usize) -> usize {
+    LENGTH_BASE.partition_point(|base| *base as usize <= len) - 1
+}
+
+/// Get the distance symbol for a match distance.
+fn distance_symbol(distance: usize) -> usize {
+    DIST_BASE.partition_point(|base| *base as usize <= distance) - 1
+}
+
+/// Find repeated sequences in the input with hash chains.
+fn lz77(inp: &[u8]) -> Vec<Token> {
+    const HASH_BITS: u32 = 15;
+    const MAX_CHAIN: usize = 64;
+    const NONE: usize = usize::MAX;
+
+    let hash = |pos: usize| {
+        let value = (inp[pos] as u32) << 16 | (inp[pos + 1] as u32) << 8 | inp[pos + 2] as u32;
+        (value.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+    };
+
+    let mut head = vec![NONE; 1 << HASH_BITS];
+    let mut prev = vec![NONE; WINDOW_SIZE];
+    let insert = |pos: usize, head: &mut [usize], prev: &mut [usize]| {
+        if pos + MIN_MATCH <= inp.len() {
+            let h = hash(pos);
+            prev[pos % WINDOW_SIZE] = head[h];
+            head[h] = pos;
+        }
+    };
+
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while pos < inp.len() {
+        let mut best_len = 0;
+        let mut best_distance = 0;
+
+        if pos + MIN_MATCH <= inp.len() {
+            let max_len = MAX_MATCH.min(inp.len() - pos);
+            let mut candidate = head[hash(pos)];
+            let mut chain = 0;
+            while candidate != NONE && pos - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = inp[candidate..]
+                    .iter()
+                    .zip(&inp[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_distance = pos - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                // entries of the ring buffer might have been overwritten by newer positions
+                if next == NONE || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            tokens.push(Token::Match {
+                len: best_len as u16,
+                distance: best_distance as u16,
+            });
+            for i in pos..pos + best_len {
+                insert(i, &mut head, &mut prev);
+            }
+            pos += best_len;
+        } else {
+            tokens.push(Token::Literal(inp[pos]));
+            insert(pos, &mut head, &mut prev);
+            pos += 1;
+        }
+    }
+    tokens
+}
+
+/// Write a block with dynamic Huffman codes.
+fn write_dynamic_block(writer: &mut BitWriter, tokens: &[Token], last: bool) {
+    let mut lit_len_freqs = [0u32; 286];
+    let mut dist_freqs = [0u32; 30];
+    for token in tokens {
+        match token {
+            Token::Literal(byte) => lit_len_freqs[*byte as usize] += 1,
+            Token::Match { len, distance } => {
+                lit_len_freqs[257 + length_symbol(*len as usize)] += 1;
+                dist_freqs[distance_symbol(*distance as usize)] += 1;
+            }
+        }
+    }
+    lit_len_freqs[END_OF_BLOCK] = 1;
+
+    let lit_len = Encoder::new(&lit_len_freqs, MAX_BITS);
+    let dist = Encoder::new(&dist_freqs, MAX_BITS);
+
+    let hlit = 257.max(
+        lit_len
+            .lengths
+            .iter()
+            .rposition(|len| *len > 0)
+            .unwrap_or(0)
+            + 1,
+    );
+    let hdist = 1.max(dist.lengths.iter().rposition(|len| *len > 0).unwrap_or(0) + 1);
+
+    // run-length encode the code lengths: (symbol, extra bits, number of extra bits)
+    let lengths: Vec<u8> = lit_len.lengths[..hlit]
+        .iter()
+        .chain(&dist.lengths[..hdist])
+        .copied()
+        .collect();
+    let mut runs: Vec<(usize, u32, u32)> = vec![];
+    let mut i = 0;
+    while i < lengths.len() {
+        let value = lengths[i];
+        let run = lengths[i..].iter().take_while(|len| **len == value).count();
+        if value == 0 && run >= 11 {
+            let run = run.min(138);
+            runs.push((18, (run - 11) as u32, 7));
+            i += run;
+        } else if value == 0 && run >= 3 {
+            runs.push((17, (run - 3) as u32, 3));
+            i += run;
+        } else if value != 0 && run >= 4 {
+            runs.push((value as usize, 0, 0));
+            let run = (run - 1).min(6);
+            runs.push((16, (run - 3) as u32, 2));
+            i += run + 1;
+        } else {
+            runs.push((value as usize, 0, 0));
+            i += 1;
+        }
+    }
+
+    let mut code_length_freqs = [0u32; 19];
+    for (symbol, _, _) in &runs {
+        code_length_freqs[*symbol] += 1;
+    }
+    let code_length = Encoder::new(&code_length_freqs, 7);
+    let hclen = 4.max(
+        CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|symbol| code_length.lengths[*symbol] > 0)
+            .unwrap_or(0)
+            + 1,
+    );
+
+    writer.write(last as u32, 1);
+    writer.write(2, 2);
+    writer.write((hlit - 257) as u32, 5);
+    writer.write((hdist - 1) as u32, 5);
+    writer.write((hclen - 4) as u32, 4);
+    for symbol in &CODE_LENGTH_ORDER[..hclen] {
+        writer.write(code_length.lengths[*symbol] as u32, 3);
+    }
+    for (symbol, extra, extra_len) in runs {
+        code_length.write(writer, symbol);
+        writer.write(extra, extra_len);
+    }
+
+    for token in tokens {
+        match token {
+            Token::Literal(byte) => lit_len.write(writer, *byte as usize),
+            Token::Match { len, distance } => {
+                let len = *len as usize;
+                let index = length_symbol(len);
+                lit_len.write(writer, 257 + index);
+                writer.write(
+                    (len - LENGTH_BASE[index] as usize) as u32,
+                    LENGTH_EXTRA[index] as u32,
+                );
+
+                let distance = *distance as usize;
+                let index = distance_symbol(distance);
+                dist.write(writer, index);
+                writer.write(
+                    (distance - DIST_BASE[index] as usize) as u32,
+                    DIST_EXTRA[index] as u32,
+                );
+            }
+        }
+    }
+    lit_len.write(writer, END_OF_BLOCK);
+}
+
+/// Compress data into a raw DEFLATE stream.
+pub(crate) fn deflate(inp: &[u8]) -> Vec<u8> {
+    const TOKENS_PER_BLOCK: usize = 1 << 16;
+
+    let tokens = lz77(inp);
+    let mut writer = BitWriter::new();
+
+    if tokens.is_empty() {
+        write_dynamic_block(&mut writer, &[], true);
+    }
+
+    let blocks = tokens.chunks(TOKENS_PER_BLOCK);
+    let block_count = blocks.len();
+    for (i, block) in blocks.enumerate() {
+        write_dynamic_block(&mut writer, block, i + 1 == block_count);
+    }
+    writer.finish()
+}
+
+/// Compress data into a zlib stream.
+pub(crate) fn zlib_compress(inp: &[u8]) -> Vec<u8> {
+    // deflate with a 32K window and the default compression level
+    let mut out = vec![0x78, 0x9c];
+    out.append(&mut deflate(inp));
+    out.extend_from_slice(&adler32(inp).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inflate_stored() {
+        let inp = [
+            0x78, 0x01, 0x01, 0x03, 0x00, 0xfc, 0xff, 0x61, 0x62, 0x63, 0x02, 0x4d, 0x01, 0x27,
+        ];
+        assert_eq!(zlib_decompress(&inp, 100).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_inflate_fixed() {
+        let inp = [
+            0x78, 0x01, 0x4b, 0x4c, 0x84, 0x81, 0xa4, 0x64, 0x08, 0x02, 0x00, 0x41, 0x04, 0x06,
+            0xdc,
+        ];
+        assert_eq!(zlib_decompress(&inp, 100).unwrap(), b"aaaaaaaaaabcabcabc");
+    }
+
+    #[test]
+    fn test_inflate_dynamic() {
+        let inp = [
+            0x78, 0x01, 0x05, 0xc1, 0xb1, 0x0d, 0x00, 0x30, 0x08, 0x03, 0x30, 0x88, 0x98, 0x9a,
+            0x88, 0x09, 0x50, 0xfe, 0xbf, 0xb4, 0x76, 0xd0, 0x90, 0xf3, 0x6d, 0xe9, 0x8a, 0x93,
+            0x7d, 0xe8, 0x0d, 0x1a, 0x72, 0xbe, 0x2d, 0x5d, 0x71, 0xb2, 0x0f, 0xbd, 0x41, 0x43,
+            0xce, 0xb7, 0xa5, 0x2b, 0x4e, 0xf6, 0xa1, 0x37, 0x68, 0xc8, 0xf9, 0xb6, 0x74, 0xc5,
+            0xc9, 0x3e, 0xf4, 0x06, 0x0d, 0x39, 0xdf, 0x96, 0xae, 0x38, 0xd9, 0x87, 0xde, 0xa0,
+            0x21, 0xe7, 0xdb, 0xd2, 0x15, 0x27, 0xfb, 0xd0, 0x1b, 0x34, 0xe4, 0x7c, 0x5b, 0xba,
+            0xe2, 0x64, 0x1f, 0x7a, 0x83, 0x86, 0x9c, 0x6f, 0x4b, 0x57, 0x9c, 0xec, 0x43, 0x6f,
+            0xd0, 0x90, 0xf3, 0x6d, 0xe9, 0x8a, 0x93, 0x7d, 0xe8, 0x0d, 0x1a, 0x72, 0xbe, 0x2d,
+            0x5d, 0x71, 0xb2, 0x0f, 0xbd, 0x41, 0x43, 0xce, 0xb7, 0xa5, 0x2b, 0x4e, 0xf6, 0xa1,
+            0x37, 0x68, 0xc8, 0xf9, 0xb6, 0x74, 0xc5, 0xc9, 0x3e, 0xf4, 0x06, 0x0d, 0x39, 0xdf,
+            0x96, 0xae, 0x38, 0xd9, 0x87, 0xde, 0xa0, 0x21, 0xe7, 0xdb, 0xd2, 0x15, 0x27, 0xfb,
+            0xd0, 0x1b, 0x34, 0xe4, 0x0f, 0x3d, 0x0f, 0x0e, 0x12,
+        ];
+        let expected: Vec<u8> = (0..300u32)
+            .map(|i| ((i * i) % 7 + (i % 3) * 10) as u8)
+            .collect();
+        assert_eq!(zlib_decompress(&inp, 1000).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_inflate_errors() {
+        // invalid block type
+        assert!(matches!(inflate(&[0x07], 100), Err(Error::InvalidData(_))));
+        // truncated stream
+        assert!(matches!(
+            inflate(&[0x4b, 0x4c], 100),
+            Err(Error::TruncatedData { .. })
+        ));
+        // checksum mismatch
+        assert!(matches!(
+            zlib_decompress(
+                &[
+                    0x78, 0x01, 0x01, 0x03, 0x00, 0xfc, 0xff, 0x61, 0x62, 0x63, 0x02, 0x4d, 0x01,
+                    0x28
+                ],
+                100
+            ),
+            Err(Error::InvalidData(_))
+        ));
+        // output exceeds limit
+        assert!(matches!(
+            zlib_decompress(
+                &[
+                    0x78, 0x01, 0x01, 0x03, 0x00, 0xfc, 0xff, 0x61, 0x62, 0x63, 0x02, 0x4d, 0x01,
+                    0x27
+                ],
+                2
+            ),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    #[test]
+    fn test_deflate_roundtrip() {
+        let mut seed = 42u32;
+        let random: Vec<u8> = (0..100_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        let repetitive: Vec<u8> = (0..200_000u32).map(|i| (i % 251 / 7) as u8).collect();
+
+        for data in [
+            vec![],
+            vec![42],
+            b"abcabcabcabc".to_vec(),
+            random,
+            repetitive,
+        ] {
+            let compressed = zlib_compress(&data);
+            assert_eq!(zlib_decompress(&compressed, data.len()).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_deflate_compresses() {
+        let data = vec![17u8; 100_000];
+        assert!(zlib_compress(&data).len() < 1000);
+    }
+
+    #[test]
+    fn test_huffman_lengths_limited() {
+        // fibonacci frequencies lead to very deep trees
+        let mut freqs = vec![1u32, 1];
+        for i in 2..30 {
+            freqs.push(freqs[i - 1] + freqs[i - 2]);
+        }
+        let lengths = huffman_lengths(&freqs, 7);
+        assert!(lengths.iter().all(|len| (1..=7).contains(len)));
+        let kraft: f64 = lengths.iter().map(|len| 0.5f64.powi(*len as i32)).sum();
+        assert!(kraft <= 1.0);
+    }
+}
@@ -0,0 +1,99 @@
+//! Errors which can occur while loading, parsing or saving images.
+
+use std::fmt;
+
+use crate::files::InvalidMagicConstantError;
+
+/// Enum representing all errors that can occur while loading, parsing or saving images.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading from or writing to the disk failed.
+    Io(std::io::Error),
+    /// The magic constant at the head of the file is unknown or does not match the expected
+    /// format.
+    InvalidMagicConstant(String),
+    /// The header of the file is malformed.
+    InvalidHeader(String),
+    /// The pixel data of the file is malformed.
+    InvalidData(String),
+    /// The file ended before all expected data could be read.
+    TruncatedData { expected: usize, actual: usize },
+    /// The dimensions of the image are too large to be represented.
+    DimensionOverflow { width: usize, height: usize },
+    /// The format of the file could not be determined or is not supported.
+    UnsupportedFormat(String),
+}
+
+/// Result type used for all fallible operations of this library.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::InvalidMagicConstant(msg) => write!(f, "Invalid magic constant: {}", msg),
+            Error::InvalidHeader(msg) => write!(f, "Invalid header: {}", msg),
+            Error::InvalidData(msg) => write!(f, "Invalid data: {}", msg),
+            Error::TruncatedData { expected, actual } => write!(
+                f,
+                "Truncated data: expected {} bytes, but got {}",
+                expected, actual
+            ),
+            Error::DimensionOverflow { width, height } => {
+                write!(f, "Dimensions {}x{} are too large", width, height)
+            }
+            Error::UnsupportedFormat(msg) => write!(f, "Unsupported format: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<InvalidMagicConstantError> for Error {
+    fn from(err: InvalidMagicConstantError) -> Self {
+        Error::InvalidMagicConstant(err.to_string())
+    }
+}
+
+/// Calculate the number of samples of an image with the given dimensions and number of channels.
+/// Returns an error, if the number of samples can not be represented.
+pub(crate) fn checked_samples(width: usize, height: usize, channels: usize) -> Result<usize> {
+    width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or(Error::DimensionOverflow { width, height })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_samples() {
+        assert_eq!(checked_samples(4, 3, 3).unwrap(), 36);
+        assert!(matches!(
+            checked_samples(usize::MAX, 2, 1),
+            Err(Error::DimensionOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_io_error() {
+        let err = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(matches!(err, Error::Io(_)));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+}
@@ -0,0 +1,179 @@
+//! Reading and writing images in the farbfeld format.
+
+use crate::{
+    bytes::{read_bytes, read_u32_be},
+    color::{u16_to_u8, u8_to_u16, Color, Rgba},
+    error::{checked_samples, Error, Result},
+    image::{Image, Image16, RgbaImage},
+};
+
+const MAGIC: &[u8] = b"farbfeld";
+const HEADER_SIZE: usize = 16;
+
+/// Trait for transforming any values into their farbfeld representation.
+pub trait Farbfeld {
+    /// Convert a value into its farbfeld representation.
+    fn to_farbfeld(&self) -> Vec<u8>;
+}
+
+/// Macro for saving an image in its farbfeld representation to the disk.
+#[macro_export]
+macro_rules! save_farbfeld {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::farbfeld::Farbfeld::to_farbfeld(&$img))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its farbfeld representation to the disk. Returns an error, if the
+/// file could not be written.
+#[macro_export]
+macro_rules! try_save_farbfeld {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::farbfeld::Farbfeld::to_farbfeld(&$img))
+    };
+}
+
+/// Create the header of a farbfeld file.
+pub(crate) fn farbfeld_header(width: usize, height: usize) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header
+}
+
+/// Append a row of pixels, given as 16 bit RGBA samples, to `out`.
+pub(crate) fn encode_farbfeld_row(row: impl IntoIterator<Item = [u16; 4]>, out: &mut Vec<u8>) {
+    for pixel in row {
+        for sample in pixel {
+            out.extend_from_slice(&sample.to_be_bytes());
+        }
+    }
+}
+
+/// Convert an RGB color into fully opaque 16 bit RGBA samples.
+pub(crate) fn color_samples(color: Color) -> [u16; 4] {
+    [color.r, color.g, color.b, u8::MAX].map(u8_to_u16)
+}
+
+/// Encode an image as farbfeld, where `pixel` provides the 16 bit RGBA samples of each pixel.
+fn encode_farbfeld(
+    width: usize,
+    height: usize,
+    pixel: impl Fn(usize, usize) -> [u16; 4],
+) -> Vec<u8> {
+    let mut ff = farbfeld_header(width, height);
+    for y in 0..height {
+        encode_farbfeld_row((0..width).map(|x| pixel(x, y)), &mut ff);
+    }
+    ff
+}
+
+impl Farbfeld for RgbaImage {
+    fn to_farbfeld(&self) -> Vec<u8> {
+        encode_farbfeld(self.cols(), self.rows(), |x, y| {
+            let color = self.get(x, y).unwrap_or_default();
+            [color.r, color.g, color.b, color.a].map(u8_to_u16)
+        })
+    }
+}
+
+impl Farbfeld for Image {
+    fn to_farbfeld(&self) -> Vec<u8> {
+        encode_farbfeld(self.cols(), self.rows(), |x, y| {
+            color_samples(self.get(x, y).unwrap_or_default())
+        })
+    }
+}
+
+impl Farbfeld for Image16 {
+    fn to_farbfeld(&self) -> Vec<u8> {
+        encode_farbfeld(self.cols(), self.rows(), |x, y| {
+            let color = self.get(x, y).unwrap_or_default();
+            [color.r, color.g, color.b, u16::MAX]
+        })
+    }
+}
+
+/// Parse a vector of u8 into an image in farbfeld format. The 16 bit samples are reduced to 8 bit.
+/// This function will panic, if the format is not valid. See `try_parse_farbfeld` for a fallible
+/// variant.
+pub fn parse_farbfeld(inp: Vec<u8>) -> RgbaImage {
+    try_parse_farbfeld(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into an image in farbfeld format. The 16 bit samples are reduced to
+/// 8 bit.
+pub fn try_parse_farbfeld(inp: Vec<u8>) -> Result<RgbaImage> {
+    if !inp.starts_with(MAGIC) {
+        return Err(Error::InvalidMagicConstant(
+            "No valid magic constant".to_string(),
+        ));
+    }
+
+    let width = read_u32_be(&inp, 8)? as usize;
+    let height = read_u32_be(&inp, 12)? as usize;
+    let data = read_bytes(&inp, HEADER_SIZE, checked_samples(width, height, 8)?)?;
+
+    let mut image = RgbaImage::new(width, height);
+    for (i, pixel) in data.chunks_exact(8).enumerate() {
+        let sample = |c: usize| u16_to_u8(u16::from_be_bytes([pixel[2 * c], pixel[2 * c + 1]]));
+        image.set(
+            i % width,
+            i / width,
+            Rgba::new(sample(0), sample(1), sample(2), sample(3)),
+        );
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color16, rgba};
+
+    #[test]
+    fn test_farbfeld_roundtrip() {
+        let mut image = RgbaImage::new(3, 2);
+        image.set(0, 0, rgba!(255, 0, 0, 255));
+        image.set(2, 1, rgba!(10, 20, 30, 40));
+
+        let ff = image.to_farbfeld();
+        assert_eq!(&ff[0..8], b"farbfeld");
+        assert_eq!(ff.len(), HEADER_SIZE + 3 * 2 * 8);
+
+        let parsed = parse_farbfeld(ff);
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(parsed.get(x, y), image.get(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_farbfeld_16bit() {
+        let mut image = Image16::new(1, 1);
+        image.set(0, 0, Color16::new(0x1234, 0, 0xffff));
+        let ff = image.to_farbfeld();
+        assert_eq!(&ff[16..], &[0x12, 0x34, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(
+            parse_farbfeld(ff).get(0, 0),
+            Some(rgba!(u16_to_u8(0x1234), 0, 255, 255))
+        );
+    }
+
+    #[test]
+    fn test_parse_farbfeld_errors() {
+        assert!(matches!(
+            try_parse_farbfeld(b"farbfelt".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+
+        let mut ff = Image::new(2, 2).to_farbfeld();
+        ff.pop();
+        assert!(matches!(
+            try_parse_farbfeld(ff),
+            Err(Error::TruncatedData { .. })
+        ));
+    }
+}
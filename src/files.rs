@@ -1,4 +1,5 @@
 /// Macro for loading a file from the disk.
+#[deprecated(note = "use Image::open or try_load_file! instead")]
 #[macro_export]
 macro_rules! load_file {
     ($file_name:expr) => {
@@ -7,6 +8,7 @@ macro_rules! load_file {
 }
 
 /// Macro for writing a file to the disk.
+#[deprecated(note = "use Image::save or try_write_file! instead")]
 #[macro_export]
 macro_rules! write_file {
     ($file_name:expr, $content:expr) => {
@@ -16,29 +18,84 @@ macro_rules! write_file {
     };
 }
 
+/// Macro for loading a file from the disk. Returns an error, if the file could not be read.
+#[macro_export]
+macro_rules! try_load_file {
+    ($file_name:expr) => {
+        std::fs::read($file_name).map_err($crate::error::Error::from)
+    };
+}
+
+/// Macro for writing a file to the disk. Returns an error, if the file could not be written.
+#[macro_export]
+macro_rules! try_write_file {
+    ($file_name:expr, $content:expr) => {
+        std::fs::write($file_name, $content).map_err($crate::error::Error::from)
+    };
+}
+
 /// Enum representing all supported files types of this library.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum FileTypes {
+    PBM1,
+    PGM2,
     PPM3,
+    PBM4,
+    PGM5,
     PPM6,
+    PAM7,
+    BMP,
+    PNG,
+    QOI,
+    JPEG,
+    GIF,
+    TIFF,
+    FARBFELD,
+    HDR,
+    TGA,
 }
 
 /// Error when an unsupported image is provided.
 #[derive(Debug)]
 pub struct InvalidMagicConstantError(String);
 
+impl std::fmt::Display for InvalidMagicConstantError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl TryFrom<&[u8]> for FileTypes {
     type Error = InvalidMagicConstantError;
 
-    fn try_from(magic_constant: &[u8]) -> Result<Self, Self::Error> {
+    /// Determine the file type from the magic constant at the head of the given data. The data
+    /// may contain more than just the magic constant (e.g., the whole file).
+    fn try_from(inp: &[u8]) -> Result<Self, Self::Error> {
         use FileTypes::*;
 
-        match magic_constant {
-            b"P3" => Ok(PPM3),
-            b"P6" => Ok(PPM6),
+        match inp {
+            [b'P', b'1', ..] => Ok(PBM1),
+            [b'P', b'2', ..] => Ok(PGM2),
+            [b'P', b'3', ..] => Ok(PPM3),
+            [b'P', b'4', ..] => Ok(PBM4),
+            [b'P', b'5', ..] => Ok(PGM5),
+            [b'P', b'6', ..] => Ok(PPM6),
+            [b'P', b'7', ..] => Ok(PAM7),
+            [b'B', b'M', ..] => Ok(BMP),
+            [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', ..] => Ok(PNG),
+            [b'q', b'o', b'i', b'f', ..] => Ok(QOI),
+            [0xff, 0xd8, 0xff, ..] => Ok(JPEG),
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Ok(GIF),
+            [b'I', b'I', b'*', 0, ..] | [b'M', b'M', 0, b'*', ..] => Ok(TIFF),
+            [b'f', b'a', b'r', b'b', b'f', b'e', b'l', b'd', ..] => Ok(FARBFELD),
+            [b'#', b'?', b'R', b'A', b'D', b'I', b'A', b'N', b'C', b'E', ..]
+            | [b'#', b'?', b'R', b'G', b'B', b'E', ..] => Ok(HDR),
+            // TGA has no magic constant and is therefore checked last
+            x if crate::tga::is_tga(x) => Ok(TGA),
             x => Err(InvalidMagicConstantError(format!(
                 "Magic constant '{:?}' currently not supported",
-                x
+                &x[..x.len().min(8)]
             ))),
         }
     }
@@ -65,4 +122,126 @@ mod tests {
             FileTypes::PPM6
         );
     }
+
+    #[test]
+    fn test_pbm_pgm() {
+        assert_eq!(
+            FileTypes::try_from("P1".as_bytes()).unwrap(),
+            FileTypes::PBM1
+        );
+        assert_eq!(
+            FileTypes::try_from("P2".as_bytes()).unwrap(),
+            FileTypes::PGM2
+        );
+        assert_eq!(
+            FileTypes::try_from("P4".as_bytes()).unwrap(),
+            FileTypes::PBM4
+        );
+        assert_eq!(
+            FileTypes::try_from("P5".as_bytes()).unwrap(),
+            FileTypes::PGM5
+        );
+    }
+
+    #[test]
+    fn test_bmp() {
+        assert_eq!(
+            FileTypes::try_from("BM".as_bytes()).unwrap(),
+            FileTypes::BMP
+        );
+    }
+
+    #[test]
+    fn test_png() {
+        assert_eq!(
+            FileTypes::try_from(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".as_slice()).unwrap(),
+            FileTypes::PNG
+        );
+        assert!(FileTypes::try_from(b"\x89PNG".as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_qoi() {
+        assert_eq!(
+            FileTypes::try_from("qoif".as_bytes()).unwrap(),
+            FileTypes::QOI
+        );
+    }
+
+    #[test]
+    fn test_jpeg() {
+        assert_eq!(
+            FileTypes::try_from(b"\xff\xd8\xff\xe0".as_slice()).unwrap(),
+            FileTypes::JPEG
+        );
+    }
+
+    #[test]
+    fn test_gif() {
+        assert_eq!(
+            FileTypes::try_from("GIF89a".as_bytes()).unwrap(),
+            FileTypes::GIF
+        );
+        assert_eq!(
+            FileTypes::try_from("GIF87a".as_bytes()).unwrap(),
+            FileTypes::GIF
+        );
+        assert!(FileTypes::try_from("GIF88a".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_tiff() {
+        assert_eq!(
+            FileTypes::try_from(b"II*\0".as_slice()).unwrap(),
+            FileTypes::TIFF
+        );
+        assert_eq!(
+            FileTypes::try_from(b"MM\0*".as_slice()).unwrap(),
+            FileTypes::TIFF
+        );
+        assert!(FileTypes::try_from(b"MM*\0".as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_farbfeld() {
+        assert_eq!(
+            FileTypes::try_from("farbfeld".as_bytes()).unwrap(),
+            FileTypes::FARBFELD
+        );
+    }
+
+    #[test]
+    fn test_hdr() {
+        assert_eq!(
+            FileTypes::try_from("#?RADIANCE\n".as_bytes()).unwrap(),
+            FileTypes::HDR
+        );
+        assert_eq!(
+            FileTypes::try_from("#?RGBE\n".as_bytes()).unwrap(),
+            FileTypes::HDR
+        );
+    }
+
+    #[test]
+    fn test_tga() {
+        let header = [0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 24, 0];
+        assert_eq!(
+            FileTypes::try_from(header.as_slice()).unwrap(),
+            FileTypes::TGA
+        );
+        assert_eq!(
+            FileTypes::try_from(b"\0\0\0\0\0\0\0\0TRUEVISION-XFILE.\0".as_slice()).unwrap(),
+            FileTypes::TGA
+        );
+        assert!(FileTypes::try_from([0, 0, 4].as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_whole_file() {
+        assert_eq!(
+            FileTypes::try_from("P6\n1 1\n255\n".as_bytes()).unwrap(),
+            FileTypes::PPM6
+        );
+        assert!(FileTypes::try_from("P".as_bytes()).is_err());
+    }
 }
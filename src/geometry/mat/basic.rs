@@ -85,6 +85,35 @@ where
     }
 }
 
+impl Mat<f64, 3, 3> {
+    /// Calculate the determinant of this matrix.
+    pub fn determinant(&self) -> f64 {
+        let m = &self.matrix;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Calculate the inverse of this matrix, or None, if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let m = &self.matrix;
+        // transposed matrix of cofactors divided by the determinant
+        let mut inverse = Self::new();
+        for y in 0..3 {
+            for x in 0..3 {
+                let (r1, r2) = ((x + 1) % 3, (x + 2) % 3);
+                let (c1, c2) = ((y + 1) % 3, (y + 2) % 3);
+                inverse.matrix[y][x] = (m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]) / det;
+            }
+        }
+        Some(inverse)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,4 +181,21 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn test_mat_inverse() {
+        let a = mat!([[2.0, 0.0, 3.0], [1.0, 1.0, -1.0], [0.0, 4.0, 1.0]]);
+        assert_eq!(a.determinant(), 22.0);
+
+        let product = a * a.inverse().unwrap();
+        for y in 0..3 {
+            for x in 0..3 {
+                let expected = if x == y { 1.0 } else { 0.0 };
+                assert!((product.matrix[y][x] - expected).abs() < 1e-12);
+            }
+        }
+
+        let singular = mat!([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 0.0, 1.0]]);
+        assert_eq!(singular.inverse(), None);
+    }
 }
@@ -0,0 +1,995 @@
+//! Reading and writing (animated) images in the GIF format.
+
+use std::collections::HashMap;
+
+use crate::{
+    bytes::{read_bytes, read_u16_le, read_u8},
+    color::Color,
+    error::{checked_samples, Error, Result},
+    image::Image,
+};
+
+const EXTENSION_INTRODUCER: u8 = 0x21;
+const IMAGE_SEPARATOR: u8 = 0x2c;
+const TRAILER: u8 = 0x3b;
+const GRAPHIC_CONTROL_LABEL: u8 = 0xf9;
+const APPLICATION_LABEL: u8 = 0xff;
+
+/// Largest number of codes of the LZW compression.
+const MAX_CODES: usize = 4096;
+const MAX_CODE_SIZE: u32 = 12;
+/// Upper limit for the number of pixels of all decoded frames together, since every frame holds a
+/// copy of the canvas.
+const MAX_PIXELS: usize = 1 << 30;
+
+/// Single frame of an (animated) GIF.
+#[derive(Clone)]
+pub struct Frame {
+    pub image: Image,
+    /// Time to display this frame in hundredths of a second.
+    pub delay: u16,
+}
+
+impl Frame {
+    pub fn new(image: Image, delay: u16) -> Self {
+        Self { image, delay }
+    }
+}
+
+/// Where the palettes of the frames are stored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PaletteMode {
+    /// One palette, which is built from the colors of all frames.
+    Global,
+    /// One palette per frame, which is built from the colors of this frame.
+    Local,
+}
+
+/// Options for writing GIF files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GifOptions {
+    pub palette: PaletteMode,
+    /// Number of times the animation is repeated, where 0 repeats forever. If this is `None`, the
+    /// animation is only played once.
+    pub repeat: Option<u16>,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            palette: PaletteMode::Global,
+            repeat: Some(0),
+        }
+    }
+}
+
+/// Trait for transforming any values into their GIF representation.
+pub trait GIF {
+    /// Convert a value into its GIF representation. Images with more than 256 colors are
+    /// quantized.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the width or height of a frame exceeds 65535 pixels. See `try_to_gif` for a
+    /// fallible variant.
+    fn to_gif(&self, options: &GifOptions) -> Vec<u8>;
+
+    /// Try to convert a value into its GIF representation. Returns an error, if the width or
+    /// height of a frame exceeds 65535 pixels. The default implementation calls `to_gif`.
+    fn try_to_gif(&self, options: &GifOptions) -> Result<Vec<u8>> {
+        Ok(self.to_gif(options))
+    }
+}
+
+/// Macro for saving an image or a sequence of frames in its GIF representation to the disk.
+#[macro_export]
+macro_rules! save_gif {
+    ($file_name:expr, $img:expr, $options:expr) => {
+        $crate::try_write_file!($file_name, &$crate::gif::GIF::to_gif(&$img, &$options))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image or a sequence of frames in its GIF representation to the disk.
+/// Returns an error, if the file could not be written.
+#[macro_export]
+macro_rules! try_save_gif {
+    ($file_name:expr, $img:expr, $options:expr) => {
+        $crate::gif::GIF::try_to_gif(&$img, &$options)
+            .and_then(|gif| $crate::try_write_file!($file_name, &gif))
+    };
+}
+
+/// Count the occurrences of all colors of an image.
+pub(crate) fn add_to_histogram(histogram: &mut HashMap<Color, u32>, image: &Image) {
+    for y in 0..image.rows() {
+        for x in 0..image.cols() {
+            *histogram
+                .entry(image.get(x, y).unwrap_or_default())
+                .or_default() += 1;
+        }
+    }
+}
+
+/// Get the value of a channel (0 = red, 1 = green, 2 = blue) of a color.
+fn channel(color: &Color, channel: usize) -> u8 {
+    match channel {
+        0 => color.r,
+        1 => color.g,
+        _ => color.b,
+    }
+}
+
+/// Get the channel with the largest range of values in a set of colors together with the range.
+fn widest_channel(colors: &[(Color, u32)]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let min = colors.iter().map(|(color, _)| channel(color, c)).min();
+            let max = colors.iter().map(|(color, _)| channel(color, c)).max();
+            (c, max.unwrap_or(0) - min.unwrap_or(0))
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+/// Build a palette of at most `max_colors` colors with the median cut algorithm. If the histogram
+/// contains fewer colors, all of them are used as they are.
+pub(crate) fn build_palette(histogram: &HashMap<Color, u32>, max_colors: usize) -> Vec<Color> {
+    let mut colors: Vec<(Color, u32)> = histogram.iter().map(|(c, n)| (*c, *n)).collect();
+    colors.sort_by_key(|(color, _)| (color.r, color.g, color.b));
+    if colors.len() <= max_colors {
+        return colors.into_iter().map(|(color, _)| color).collect();
+    }
+
+    let mut boxes = vec![colors];
+    while boxes.len() < max_colors {
+        // split the box with the widest range along this range
+        let Some((index, (c, _))) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| (i, widest_channel(colors)))
+            .max_by_key(|(_, (_, range))| *range)
+        else {
+            break;
+        };
+
+        let mut first = boxes.swap_remove(index);
+        first.sort_by_key(|(color, _)| channel(color, c));
+
+        // split at the median of the pixels (not of the distinct colors)
+        let total: u64 = first.iter().map(|(_, n)| *n as u64).sum();
+        let mut count = 0;
+        let split = first
+            .iter()
+            .position(|(_, n)| {
+                count += *n as u64;
+                count * 2 >= total
+            })
+            .map_or(1, |i| i + 1)
+            .clamp(1, first.len() - 1);
+        let second = first.split_off(split);
+        boxes.push(first);
+        boxes.push(second);
+    }
+
+    boxes
+        .iter()
+        .map(|colors| {
+            let total: u64 = colors.iter().map(|(_, n)| *n as u64).sum();
+            let mean = |c: usize| {
+                let sum: u64 = colors
+                    .iter()
+                    .map(|(color, n)| channel(color, c) as u64 * *n as u64)
+                    .sum();
+                ((sum + total / 2) / total) as u8
+            };
+            Color::new(mean(0), mean(1), mean(2))
+        })
+        .collect()
+}
+
+/// Get the index of the palette color closest to the given color.
+fn nearest_index(palette: &[Color], color: &Color) -> u8 {
+    let distance = |other: &Color| {
+        let dr = color.r as i32 - other.r as i32;
+        let dg = color.g as i32 - other.g as i32;
+        let db = color.b as i32 - other.b as i32;
+        dr * dr + dg * dg + db * db
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, other)| distance(other))
+        .map_or(0, |(i, _)| i as u8)
+}
+
+/// Map all pixels of an image to the indices of their closest palette colors.
+pub(crate) fn map_to_palette(image: &Image, palette: &[Color]) -> Vec<u8> {
+    let mut cache = HashMap::new();
+    let mut indices = Vec::with_capacity(image.cols() * image.rows());
+    for y in 0..image.rows() {
+        for x in 0..image.cols() {
+            let color = image.get(x, y).unwrap_or_default();
+            indices.push(
+                *cache
+                    .entry(color)
+                    .or_insert_with(|| nearest_index(palette, &color)),
+            );
+        }
+    }
+    indices
+}
+
+/// Get the exponent of the color table size (i.e., the table contains `2^(size + 1)` colors).
+fn color_table_size(colors: usize) -> u8 {
+    let mut size = 0;
+    while (2 << size) < colors {
+        size += 1;
+    }
+    size
+}
+
+/// Append a color table, which is padded to its full size.
+fn write_color_table(gif: &mut Vec<u8>, palette: &[Color]) {
+    let len = 2 << color_table_size(palette.len());
+    for i in 0..len {
+        let color = palette.get(i).copied().unwrap_or_default();
+        gif.extend_from_slice(&[color.r, color.g, color.b]);
+    }
+}
+
+/// Append data as a sequence of sub-blocks followed by a block terminator.
+fn write_sub_blocks(gif: &mut Vec<u8>, data: &[u8]) {
+    for chunk in data.chunks(255) {
+        gif.push(chunk.len() as u8);
+        gif.extend_from_slice(chunk);
+    }
+    gif.push(0);
+}
+
+/// Writer for the LSB-first bit stream of the LZW compression.
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.bits |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// Compress color indices with the variable-length LZW compression of GIF.
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let initial_size = min_code_size as u32 + 1;
+
+    let mut writer = BitWriter {
+        out: vec![],
+        bits: 0,
+        count: 0,
+    };
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = initial_size;
+    let mut prefix: Option<u16> = None;
+
+    writer.write(clear, size);
+    for index in indices {
+        let Some(code) = prefix else {
+            prefix = Some(*index as u16);
+            continue;
+        };
+        if let Some(extended) = dictionary.get(&(code, *index)) {
+            prefix = Some(*extended);
+            continue;
+        }
+
+        writer.write(code, size);
+        // the decoder adds its entries one code later, so the code size is increased before
+        // adding the new entry
+        if next == 1 << size && size < MAX_CODE_SIZE {
+            size += 1;
+        }
+        dictionary.insert((code, *index), next);
+        next += 1;
+        if next as usize == MAX_CODES {
+            writer.write(clear, size);
+            dictionary.clear();
+            next = end + 1;
+            size = initial_size;
+        }
+        prefix = Some(*index as u16);
+    }
+
+    if let Some(code) = prefix {
+        writer.write(code, size);
+        if next == 1 << size && size < MAX_CODE_SIZE {
+            size += 1;
+        }
+    }
+    writer.write(end, size);
+    writer.finish()
+}
+
+/// Encode frames as GIF, where every frame is placed at the top left corner of the canvas.
+fn encode_gif(frames: &[Frame], options: &GifOptions) -> Result<Vec<u8>> {
+    let width = frames.iter().map(|f| f.image.cols()).max().unwrap_or(0);
+    let height = frames.iter().map(|f| f.image.rows()).max().unwrap_or(0);
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(Error::DimensionOverflow { width, height });
+    }
+
+    let global_palette = match options.palette {
+        PaletteMode::Global => {
+            let mut histogram = HashMap::new();
+            for frame in frames {
+                add_to_histogram(&mut histogram, &frame.image);
+            }
+            Some(build_palette(&histogram, 256))
+        }
+        PaletteMode::Local => None,
+    };
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&(width as u16).to_le_bytes());
+    gif.extend_from_slice(&(height as u16).to_le_bytes());
+    match &global_palette {
+        Some(palette) => {
+            // global color table flag, 8bit color resolution and the table size
+            gif.extend_from_slice(&[0xf0 | color_table_size(palette.len()), 0, 0]);
+            write_color_table(&mut gif, palette);
+        }
+        None => gif.extend_from_slice(&[0x70, 0, 0]),
+    }
+
+    if let Some(repeat) = options.repeat {
+        gif.extend_from_slice(&[EXTENSION_INTRODUCER, APPLICATION_LABEL, 11]);
+        gif.extend_from_slice(b"NETSCAPE2.0");
+        gif.extend_from_slice(&[3, 1]);
+        gif.extend_from_slice(&repeat.to_le_bytes());
+        gif.push(0);
+    }
+
+    for frame in frames {
+        // graphic control extension without disposal and transparency
+        gif.extend_from_slice(&[EXTENSION_INTRODUCER, GRAPHIC_CONTROL_LABEL, 4, 0]);
+        gif.extend_from_slice(&frame.delay.to_le_bytes());
+        gif.extend_from_slice(&[0, 0]);
+
+        gif.push(IMAGE_SEPARATOR);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&(frame.image.cols() as u16).to_le_bytes());
+        gif.extend_from_slice(&(frame.image.rows() as u16).to_le_bytes());
+
+        let palette = match &global_palette {
+            Some(palette) => {
+                gif.push(0);
+                palette.clone()
+            }
+            None => {
+                let mut histogram = HashMap::new();
+                add_to_histogram(&mut histogram, &frame.image);
+                let palette = build_palette(&histogram, 256);
+                // local color table flag and the table size
+                gif.push(0x80 | color_table_size(palette.len()));
+                write_color_table(&mut gif, &palette);
+                palette
+            }
+        };
+
+        let min_code_size = (color_table_size(palette.len()) + 1).max(2);
+        gif.push(min_code_size);
+        let indices = map_to_palette(&frame.image, &palette);
+        write_sub_blocks(&mut gif, &lzw_encode(&indices, min_code_size));
+    }
+
+    gif.push(TRAILER);
+    Ok(gif)
+}
+
+impl GIF for [Frame] {
+    fn to_gif(&self, options: &GifOptions) -> Vec<u8> {
+        self.try_to_gif(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_gif(&self, options: &GifOptions) -> Result<Vec<u8>> {
+        encode_gif(self, options)
+    }
+}
+
+impl GIF for Vec<Frame> {
+    fn to_gif(&self, options: &GifOptions) -> Vec<u8> {
+        self.as_slice().to_gif(options)
+    }
+
+    fn try_to_gif(&self, options: &GifOptions) -> Result<Vec<u8>> {
+        self.as_slice().try_to_gif(options)
+    }
+}
+
+impl GIF for Image {
+    fn to_gif(&self, options: &GifOptions) -> Vec<u8> {
+        self.try_to_gif(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_gif(&self, options: &GifOptions) -> Result<Vec<u8>> {
+        encode_gif(&[Frame::new(self.clone(), 0)], options)
+    }
+}
+
+/// Read a sequence of sub-blocks starting at the given offset. Returns the concatenated data and
+/// the offset after the block terminator.
+fn read_sub_blocks(inp: &[u8], mut pos: usize) -> Result<(Vec<u8>, usize)> {
+    let mut data = vec![];
+    loop {
+        let len = read_u8(inp, pos)? as usize;
+        pos += 1;
+        if len == 0 {
+            return Ok((data, pos));
+        }
+        data.extend_from_slice(read_bytes(inp, pos, len)?);
+        pos += len;
+    }
+}
+
+/// Read a color table with `2^(size + 1)` colors.
+fn read_color_table(inp: &[u8], pos: usize, size: u8) -> Result<Vec<Color>> {
+    let len = 2usize << size;
+    Ok(read_bytes(inp, pos, len * 3)?
+        .chunks_exact(3)
+        .map(Color::from_u8_array)
+        .collect())
+}
+
+/// Decompress LZW compressed color indices. Decoding stops after `expected` indices.
+fn lzw_decode(data: &[u8], min_code_size: u8, expected: usize) -> Result<Vec<u8>> {
+    if !(2..=8).contains(&min_code_size) {
+        return Err(Error::InvalidData(format!(
+            "Invalid LZW code size ({})",
+            min_code_size
+        )));
+    }
+    let clear = 1usize << min_code_size;
+    let end = clear + 1;
+    let initial_size = min_code_size as u32 + 1;
+
+    // every entry is a sequence in the output, given by its start and length
+    let mut entries: Vec<(usize, usize)> = Vec::with_capacity(MAX_CODES);
+    let reset = |entries: &mut Vec<(usize, usize)>| {
+        entries.clear();
+        entries.extend((0..clear + 2).map(|_| (0, 0)));
+    };
+    reset(&mut entries);
+
+    // the output grows with the decoded codes, since `expected` is taken from the untrusted header
+    let mut out: Vec<u8> = vec![];
+    let mut size = initial_size;
+    let mut previous: Option<(usize, usize)> = None;
+    let (mut bits, mut count, mut pos) = (0u32, 0u32, 0usize);
+
+    while out.len() < expected {
+        while count < size {
+            let Some(byte) = data.get(pos) else {
+                return Err(Error::TruncatedData {
+                    expected: data.len() + 1,
+                    actual: data.len(),
+                });
+            };
+            bits |= (*byte as u32) << count;
+            count += 8;
+            pos += 1;
+        }
+        let code = (bits & ((1 << size) - 1)) as usize;
+        bits >>= size;
+        count -= size;
+
+        if code == clear {
+            reset(&mut entries);
+            size = initial_size;
+            previous = None;
+            continue;
+        }
+        if code == end {
+            break;
+        }
+
+        let start = out.len();
+        if code < clear {
+            out.push(code as u8);
+        } else if code < entries.len() && code > end {
+            let (entry_start, len) = entries[code];
+            out.extend_from_within(entry_start..entry_start + len);
+        } else if code == entries.len() && previous.is_some() {
+            // the code is defined by this very step: previous sequence + its first index
+            let (previous_start, len) = previous.unwrap_or_default();
+            out.extend_from_within(previous_start..previous_start + len);
+            out.push(out[previous_start]);
+        } else {
+            return Err(Error::InvalidData(format!("Invalid LZW code ({})", code)));
+        }
+
+        if let Some((previous_start, len)) = previous {
+            if entries.len() < MAX_CODES {
+                // the previous sequence directly precedes the first index of the current one
+                entries.push((previous_start, len + 1));
+                if entries.len() == 1 << size && size < MAX_CODE_SIZE {
+                    size += 1;
+                }
+            }
+        }
+        previous = Some((start, out.len() - start));
+    }
+
+    if out.len() < expected {
+        return Err(Error::TruncatedData {
+            expected,
+            actual: out.len(),
+        });
+    }
+    out.truncate(expected);
+    Ok(out)
+}
+
+/// Frame, which has been decoded, but not yet composed onto the canvas.
+struct DecodedFrame {
+    left: usize,
+    top: usize,
+    width: usize,
+    /// Canvas rows (relative to `top`) in the order of the rows of `indices`.
+    rows: Vec<usize>,
+    palette: Vec<Color>,
+    indices: Vec<u8>,
+    /// Delay, disposal method and transparent index.
+    control: (u16, u8, Option<u8>),
+}
+
+/// Order of the rows of an interlaced image.
+fn interlaced_rows(height: usize) -> Vec<usize> {
+    [(0, 8), (4, 8), (2, 4), (1, 2)]
+        .into_iter()
+        .flat_map(|(start, step)| (start..height).step_by(step))
+        .collect()
+}
+
+/// Parse a vector of u8 into the frames of a GIF. Every frame is composed onto the canvas of the
+/// previous frames. This function will panic, if the format is not valid. See `try_parse_gif` for
+/// a fallible variant.
+pub fn parse_gif(inp: Vec<u8>) -> Vec<Frame> {
+    try_parse_gif(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into the frames of a GIF.
+pub fn try_parse_gif(inp: Vec<u8>) -> Result<Vec<Frame>> {
+    if !matches!(inp.get(0..6), Some(b"GIF87a" | b"GIF89a")) {
+        return Err(Error::InvalidMagicConstant(
+            "No valid magic constant".to_string(),
+        ));
+    }
+
+    let width = read_u16_le(&inp, 6)? as usize;
+    let height = read_u16_le(&inp, 8)? as usize;
+    let flags = read_u8(&inp, 10)?;
+    let background = read_u8(&inp, 11)? as usize;
+    let mut pos = 13;
+
+    let mut global_palette = vec![];
+    if flags & 0x80 != 0 {
+        global_palette = read_color_table(&inp, pos, flags & 0x07)?;
+        pos += global_palette.len() * 3;
+    }
+    let background = global_palette.get(background).copied().unwrap_or_default();
+
+    // all frames are decoded before the canvas is allocated, so that the size of all composed
+    // frames can be checked first
+    let mut decoded = vec![];
+    // delay, disposal method and transparent index of the next frame
+    let mut control = (0u16, 0u8, None::<u8>);
+
+    loop {
+        // some encoders omit the trailer, so the end of the input after a block ends the stream
+        if pos == inp.len() {
+            break;
+        }
+        match read_u8(&inp, pos)? {
+            EXTENSION_INTRODUCER => {
+                let label = read_u8(&inp, pos + 1)?;
+                let (data, next) = read_sub_blocks(&inp, pos + 2)?;
+                if label == GRAPHIC_CONTROL_LABEL && data.len() >= 4 {
+                    let transparent = (data[0] & 0x01 != 0).then_some(data[3]);
+                    control = (
+                        u16::from_le_bytes([data[1], data[2]]),
+                        (data[0] >> 2) & 0x07,
+                        transparent,
+                    );
+                }
+                pos = next;
+            }
+            IMAGE_SEPARATOR => {
+                let left = read_u16_le(&inp, pos + 1)? as usize;
+                let top = read_u16_le(&inp, pos + 3)? as usize;
+                let frame_width = read_u16_le(&inp, pos + 5)? as usize;
+                let frame_height = read_u16_le(&inp, pos + 7)? as usize;
+                let flags = read_u8(&inp, pos + 9)?;
+                pos += 10;
+
+                let palette = if flags & 0x80 != 0 {
+                    let local_palette = read_color_table(&inp, pos, flags & 0x07)?;
+                    pos += local_palette.len() * 3;
+                    local_palette
+                } else {
+                    global_palette.clone()
+                };
+                if palette.is_empty() {
+                    return Err(Error::InvalidData("Missing color table".to_string()));
+                }
+
+                let min_code_size = read_u8(&inp, pos)?;
+                let (data, next) = read_sub_blocks(&inp, pos + 1)?;
+                pos = next;
+                let expected = checked_samples(frame_width, frame_height, 1)?;
+                let indices = lzw_decode(&data, min_code_size, expected)?;
+                for index in &indices {
+                    if Some(*index) != control.2 && *index as usize >= palette.len() {
+                        return Err(Error::InvalidData(format!(
+                            "Color index {} out of bounds",
+                            index
+                        )));
+                    }
+                }
+
+                let rows: Vec<usize> = if flags & 0x40 != 0 {
+                    interlaced_rows(frame_height)
+                } else {
+                    (0..frame_height).collect()
+                };
+                decoded.push(DecodedFrame {
+                    left,
+                    top,
+                    width: frame_width,
+                    rows,
+                    palette,
+                    indices,
+                    control,
+                });
+                control = (0, 0, None);
+            }
+            TRAILER => break,
+            block => {
+                return Err(Error::InvalidData(format!(
+                    "Unknown block type ({:#04x})",
+                    block
+                )));
+            }
+        }
+    }
+
+    if checked_samples(width, height, decoded.len())? > MAX_PIXELS {
+        return Err(Error::DimensionOverflow { width, height });
+    }
+
+    let mut canvas = Image::new(width, height);
+    canvas.fill_with(background);
+    let mut frames = vec![];
+    for frame in decoded {
+        let (delay, disposal, transparent) = frame.control;
+        let previous = canvas.clone();
+
+        for (i, y) in frame.rows.iter().enumerate() {
+            for x in 0..frame.width {
+                let index = frame.indices[i * frame.width + x];
+                if Some(index) == transparent {
+                    continue;
+                }
+                canvas.set(frame.left + x, frame.top + y, frame.palette[index as usize]);
+            }
+        }
+        frames.push(Frame::new(canvas.clone(), delay));
+
+        match disposal {
+            // restore the area of the frame to the background
+            2 => {
+                for y in frame.top..frame.top + frame.rows.len() {
+                    for x in frame.left..frame.left + frame.width {
+                        canvas.set(x, y, background);
+                    }
+                }
+            }
+            // restore the canvas to the state before the frame
+            3 => canvas = previous,
+            _ => {}
+        }
+    }
+
+    if frames.is_empty() {
+        return Err(Error::InvalidData("No image data".to_string()));
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgb;
+
+    fn gradient(cols: usize, rows: usize, offset: usize) -> Image {
+        let mut image = Image::new(cols, rows);
+        for y in 0..rows {
+            for x in 0..cols {
+                image.set(
+                    x,
+                    y,
+                    rgb!((x * 40) as u8, (y * 40) as u8, (offset * 50) as u8),
+                );
+            }
+        }
+        image
+    }
+
+    fn assert_images_eq(a: &Image, b: &Image) {
+        assert_eq!((a.cols(), a.rows()), (b.cols(), b.rows()));
+        for y in 0..a.rows() {
+            for x in 0..a.cols() {
+                assert_eq!(a.get(x, y), b.get(x, y), "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lzw_roundtrip() {
+        let mut seed = 7u32;
+        let random: Vec<u8> = (0..20_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8 & 0x0f
+            })
+            .collect();
+        let repetitive: Vec<u8> = (0..20_000).map(|i| (i % 3) as u8).collect();
+
+        for (data, min_code_size) in [
+            (vec![0u8], 2),
+            (vec![1, 1, 1, 1, 1, 1, 1], 2),
+            (random, 4),
+            (repetitive, 2),
+        ] {
+            let compressed = lzw_encode(&data, min_code_size);
+            assert_eq!(
+                lzw_decode(&compressed, min_code_size, data.len()).unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn test_lzw_decode_known() {
+        // 10x10 sample image of the well-known GIF walkthrough with 4 colors
+        let data = [
+            0x8c, 0x2d, 0x99, 0x87, 0x2a, 0x1c, 0xdc, 0x33, 0xa0, 0x02, 0x75, 0xec, 0x95, 0xfa,
+            0xa8, 0xde, 0x60, 0x8c, 0x04, 0x91, 0x4c, 0x01,
+        ];
+        let mut expected = vec![];
+        for y in 0..10 {
+            for x in 0..10 {
+                expected.push(match (x, y) {
+                    (3..=6, 3..=6) => 0,
+                    _ if (x < 5) == (y < 5) => 1,
+                    _ => 2,
+                });
+            }
+        }
+        assert_eq!(lzw_decode(&data, 2, 100).unwrap(), expected);
+        assert_eq!(lzw_encode(&expected, 2).len(), data.len());
+    }
+
+    #[test]
+    fn test_gif_animation_roundtrip() {
+        let frames: Vec<Frame> = (0..3)
+            .map(|i| Frame::new(gradient(6, 5, i), 10 * (i as u16 + 1)))
+            .collect();
+
+        for palette in [PaletteMode::Global, PaletteMode::Local] {
+            let gif = frames.to_gif(&GifOptions {
+                palette,
+                repeat: Some(0),
+            });
+            assert_eq!(&gif[0..6], b"GIF89a");
+            assert!(gif.windows(11).any(|w| w == b"NETSCAPE2.0"));
+
+            let parsed = parse_gif(gif);
+            assert_eq!(parsed.len(), 3);
+            for (frame, original) in parsed.iter().zip(&frames) {
+                assert_eq!(frame.delay, original.delay);
+                assert_images_eq(&frame.image, &original.image);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gif_without_loop() {
+        let image = gradient(3, 3, 0);
+        let gif = image.to_gif(&GifOptions {
+            repeat: None,
+            ..Default::default()
+        });
+        assert!(!gif.windows(11).any(|w| w == b"NETSCAPE2.0"));
+        assert_images_eq(&parse_gif(gif)[0].image, &image);
+    }
+
+    #[test]
+    fn test_gif_quantization() {
+        let image = crate::ppm::parse_ppm6(std::fs::read("assets/mona_lisa_small.ppm").unwrap());
+        let mut crop = Image::new(64, 64);
+        for y in 0..64 {
+            for x in 0..64 {
+                crop.set(x, y, image.get(x + 100, y + 100).unwrap());
+            }
+        }
+
+        let parsed = &parse_gif(crop.to_gif(&GifOptions::default()))[0].image;
+        let mut error = 0u64;
+        for y in 0..64 {
+            for x in 0..64 {
+                let (a, b) = (crop.get(x, y).unwrap(), parsed.get(x, y).unwrap());
+                error += (a.r.abs_diff(b.r) + a.g.abs_diff(b.g) + a.b.abs_diff(b.b)) as u64;
+            }
+        }
+        assert!(error / (64 * 64 * 3) < 4);
+    }
+
+    #[test]
+    fn test_build_palette() {
+        let mut histogram = HashMap::new();
+        for i in 0..=255u8 {
+            histogram.insert(rgb!(i, 0, 0), 1);
+            histogram.insert(rgb!(0, i, 0), 1);
+        }
+        let palette = build_palette(&histogram, 16);
+        assert_eq!(palette.len(), 16);
+        assert_eq!(build_palette(&histogram, 1000).len(), 511);
+    }
+
+    #[test]
+    fn test_parse_gif_transparency_and_disposal() {
+        // 2x1 canvas with a red background, the first frame paints the second pixel blue and is
+        // restored to the background, the second frame is transparent at the first pixel
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&[2, 0, 1, 0, 0x80, 0, 0]);
+        gif.extend_from_slice(&[255, 0, 0, 0, 0, 255]);
+
+        gif.extend_from_slice(&[0x21, 0xf9, 4, 2 << 2, 5, 0, 0, 0]);
+        gif.extend_from_slice(&[0x2c, 1, 0, 0, 0, 1, 0, 1, 0, 0, 2]);
+        let data = lzw_encode(&[1], 2);
+        write_sub_blocks(&mut gif, &data);
+
+        gif.extend_from_slice(&[0x21, 0xf9, 4, 0x01, 7, 0, 0, 0]);
+        gif.extend_from_slice(&[0x2c, 0, 0, 0, 0, 2, 0, 1, 0, 0, 2]);
+        let data = lzw_encode(&[0, 1], 2);
+        write_sub_blocks(&mut gif, &data);
+        gif.push(TRAILER);
+
+        let frames = parse_gif(gif);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].delay, 5);
+        assert_eq!(frames[0].image.get(0, 0), Some(rgb!(255, 0, 0)));
+        assert_eq!(frames[0].image.get(1, 0), Some(rgb!(0, 0, 255)));
+        assert_eq!(frames[1].delay, 7);
+        assert_eq!(frames[1].image.get(0, 0), Some(rgb!(255, 0, 0)));
+        assert_eq!(frames[1].image.get(1, 0), Some(rgb!(0, 0, 255)));
+    }
+
+    #[test]
+    fn test_parse_gif_interlaced() {
+        let mut gif = b"GIF87a".to_vec();
+        gif.extend_from_slice(&[1, 0, 5, 0, 0x80, 0, 0]);
+        gif.extend_from_slice(&[0, 0, 0, 255, 255, 255]);
+        gif.extend_from_slice(&[0x2c, 0, 0, 0, 0, 1, 0, 5, 0, 0x40, 2]);
+        // rows in the order 0, 4, 2, 1, 3
+        let data = lzw_encode(&[0, 1, 0, 1, 1], 2);
+        write_sub_blocks(&mut gif, &data);
+        gif.push(TRAILER);
+
+        let image = &parse_gif(gif)[0].image;
+        let column: Vec<u8> = (0..5).map(|y| image.get(0, y).unwrap().r).collect();
+        assert_eq!(column, vec![0, 255, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_parse_gif_errors() {
+        assert!(matches!(
+            try_parse_gif(b"GIF88a".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+
+        let mut gif = gradient(2, 2, 0).to_gif(&GifOptions::default());
+        gif.truncate(gif.len() - 4);
+        assert!(matches!(
+            try_parse_gif(gif),
+            Err(Error::TruncatedData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_gif_huge_dimensions() {
+        let header = |gif: &mut Vec<u8>, width: u16, height: u16| {
+            gif.extend_from_slice(b"GIF89a");
+            gif.extend_from_slice(&width.to_le_bytes());
+            gif.extend_from_slice(&height.to_le_bytes());
+            gif.extend_from_slice(&[0x80, 0, 0]);
+            gif.extend_from_slice(&[0, 0, 0, 255, 255, 255]);
+        };
+        let frame = |gif: &mut Vec<u8>, left: u16, top: u16, width: u16, height: u16| {
+            gif.push(0x2c);
+            for value in [left, top, width, height] {
+                gif.extend_from_slice(&value.to_le_bytes());
+            }
+            gif.extend_from_slice(&[0, 2]);
+            write_sub_blocks(gif, &lzw_encode(&vec![1; (width * height) as usize], 2));
+        };
+
+        // the canvas keeps the size of the logical screen, even if frames do not cover it
+        let mut gif = vec![];
+        header(&mut gif, 10, 8);
+        frame(&mut gif, 8, 6, 2, 2);
+        gif.push(TRAILER);
+        let image = &parse_gif(gif)[0].image;
+        assert_eq!((image.cols(), image.rows()), (10, 8));
+        assert_eq!(image.get(0, 0), Some(rgb!(0)));
+        assert_eq!(image.get(9, 7), Some(rgb!(255)));
+
+        // a 65535x65535 screen is too large, even with a tiny frame
+        let mut gif = vec![];
+        header(&mut gif, 0xffff, 0xffff);
+        frame(&mut gif, 0, 0, 1, 1);
+        gif.push(TRAILER);
+        assert!(matches!(
+            try_parse_gif(gif),
+            Err(Error::DimensionOverflow { .. })
+        ));
+
+        // the frame claims 65535x65535 pixels, but only contains a few
+        let mut gif = vec![];
+        header(&mut gif, 0xffff, 0xffff);
+        gif.extend_from_slice(&[0x2c, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 2]);
+        write_sub_blocks(&mut gif, &lzw_encode(&[1, 0, 1], 2));
+        gif.push(TRAILER);
+        assert!(matches!(
+            try_parse_gif(gif),
+            Err(Error::TruncatedData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_gif_without_trailer() {
+        let image = Image::new(3, 2);
+        let mut gif = image.to_gif(&GifOptions::default());
+        assert_eq!(gif.pop(), Some(TRAILER));
+        let frames = parse_gif(gif.clone());
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].image, image);
+
+        // the input must not end inside of a block
+        gif.pop();
+        assert!(matches!(
+            try_parse_gif(gif),
+            Err(Error::TruncatedData { .. })
+        ));
+    }
+}
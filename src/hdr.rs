@@ -0,0 +1,474 @@
+//! Reading and writing images in the Radiance HDR (RGBE) format.
+
+use crate::{
+    bytes::{read_bytes, read_u8},
+    color::ColorF32,
+    error::{checked_samples, Error, Result},
+    image::ImageF32,
+};
+
+const FORMAT_RGBE: &str = "32-bit_rle_rgbe";
+
+/// Scanlines with a width in this range are run-length encoded.
+const RLE_WIDTH: std::ops::Range<usize> = 8..0x8000;
+
+/// Shortest run, which is encoded as run when writing.
+const MIN_RUN: usize = 4;
+/// Longest run, which can be encoded in a single byte.
+const MAX_RUN: usize = 127;
+/// Longest literal sequence, which can be encoded in a single packet.
+const MAX_LITERAL: usize = 128;
+
+/// Trait for transforming any values into their Radiance HDR representation.
+pub trait HDR {
+    /// Convert a value into its Radiance HDR representation.
+    fn to_hdr(&self) -> Vec<u8>;
+}
+
+/// Macro for saving an image in its Radiance HDR representation to the disk.
+#[macro_export]
+macro_rules! save_hdr {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::hdr::HDR::to_hdr(&$img))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its Radiance HDR representation to the disk. Returns an error, if
+/// the file could not be written.
+#[macro_export]
+macro_rules! try_save_hdr {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::hdr::HDR::to_hdr(&$img))
+    };
+}
+
+/// Convert a color into the shared exponent representation.
+fn to_rgbe(color: &ColorF32) -> [u8; 4] {
+    let max = color.r.max(color.g).max(color.b);
+    if max.is_nan() || max < 1e-32 {
+        return [0; 4];
+    }
+
+    // infinite samples are clamped to the largest representable value
+    let max = max.min(f32::MAX);
+
+    // max = mantissa * 2^exponent with a mantissa from 0.5 to 1
+    let mut exponent = max.log2().floor() as i32 + 1;
+    if max / (exponent as f32).exp2() >= 1.0 {
+        exponent += 1;
+    }
+    let exponent = exponent.clamp(-128, 127);
+    let scale = 256.0 / (exponent as f32).exp2();
+    let sample = |val: f32| (val * scale).clamp(0.0, 255.0) as u8;
+    [
+        sample(color.r),
+        sample(color.g),
+        sample(color.b),
+        (exponent + 128) as u8,
+    ]
+}
+
+/// Convert a color from the shared exponent representation.
+fn from_rgbe(rgbe: &[u8]) -> ColorF32 {
+    if rgbe[3] == 0 {
+        return ColorF32::default();
+    }
+    let scale = (rgbe[3] as f32 - 136.0).exp2();
+    ColorF32::new(
+        (rgbe[0] as f32 + 0.5) * scale,
+        (rgbe[1] as f32 + 0.5) * scale,
+        (rgbe[2] as f32 + 0.5) * scale,
+    )
+}
+
+/// Append the run-length encoding of a single channel of a scanline.
+fn rle_encode(data: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < data.len() {
+        // find the start of the next run, which is worth encoding
+        let mut start = i;
+        let mut run = 0;
+        while start < data.len() {
+            run = data[start..]
+                .iter()
+                .take(MAX_RUN)
+                .take_while(|val| **val == data[start])
+                .count();
+            if run >= MIN_RUN {
+                break;
+            }
+            start += run;
+        }
+
+        while i < start {
+            let len = (start - i).min(MAX_LITERAL);
+            out.push(len as u8);
+            out.extend_from_slice(&data[i..i + len]);
+            i += len;
+        }
+        if start < data.len() {
+            out.push((128 + run) as u8);
+            out.push(data[start]);
+            i = start + run;
+        }
+    }
+}
+
+impl HDR for ImageF32 {
+    fn to_hdr(&self) -> Vec<u8> {
+        let mut hdr = format!(
+            "#?RADIANCE\nFORMAT={}\n\n-Y {} +X {}\n",
+            FORMAT_RGBE,
+            self.rows(),
+            self.cols()
+        )
+        .into_bytes();
+
+        let width = self.cols();
+        for y in 0..self.rows() {
+            let pixels: Vec<[u8; 4]> = (0..width)
+                .map(|x| to_rgbe(&self.get(x, y).unwrap_or_default()))
+                .collect();
+
+            if !RLE_WIDTH.contains(&width) {
+                hdr.extend(pixels.iter().flatten());
+                continue;
+            }
+
+            hdr.extend_from_slice(&[2, 2, (width >> 8) as u8, width as u8]);
+            for channel in 0..4 {
+                let data: Vec<u8> = pixels.iter().map(|pixel| pixel[channel]).collect();
+                rle_encode(&data, &mut hdr);
+            }
+        }
+        hdr
+    }
+}
+
+/// Read a line of the header (without the line break) starting at the given offset. Returns the
+/// line and the offset of the next line.
+fn read_line(inp: &[u8], pos: usize) -> Result<(String, usize)> {
+    let len = inp
+        .get(pos..)
+        .and_then(|rest| rest.iter().position(|b| *b == b'\n'))
+        .ok_or(Error::TruncatedData {
+            expected: inp.len() + 1,
+            actual: inp.len(),
+        })?;
+    let line = String::from_utf8_lossy(&inp[pos..pos + len]).to_string();
+    Ok((line, pos + len + 1))
+}
+
+/// Read a scanline with the new run-length encoding, where the 4 bytes of the marker have
+/// already been read. Returns the offset after the scanline.
+fn read_rle_scanline(inp: &[u8], mut pos: usize, scanline: &mut [u8]) -> Result<usize> {
+    let width = scanline.len() / 4;
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = read_u8(inp, pos)? as usize;
+            pos += 1;
+            let len = if count > 128 { count - 128 } else { count };
+            if len == 0 || x + len > width {
+                return Err(Error::InvalidData(
+                    "Invalid run length in scanline".to_string(),
+                ));
+            }
+
+            if count > 128 {
+                let val = read_u8(inp, pos)?;
+                pos += 1;
+                for i in x..x + len {
+                    scanline[i * 4 + channel] = val;
+                }
+            } else {
+                for (i, val) in read_bytes(inp, pos, len)?.iter().enumerate() {
+                    scanline[(x + i) * 4 + channel] = *val;
+                }
+                pos += len;
+            }
+            x += len;
+        }
+    }
+    Ok(pos)
+}
+
+/// Read a flat scanline of `width` pixels, which may contain runs of the old run-length encoding.
+/// The scanline grows with the decoded pixels. Returns the offset after the scanline.
+fn read_flat_scanline(
+    inp: &[u8],
+    mut pos: usize,
+    width: usize,
+    scanline: &mut Vec<u8>,
+) -> Result<usize> {
+    scanline.clear();
+    let mut shift = 0;
+    while scanline.len() < width * 4 {
+        let pixel = read_bytes(inp, pos, 4)?;
+        pos += 4;
+
+        // (1, 1, 1, n) repeats the previous pixel, where consecutive markers hold the higher bytes
+        // of the count
+        if pixel[0..3] == [1, 1, 1] && !scanline.is_empty() {
+            let x = scanline.len() / 4;
+            let count = (pixel[3] as usize) << shift;
+            if shift >= 24 || count == 0 || x + count > width {
+                return Err(Error::InvalidData(
+                    "Invalid run length in scanline".to_string(),
+                ));
+            }
+            for _ in 0..count {
+                scanline.extend_from_within((x - 1) * 4..x * 4);
+            }
+            shift += 8;
+        } else {
+            scanline.extend_from_slice(pixel);
+            shift = 0;
+        }
+    }
+    Ok(pos)
+}
+
+/// Parse a vector of u8 into an image in Radiance HDR format. This function will panic, if the
+/// format is not valid. See `try_parse_hdr` for a fallible variant.
+pub fn parse_hdr(inp: Vec<u8>) -> ImageF32 {
+    try_parse_hdr(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into an image in Radiance HDR format.
+pub fn try_parse_hdr(inp: Vec<u8>) -> Result<ImageF32> {
+    let (magic, mut pos) = read_line(&inp, 0)?;
+    if magic != "#?RADIANCE" && magic != "#?RGBE" {
+        return Err(Error::InvalidMagicConstant(
+            "No valid magic constant".to_string(),
+        ));
+    }
+
+    // the header ends with an empty line
+    let mut exposure = 1.0;
+    loop {
+        let (line, next) = read_line(&inp, pos)?;
+        pos = next;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format.trim() != FORMAT_RGBE {
+                return Err(Error::InvalidHeader(format!(
+                    "Unsupported format ({})",
+                    format
+                )));
+            }
+        } else if let Some(value) = line.strip_prefix("EXPOSURE=") {
+            // the pixels have been multiplied with all given exposures
+            exposure *= value
+                .trim()
+                .parse::<f32>()
+                .map_err(|_| Error::InvalidHeader(format!("Invalid exposure ({})", value)))?;
+        }
+    }
+
+    let (resolution, next) = read_line(&inp, pos)?;
+    pos = next;
+    let (top_down, height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+        [y @ ("-Y" | "+Y"), height, "+X", width] => (
+            y == "-Y",
+            height.parse::<usize>().ok(),
+            width.parse::<usize>().ok(),
+        ),
+        _ => (true, None, None),
+    };
+    let (Some(height), Some(width)) = (height, width) else {
+        return Err(Error::InvalidHeader(format!(
+            "Unsupported resolution ({})",
+            resolution
+        )));
+    };
+
+    checked_samples(width, height, 4)?;
+    // the image has no pixels, so there are no scanlines to read
+    if width == 0 || height == 0 {
+        return Ok(ImageF32::new(width, height));
+    }
+
+    // the buffers grow with the scanlines, which are actually decoded, so that a header with huge
+    // dimensions cannot allocate more memory than the input provides
+    let mut data = vec![];
+    let mut scanline = vec![];
+    let factor = 1.0 / exposure;
+    for _ in 0..height {
+        let marker = read_bytes(&inp, pos, 4).unwrap_or(&[0; 4]);
+        let rle = RLE_WIDTH.contains(&width)
+            && marker[0..2] == [2, 2]
+            && marker[2] & 0x80 == 0
+            && ((marker[2] as usize) << 8 | marker[3] as usize) == width;
+        pos = if rle {
+            scanline.resize(width * 4, 0);
+            read_rle_scanline(&inp, pos + 4, &mut scanline)?
+        } else {
+            read_flat_scanline(&inp, pos, width, &mut scanline)?
+        };
+
+        for rgbe in scanline.chunks_exact(4) {
+            let color = from_rgbe(rgbe).scale(factor);
+            data.extend_from_slice(&[color.r, color.g, color.b]);
+        }
+    }
+
+    let image = ImageF32::from_raw(width, height, data)?;
+    Ok(if top_down {
+        image
+    } else {
+        image.flip_vertical()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: ColorF32, b: ColorF32) {
+        let max = b.r.max(b.g).max(b.b);
+        for (x, y) in [(a.r, b.r), (a.g, b.g), (a.b, b.b)] {
+            assert!((x - y).abs() <= max / 128.0, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn test_image(cols: usize, rows: usize) -> ImageF32 {
+        let mut image = ImageF32::new(cols, rows);
+        for y in 0..rows {
+            for x in 0..cols {
+                let val = if x < cols / 2 { 0.5 } else { x as f32 * 10.0 };
+                image.set(
+                    x,
+                    y,
+                    ColorF32::new(val, y as f32 * 0.01, 1000.0 / (x + 1) as f32),
+                );
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn test_rgbe_conversion() {
+        assert_eq!(to_rgbe(&ColorF32::new(1.0, 0.5, 0.0)), [128, 64, 0, 129]);
+        assert_eq!(
+            from_rgbe(&[128, 64, 0, 129]),
+            ColorF32::new(128.5 / 128.0, 64.5 / 128.0, 0.5 / 128.0)
+        );
+        assert_eq!(to_rgbe(&ColorF32::default()), [0; 4]);
+        assert_eq!(from_rgbe(&[0; 4]), ColorF32::default());
+
+        for val in [1e-10, 0.3, 1.0, 2.0, 12345.0, 1e30] {
+            let color = ColorF32::new(val, val / 2.0, 0.0);
+            assert_close(from_rgbe(&to_rgbe(&color)), color);
+        }
+    }
+
+    #[test]
+    fn test_rgbe_non_finite() {
+        let inf = f32::INFINITY;
+        assert_eq!(to_rgbe(&ColorF32::new(inf, 1.0, 0.0)), [255, 0, 0, 255]);
+        assert_eq!(to_rgbe(&ColorF32::splat(f32::NAN)), [0; 4]);
+        assert_eq!(to_rgbe(&ColorF32::splat(-inf)), [0; 4]);
+
+        let mut image = ImageF32::new(2, 1);
+        image.set(0, 0, ColorF32::splat(inf));
+        let parsed = parse_hdr(image.to_hdr());
+        let pixel = parsed.get(0, 0).unwrap();
+        assert!(pixel.r.is_finite() && pixel.r > 1e38);
+        assert_eq!(parsed.get(1, 0), Some(ColorF32::default()));
+    }
+
+    #[test]
+    fn test_hdr_roundtrip() {
+        for (cols, rows) in [(40, 3), (5, 2), (300, 1)] {
+            let image = test_image(cols, rows);
+            let hdr = image.to_hdr();
+            assert!(hdr.starts_with(b"#?RADIANCE\n"));
+
+            let parsed = parse_hdr(hdr);
+            assert_eq!((parsed.cols(), parsed.rows()), (cols, rows));
+            for y in 0..rows {
+                for x in 0..cols {
+                    assert_close(parsed.get(x, y).unwrap(), image.get(x, y).unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hdr_rle_compresses() {
+        let mut image = ImageF32::new(1000, 4);
+        image.fill_with(ColorF32::new(1.0, 2.0, 3.0));
+        // 4 rows with 4 channels of 8 runs each
+        assert!(image.to_hdr().len() < 400);
+    }
+
+    #[test]
+    fn test_parse_hdr_flat_and_exposure() {
+        // bottom-up file with an old-style run repeating the first pixel twice
+        let mut hdr = b"#?RGBE\nEXPOSURE=2\nFORMAT=32-bit_rle_rgbe\n\n+Y 2 +X 2\n".to_vec();
+        hdr.extend_from_slice(&[128, 0, 0, 129, 1, 1, 1, 1]);
+        hdr.extend_from_slice(&[0, 128, 0, 129, 0, 0, 0, 0]);
+
+        let image = parse_hdr(hdr);
+        assert_close(image.get(0, 1).unwrap(), ColorF32::new(0.5, 0.0, 0.0));
+        assert_close(image.get(1, 1).unwrap(), ColorF32::new(0.5, 0.0, 0.0));
+        assert_close(image.get(0, 0).unwrap(), ColorF32::new(0.0, 0.5, 0.0));
+        assert_eq!(image.get(1, 0), Some(ColorF32::default()));
+    }
+
+    #[test]
+    fn test_parse_hdr_errors() {
+        assert!(matches!(
+            try_parse_hdr(b"#?RADIANCF\n\n".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+        assert!(matches!(
+            try_parse_hdr(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n".to_vec()),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            try_parse_hdr(b"#?RADIANCE\n\n+X 1 -Y 1\n".to_vec()),
+            Err(Error::InvalidHeader(_))
+        ));
+
+        let mut hdr = test_image(20, 2).to_hdr();
+        hdr.truncate(hdr.len() - 10);
+        assert!(matches!(
+            try_parse_hdr(hdr),
+            Err(Error::TruncatedData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_hdr_invalid_runs() {
+        let header = b"#?RADIANCE\n\n-Y 1 +X 100000000\n".to_vec();
+
+        // too many consecutive run markers overflow the run length
+        let mut hdr = header.clone();
+        hdr.extend_from_slice(&[1, 2, 3, 4]);
+        for _ in 0..8 {
+            hdr.extend_from_slice(&[1, 1, 1, 1]);
+        }
+        assert!(matches!(try_parse_hdr(hdr), Err(Error::InvalidData(_))));
+
+        let mut hdr = header;
+        hdr.extend_from_slice(&[1, 2, 3, 4, 1, 1, 1, 0]);
+        assert!(matches!(try_parse_hdr(hdr), Err(Error::InvalidData(_))));
+    }
+
+    #[test]
+    fn test_parse_hdr_huge_dimensions() {
+        let hdr = b"#?RADIANCE\n\n-Y 100000 +X 300000\n\x01\x02\x03\x04".to_vec();
+        assert!(matches!(
+            try_parse_hdr(hdr),
+            Err(Error::TruncatedData { .. })
+        ));
+
+        let image = parse_hdr(b"#?RADIANCE\n\n-Y 99999999999 +X 0\n".to_vec());
+        assert_eq!((image.cols(), image.rows()), (0, 99999999999));
+    }
+}
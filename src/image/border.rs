@@ -0,0 +1,109 @@
+//! Module containing the handling of pixels outside of the borders of images.
+use super::{ImageBuffer, Pixel};
+
+/// Modes for extending an image beyond its borders, e.g., for padding or filtering. The examples
+/// show how a row `abcd` is extended by three pixels on each side.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BorderMode<P: Pixel> {
+    /// Use a constant pixel: `xxx|abcd|xxx`.
+    Constant(P),
+    /// Repeat the pixels at the border: `aaa|abcd|ddd`.
+    Replicate,
+    /// Mirror the image at the border without repeating the border pixels: `dcb|abcd|cba`.
+    Reflect,
+    /// Continue with the pixels of the opposite border: `bcd|abcd|abc`.
+    Wrap,
+}
+
+impl<P: Pixel> BorderMode<P> {
+    /// Map a (possibly negative or too large) index to an index inside of the range `0..len`, or
+    /// None, if a constant should be used instead.
+    pub(crate) fn resolve(&self, index: isize, len: usize) -> Option<usize> {
+        if (0..len as isize).contains(&index) {
+            return Some(index as usize);
+        }
+        if len == 0 {
+            return None;
+        }
+        let last = len as isize - 1;
+        match self {
+            BorderMode::Constant(_) => None,
+            BorderMode::Replicate => Some(index.clamp(0, last) as usize),
+            BorderMode::Reflect => {
+                if last == 0 {
+                    return Some(0);
+                }
+                let index = index.rem_euclid(2 * last);
+                Some(if index > last {
+                    2 * last - index
+                } else {
+                    index
+                } as usize)
+            }
+            BorderMode::Wrap => Some(index.rem_euclid(len as isize) as usize),
+        }
+    }
+
+    /// Get the pixel, which is used instead of a pixel outside of an empty image or for constant
+    /// borders.
+    fn constant(&self) -> P {
+        match self {
+            BorderMode::Constant(pixel) => *pixel,
+            _ => P::default(),
+        }
+    }
+}
+
+impl<P: Pixel> ImageBuffer<P> {
+    /// Get the pixel at the specified index, where indices outside of the image are mapped
+    /// according to the border mode.
+    pub fn get_with_border(&self, x: isize, y: isize, border: &BorderMode<P>) -> P {
+        match (
+            border.resolve(x, self.cols()),
+            border.resolve(y, self.rows()),
+        ) {
+            (Some(x), Some(y)) => self.get(x, y).unwrap_or_else(|| border.constant()),
+            _ => border.constant(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::GrayImage;
+
+    use super::*;
+
+    fn extend(border: BorderMode<u8>) -> Vec<u8> {
+        let row = GrayImage::from_raw(4, 1, vec![1, 2, 3, 4]).unwrap();
+        (-3..7)
+            .map(|x| row.get_with_border(x, 0, &border))
+            .collect()
+    }
+
+    #[test]
+    fn test_border_modes() {
+        assert_eq!(
+            extend(BorderMode::Constant(9)),
+            [9, 9, 9, 1, 2, 3, 4, 9, 9, 9]
+        );
+        assert_eq!(
+            extend(BorderMode::Replicate),
+            [1, 1, 1, 1, 2, 3, 4, 4, 4, 4]
+        );
+        assert_eq!(extend(BorderMode::Reflect), [4, 3, 2, 1, 2, 3, 4, 3, 2, 1]);
+        assert_eq!(extend(BorderMode::Wrap), [2, 3, 4, 1, 2, 3, 4, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_border_resolve() {
+        let reflect = BorderMode::<u8>::Reflect;
+        assert_eq!(reflect.resolve(-7, 3), Some(1));
+        assert_eq!(reflect.resolve(5, 1), Some(0));
+        assert_eq!(reflect.resolve(0, 0), None);
+        assert_eq!(BorderMode::<u8>::Wrap.resolve(-7, 3), Some(2));
+
+        let empty = GrayImage::new(0, 0);
+        assert_eq!(empty.get_with_border(0, 0, &BorderMode::Replicate), 0);
+    }
+}
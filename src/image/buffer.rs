@@ -0,0 +1,179 @@
+//! Module containing the generic representation of images.
+use std::borrow::Borrow;
+
+use crate::error::{checked_samples, Error, Result};
+
+use super::Pixel;
+
+/// Struct for representing an image with arbitrary pixels. The channels of the pixels are stored
+/// row by row in a single contiguous buffer.
+///
+/// Usually, you do not need to name this type directly, but use one of its aliases (e.g., `Image`
+/// or `GrayImage`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageBuffer<P: Pixel> {
+    cols: usize,
+    rows: usize,
+    /// Number of samples between the start of two consecutive rows.
+    stride: usize,
+    data: Vec<P::Subpixel>,
+}
+
+impl<P: Pixel> ImageBuffer<P> {
+    /// Create a new image with the specified width and height, where all samples are zero (i.e.,
+    /// all pixels are black or transparent black).
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the number of samples of the image overflows a usize.
+    pub fn new(cols: usize, rows: usize) -> Self {
+        let len = checked_samples(cols, rows, P::CHANNELS).unwrap_or_else(|err| panic!("{}", err));
+        Self {
+            cols,
+            rows,
+            stride: cols * P::CHANNELS,
+            data: vec![P::Subpixel::default(); len],
+        }
+    }
+
+    /// Create an image from a buffer of raw samples, which are stored row by row without any
+    /// padding. Returns an error, if the length of the buffer does not match the dimensions.
+    pub fn from_raw(cols: usize, rows: usize, data: Vec<P::Subpixel>) -> Result<Self> {
+        let len = checked_samples(cols, rows, P::CHANNELS)?;
+        if data.len() != len {
+            return Err(Error::InvalidData(format!(
+                "Buffer of {} samples does not match the dimensions {}x{} ({} samples)",
+                data.len(),
+                cols,
+                rows,
+                len
+            )));
+        }
+        Ok(Self {
+            cols,
+            rows,
+            stride: cols * P::CHANNELS,
+            data,
+        })
+    }
+
+    /// Get the number of columns of this image.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Get the number of rows of this image.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Get the number of samples between the start of two consecutive rows in the raw buffer.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Get the raw samples of this image. See `ImageBuffer::stride` for the layout of the rows.
+    pub fn as_raw(&self) -> &[P::Subpixel] {
+        &self.data
+    }
+
+    /// Get the raw samples of this image mutably. See `ImageBuffer::stride` for the layout of the
+    /// rows.
+    pub fn as_raw_mut(&mut self) -> &mut [P::Subpixel] {
+        &mut self.data
+    }
+
+    /// Consume this image and get its raw samples.
+    pub fn into_raw(self) -> Vec<P::Subpixel> {
+        self.data
+    }
+
+    /// Get the samples of a single row. The index has to be in bounds.
+    pub(crate) fn raw_row(&self, y: usize) -> &[P::Subpixel] {
+        &self.data[y * self.stride..y * self.stride + self.cols * P::CHANNELS]
+    }
+
+    /// Get the offset of a pixel in the raw buffer, or None, if the index is out of bounds.
+    fn offset(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.cols && y < self.rows).then(|| y * self.stride + x * P::CHANNELS)
+    }
+
+    /// Get the pixel at the specified index, or None, if the index it out of bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<P> {
+        let offset = self.offset(x, y)?;
+        Some(P::from_channels(&self.data[offset..]))
+    }
+
+    /// Set a pixel at the specified coordinates. The pixel can be passed by value or by reference.
+    pub fn set(&mut self, x: usize, y: usize, pixel: impl Borrow<P>) {
+        let Some(offset) = self.offset(x, y) else {
+            // TODO: Return error if out of bounds
+            return;
+        };
+        pixel.borrow().write_channels(&mut self.data[offset..]);
+    }
+
+    /// Fill the entire image with one pixel.
+    pub fn fill_with(&mut self, pixel: impl Borrow<P>) {
+        if self.data.is_empty() {
+            return;
+        }
+        pixel.borrow().write_channels(&mut self.data);
+        for offset in (P::CHANNELS..self.data.len()).step_by(P::CHANNELS) {
+            self.data.copy_within(..P::CHANNELS, offset);
+        }
+    }
+
+    /// Convert this image to another pixel type. See `Pixel` for which conversions are lossy.
+    pub fn convert<Q: Pixel>(&self) -> ImageBuffer<Q> {
+        self.map(|pixel| Q::from_rgba_unit(pixel.to_rgba_unit()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::{Color, GrayAlpha, Rgba},
+        image::{GrayAlphaImage, GrayImage, Image, RgbaImage},
+        rgb, rgba,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_image_buffer_raw() {
+        let mut img = GrayAlphaImage::from_raw(2, 2, (0..8).collect()).unwrap();
+        assert_eq!(img.stride(), 4);
+        assert_eq!(img.get(1, 1), Some(GrayAlpha::new(6, 7)));
+
+        img.set(0, 1, GrayAlpha::new(42, 17));
+        assert_eq!(img.as_raw()[4..6], [42, 17]);
+        img.fill_with(GrayAlpha::new(1, 2));
+        assert_eq!(img.into_raw(), [1, 2].repeat(4));
+
+        assert!(matches!(
+            RgbaImage::from_raw(1, 1, vec![0; 3]),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn test_image_buffer_convert() {
+        let mut img = Image::new(2, 1);
+        img.set(1, 0, rgb!(255, 0, 0));
+
+        let gray: GrayImage = img.convert();
+        assert_eq!(gray.get(1, 0), Some(76));
+
+        let rgba: RgbaImage = img.convert();
+        assert_eq!(rgba.get(0, 0), Some(rgba!(0, 0, 0)));
+        assert_eq!(rgba.convert::<Color>(), img);
+
+        let transparent = RgbaImage::new(1, 1);
+        assert_eq!(transparent.get(0, 0), Some(Rgba::default()));
+        assert_eq!(
+            transparent.convert::<GrayAlpha>().get(0, 0),
+            Some(GrayAlpha::new(0, 0))
+        );
+    }
+}
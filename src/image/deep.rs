@@ -0,0 +1,182 @@
+//! Module containing the internal representation of images with 16bit per sample.
+use crate::{
+    color::{u16_to_u8, u8_to_u16, Color, Color16},
+    ppm::{encode_ascii_pnm, encode_ascii_row, pnm_header, PBM, PGM, PNM},
+};
+
+use super::{GrayImage, Image, ImageBuffer};
+
+/// Type for representing an RGB image with 16bit per channel.
+pub type Image16 = ImageBuffer<Color16>;
+
+/// Type for representing a grayscale image with 16bit luminance values.
+pub type GrayImage16 = ImageBuffer<u16>;
+
+impl From<&Image> for Image16 {
+    fn from(image: &Image) -> Self {
+        image.map(Color16::from)
+    }
+}
+
+impl From<&Image16> for Image {
+    fn from(deep: &Image16) -> Self {
+        deep.map(Color::from)
+    }
+}
+
+impl From<&GrayImage> for GrayImage16 {
+    fn from(gray: &GrayImage) -> Self {
+        gray.map(u8_to_u16)
+    }
+}
+
+impl From<&GrayImage16> for GrayImage {
+    fn from(deep: &GrayImage16) -> Self {
+        deep.map(u16_to_u8)
+    }
+}
+
+impl From<&Image16> for GrayImage16 {
+    /// Convert an RGB image to grayscale by calculating the luma of every pixel.
+    fn from(image: &Image16) -> Self {
+        image.map(|color| color.luma())
+    }
+}
+
+impl From<&GrayImage16> for Image16 {
+    /// Convert a grayscale image to RGB by replicating the luminance in every channel.
+    fn from(gray: &GrayImage16) -> Self {
+        gray.map(Color16::splat)
+    }
+}
+
+impl PNM for Image16 {
+    fn to_pnm_p3(&self) -> String {
+        let header = pnm_header("P3", self.cols(), self.rows(), Some(u16::MAX as usize));
+        encode_ascii_pnm(header, self.rows(), |y, out| {
+            encode_ascii_row(self.raw_row(y), out)
+        })
+    }
+
+    /// Convert this image into its P6 representation with two bytes (big endian) per sample.
+    fn to_pnm_p6(&self) -> Vec<u8> {
+        let mut ppm =
+            pnm_header("P6", self.cols(), self.rows(), Some(u16::MAX as usize)).into_bytes();
+        for sample in self.as_raw() {
+            ppm.extend_from_slice(&sample.to_be_bytes());
+        }
+        ppm
+    }
+}
+
+impl PBM for Image16 {
+    fn to_pnm_p1(&self) -> String {
+        GrayImage::from(&GrayImage16::from(self)).to_pnm_p1()
+    }
+
+    fn to_pnm_p4(&self) -> Vec<u8> {
+        GrayImage::from(&GrayImage16::from(self)).to_pnm_p4()
+    }
+}
+
+impl PGM for Image16 {
+    fn to_pnm_p2(&self) -> String {
+        GrayImage16::from(self).to_pnm_p2()
+    }
+
+    fn to_pnm_p5(&self) -> Vec<u8> {
+        GrayImage16::from(self).to_pnm_p5()
+    }
+}
+
+impl PNM for GrayImage16 {
+    fn to_pnm_p3(&self) -> String {
+        Image16::from(self).to_pnm_p3()
+    }
+
+    fn to_pnm_p6(&self) -> Vec<u8> {
+        Image16::from(self).to_pnm_p6()
+    }
+}
+
+impl PBM for GrayImage16 {
+    fn to_pnm_p1(&self) -> String {
+        GrayImage::from(self).to_pnm_p1()
+    }
+
+    fn to_pnm_p4(&self) -> Vec<u8> {
+        GrayImage::from(self).to_pnm_p4()
+    }
+}
+
+impl PGM for GrayImage16 {
+    fn to_pnm_p2(&self) -> String {
+        let header = pnm_header("P2", self.cols(), self.rows(), Some(u16::MAX as usize));
+        encode_ascii_pnm(header, self.rows(), |y, out| {
+            encode_ascii_row(self.raw_row(y), out)
+        })
+    }
+
+    /// Convert this image into its P5 representation with two bytes (big endian) per sample.
+    fn to_pnm_p5(&self) -> Vec<u8> {
+        let mut pgm =
+            pnm_header("P5", self.cols(), self.rows(), Some(u16::MAX as usize)).into_bytes();
+        for value in self.as_raw() {
+            pgm.extend_from_slice(&value.to_be_bytes());
+        }
+        pgm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rgb;
+
+    use super::*;
+
+    #[test]
+    fn test_image16_new() {
+        let img = Image16::new(42, 17);
+        assert_eq!(img.cols(), 42);
+        assert_eq!(img.rows(), 17);
+        assert_eq!(img.as_raw(), vec![0; 42 * 17 * 3]);
+    }
+
+    #[test]
+    fn test_image16_set() {
+        let mut img = Image16::new(42, 17);
+        img.set(10, 10, Color16::new(1000, 2000, 3000));
+        assert_eq!(img.get(10, 10), Some(Color16::new(1000, 2000, 3000)));
+        assert_eq!(img.get(42, 10), None);
+    }
+
+    #[test]
+    fn test_image16_conversion() {
+        let mut img = Image::new(2, 1);
+        img.set(1, 0, rgb!(255, 128, 0));
+
+        let deep = Image16::from(&img);
+        assert_eq!(deep.get(1, 0), Some(Color16::new(65535, 32896, 0)));
+        assert_eq!(Image::from(&deep).get(1, 0), Some(rgb!(255, 128, 0)));
+    }
+
+    #[test]
+    fn test_image16_to_pnm_p6() {
+        let mut img = Image16::new(1, 1);
+        img.set(0, 0, Color16::new(0x0102, 0x0304, 0x0506));
+        assert_eq!(
+            img.to_pnm_p6(),
+            b"P6\n1 1\n65535\n\x01\x02\x03\x04\x05\x06".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_gray_image16_to_pnm_p5() {
+        let mut img = GrayImage16::new(2, 1);
+        img.set(1, 0, 0xabcd);
+        assert_eq!(
+            img.to_pnm_p5(),
+            b"P5\n2 1\n65535\n\x00\x00\xab\xcd".to_vec()
+        );
+    }
+}
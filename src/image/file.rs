@@ -0,0 +1,151 @@
+//! Module for loading images from and saving images to the disk.
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use crate::{
+    error::{Error, Result},
+    gif::GifOptions,
+    jpeg::JpegOptions,
+    tga::TgaOptions,
+    tiff::TiffOptions,
+};
+
+use super::{Image, ImageFormat};
+
+/// Options for saving an image. Options of formats other than the one the image is saved in are
+/// ignored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct SaveOptions {
+    /// Format of the file. If `None`, the format is inferred from the extension of the path.
+    pub format: Option<ImageFormat>,
+    pub jpeg: JpegOptions,
+    pub gif: GifOptions,
+    pub tiff: TiffOptions,
+    pub tga: TgaOptions,
+}
+
+impl ImageFormat {
+    /// Get the format belonging to a file extension (case insensitive), or None, if the extension
+    /// is unknown. PNM extensions are mapped to their binary representation.
+    pub fn from_extension(extension: &str) -> Option<ImageFormat> {
+        Some(match extension.to_ascii_lowercase().as_str() {
+            "pbm" => ImageFormat::Pbm4,
+            "pgm" => ImageFormat::Pgm5,
+            "ppm" | "pnm" => ImageFormat::Ppm6,
+            "pam" => ImageFormat::Pam,
+            "bmp" => ImageFormat::Bmp,
+            "png" => ImageFormat::Png,
+            "qoi" => ImageFormat::Qoi,
+            "jpg" | "jpeg" | "jpe" | "jfif" => ImageFormat::Jpeg,
+            "gif" => ImageFormat::Gif,
+            "tif" | "tiff" => ImageFormat::Tiff,
+            "tga" | "icb" | "vda" | "vst" => ImageFormat::Tga,
+            "ff" | "farbfeld" => ImageFormat::Farbfeld,
+            "hdr" | "pic" => ImageFormat::Hdr,
+            _ => return None,
+        })
+    }
+
+    /// Get the format belonging to the extension of a path, or None, if the path has no known
+    /// extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<ImageFormat> {
+        ImageFormat::from_extension(path.as_ref().extension()?.to_str()?)
+    }
+}
+
+impl Image {
+    /// Load an image from the disk. The format will be determined from the magic constant at the
+    /// head of the file (regardless of its extension).
+    pub fn open(path: impl AsRef<Path>) -> Result<Image> {
+        Image::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Save this image to the disk. The format will be determined from the extension of the path.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.save_with(path, &SaveOptions::default())
+    }
+
+    /// Save this image to the disk with the given options. Unless specified in the options, the
+    /// format will be determined from the extension of the path.
+    pub fn save_with(&self, path: impl AsRef<Path>, options: &SaveOptions) -> Result<()> {
+        let path = path.as_ref();
+        let Some(format) = options.format.or_else(|| ImageFormat::from_path(path)) else {
+            return Err(Error::UnsupportedFormat(format!(
+                "Could not determine the format of {}",
+                path.display()
+            )));
+        };
+        self.write_with(BufWriter::new(File::create(path)?), format, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rgb;
+
+    use super::*;
+
+    fn gradient(cols: usize, rows: usize) -> Image {
+        let mut img = Image::new(cols, rows);
+        for y in 0..rows {
+            for x in 0..cols {
+                img.set(x, y, rgb!((x * 20) as u8, (y * 30) as u8, 100));
+            }
+        }
+        img
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rustvision_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ImageFormat::from_path("a/b.PNG"), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::from_path("b.jpeg"), Some(ImageFormat::Jpeg));
+        assert_eq!(ImageFormat::from_path("b.ppm"), Some(ImageFormat::Ppm6));
+        assert_eq!(ImageFormat::from_path("b.txt"), None);
+        assert_eq!(ImageFormat::from_path("ppm"), None);
+    }
+
+    #[test]
+    fn test_save_and_open() {
+        let img = gradient(9, 4);
+        for name in ["img.png", "img.qoi", "img.pgm", "img.tga", "img.ff"] {
+            let path = temp_path(name);
+            img.save(&path).unwrap();
+            let read = Image::open(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!((read.cols(), read.rows()), (9, 4), "{}", name);
+            let expected = if name.ends_with("pgm") {
+                rgb!(img.get(5, 3).unwrap().luma())
+            } else {
+                img.get(5, 3).unwrap()
+            };
+            assert_eq!(read.get(5, 3), Some(expected), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_save_with() {
+        let img = gradient(3, 2);
+        let path = temp_path("ascii.txt");
+        assert!(matches!(img.save(&path), Err(Error::UnsupportedFormat(_))));
+
+        let options = SaveOptions {
+            format: Some(ImageFormat::Ppm3),
+            ..Default::default()
+        };
+        img.save_with(&path, &options).unwrap();
+        let content = std::fs::read(&path).unwrap();
+        assert!(content.starts_with(b"P3\n3 2\n255\n"));
+        // the format is determined by the content instead of the extension
+        assert_eq!(Image::open(&path).unwrap().get(2, 1), img.get(2, 1));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(Image::open(&path), Err(Error::Io(_))));
+    }
+}
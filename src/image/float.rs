@@ -0,0 +1,156 @@
+//! Module containing the internal representation of images with floating point samples.
+use crate::color::{Color, ColorF32};
+
+use super::{Image, ImageBuffer};
+
+/// Type for representing a (linear) RGB image with floating point samples, e.g., with a high
+/// dynamic range.
+pub type ImageF32 = ImageBuffer<ColorF32>;
+
+impl ImageF32 {
+    /// Map the (high dynamic range) colors of this image to an 8bit image with the given operator.
+    pub fn tone_map(&self, operator: ToneMapOperator, options: &ToneMapOptions) -> Image {
+        let factor = options.exposure.exp2();
+        let encode = |val: f32| val.clamp(0.0, 1.0).powf(1.0 / options.gamma);
+        self.map(|color| {
+            let color = operator.apply(color.scale(factor));
+            Color::from(ColorF32::new(
+                encode(color.r),
+                encode(color.g),
+                encode(color.b),
+            ))
+        })
+    }
+}
+
+/// Operators for mapping colors with a high dynamic range to the range from 0 to 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ToneMapOperator {
+    /// Only scale the colors by the exposure and clamp them.
+    Exposure,
+    /// Global operator by Reinhard et al., which compresses the luminance `L` to `L / (1 + L)`.
+    Reinhard,
+    /// Filmic curve, which approximates the ACES reference rendering transform (fit by Krzysztof
+    /// Narkowicz).
+    Aces,
+}
+
+impl ToneMapOperator {
+    fn apply(self, color: ColorF32) -> ColorF32 {
+        match self {
+            ToneMapOperator::Exposure => color,
+            ToneMapOperator::Reinhard => {
+                let luminance = color.luminance();
+                if luminance > 0.0 {
+                    color.scale(1.0 / (1.0 + luminance))
+                } else {
+                    ColorF32::default()
+                }
+            }
+            ToneMapOperator::Aces => {
+                let curve = |x: f32| {
+                    let x = x.max(0.0);
+                    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+                };
+                ColorF32::new(curve(color.r), curve(color.g), curve(color.b))
+            }
+        }
+    }
+}
+
+/// Options for tone mapping.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ToneMapOptions {
+    /// Exposure in stops, i.e., colors are scaled by `2^exposure` before tone mapping.
+    pub exposure: f32,
+    /// Gamma for encoding the tone mapped colors.
+    pub gamma: f32,
+}
+
+impl Default for ToneMapOptions {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            gamma: 2.2,
+        }
+    }
+}
+
+impl From<&Image> for ImageF32 {
+    /// Convert an image into floating point samples from 0 to 1.
+    fn from(image: &Image) -> Self {
+        image.map(ColorF32::from)
+    }
+}
+
+impl From<&ImageF32> for Image {
+    /// Convert floating point samples into an image, where samples outside of the range from 0 to
+    /// 1 are clamped. See `ImageF32::tone_map` for mapping high dynamic ranges.
+    fn from(float: &ImageF32) -> Self {
+        float.map(Color::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rgb;
+
+    use super::*;
+
+    #[test]
+    fn test_image_f32_set() {
+        let mut img = ImageF32::new(42, 17);
+        assert_eq!((img.cols(), img.rows()), (42, 17));
+        img.set(10, 10, ColorF32::new(1.5, 20.0, 0.0));
+        assert_eq!(img.get(10, 10), Some(ColorF32::new(1.5, 20.0, 0.0)));
+        assert_eq!(img.get(42, 10), None);
+    }
+
+    #[test]
+    fn test_image_f32_conversion() {
+        let mut img = Image::new(2, 1);
+        img.set(1, 0, rgb!(255, 51, 0));
+
+        let float = ImageF32::from(&img);
+        assert_eq!(float.get(1, 0), Some(ColorF32::new(1.0, 0.2, 0.0)));
+        assert_eq!(Image::from(&float).get(1, 0), Some(rgb!(255, 51, 0)));
+    }
+
+    #[test]
+    fn test_tone_map() {
+        let mut img = ImageF32::new(3, 1);
+        img.set(0, 0, ColorF32::splat(0.25));
+        img.set(1, 0, ColorF32::splat(1.0));
+        img.set(2, 0, ColorF32::splat(1000.0));
+
+        let linear = ToneMapOptions {
+            exposure: 0.0,
+            gamma: 1.0,
+        };
+        let exposure = img.tone_map(ToneMapOperator::Exposure, &linear);
+        assert_eq!(exposure.get(0, 0), Some(rgb!(64)));
+        assert_eq!(exposure.get(2, 0), Some(rgb!(255)));
+
+        let brighter = img.tone_map(
+            ToneMapOperator::Exposure,
+            &ToneMapOptions {
+                exposure: 1.0,
+                gamma: 1.0,
+            },
+        );
+        assert_eq!(brighter.get(0, 0), Some(rgb!(128)));
+
+        let reinhard = img.tone_map(ToneMapOperator::Reinhard, &linear);
+        assert_eq!(reinhard.get(0, 0), Some(rgb!(51)));
+        assert_eq!(reinhard.get(1, 0), Some(rgb!(128)));
+        assert_eq!(reinhard.get(2, 0), Some(rgb!(255)));
+
+        let aces = img.tone_map(ToneMapOperator::Aces, &linear);
+        assert_eq!(aces.get(1, 0), Some(rgb!(205)));
+        assert_eq!(aces.get(2, 0), Some(rgb!(255)));
+
+        // gamma brightens the mid tones
+        let gamma = img.tone_map(ToneMapOperator::Exposure, &ToneMapOptions::default());
+        assert_eq!(gamma.get(0, 0), Some(rgb!(136)));
+    }
+}
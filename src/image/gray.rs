@@ -0,0 +1,121 @@
+//! Module containing the internal representation of grayscale images.
+use crate::{
+    color::{Color, GrayAlpha},
+    ppm::{
+        encode_ascii_pnm, encode_ascii_row, encode_p1_row, encode_p4_row, pnm_header, PBM, PGM, PNM,
+    },
+};
+
+use super::{Image, ImageBuffer};
+
+/// Type for representing a grayscale image with 8bit luminance values.
+pub type GrayImage = ImageBuffer<u8>;
+
+/// Type for representing a grayscale image with 8bit luminance values and an alpha channel.
+pub type GrayAlphaImage = ImageBuffer<GrayAlpha>;
+
+/// Type for representing a grayscale image with floating point luminance values.
+pub type GrayImageF32 = ImageBuffer<f32>;
+
+impl From<&Image> for GrayImage {
+    /// Convert an RGB image to grayscale by calculating the luma of every pixel.
+    fn from(image: &Image) -> Self {
+        image.map(|color| color.luma())
+    }
+}
+
+impl From<&GrayImage> for Image {
+    /// Convert a grayscale image to RGB by replicating the luminance in every channel.
+    fn from(gray: &GrayImage) -> Self {
+        gray.map(Color::splat)
+    }
+}
+
+impl PNM for GrayImage {
+    fn to_pnm_p3(&self) -> String {
+        Image::from(self).to_pnm_p3()
+    }
+
+    fn to_pnm_p6(&self) -> Vec<u8> {
+        Image::from(self).to_pnm_p6()
+    }
+}
+
+impl PBM for GrayImage {
+    fn to_pnm_p1(&self) -> String {
+        let header = pnm_header("P1", self.cols(), self.rows(), None);
+        encode_ascii_pnm(header, self.rows(), |y, out| {
+            encode_p1_row(self.raw_row(y).iter().copied(), out)
+        })
+    }
+
+    fn to_pnm_p4(&self) -> Vec<u8> {
+        let mut pbm = pnm_header("P4", self.cols(), self.rows(), None).into_bytes();
+        for y in 0..self.rows() {
+            encode_p4_row(self.raw_row(y).iter().copied(), &mut pbm);
+        }
+        pbm
+    }
+}
+
+impl PGM for GrayImage {
+    fn to_pnm_p2(&self) -> String {
+        let header = pnm_header("P2", self.cols(), self.rows(), Some(u8::MAX as usize));
+        encode_ascii_pnm(header, self.rows(), |y, out| {
+            encode_ascii_row(self.raw_row(y), out)
+        })
+    }
+
+    fn to_pnm_p5(&self) -> Vec<u8> {
+        let mut pgm =
+            pnm_header("P5", self.cols(), self.rows(), Some(u8::MAX as usize)).into_bytes();
+        pgm.extend_from_slice(self.as_raw());
+        pgm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rgb;
+
+    use super::*;
+
+    #[test]
+    fn test_gray_image_new() {
+        let img = GrayImage::new(42, 17);
+        assert_eq!(img.cols(), 42);
+        assert_eq!(img.rows(), 17);
+        assert_eq!(img.as_raw(), vec![0; 42 * 17]);
+    }
+
+    #[test]
+    fn test_gray_image_set() {
+        let mut img = GrayImage::new(42, 17);
+        img.set(10, 10, 42);
+        assert_eq!(img.get(10, 10), Some(42));
+        assert_eq!(img.get(100, 10), None);
+    }
+
+    #[test]
+    fn test_gray_image_conversion() {
+        let mut img = Image::new(2, 1);
+        img.set(0, 0, rgb!(255, 255, 255));
+        img.set(1, 0, rgb!(255, 0, 0));
+
+        let gray = GrayImage::from(&img);
+        assert_eq!(gray.get(0, 0), Some(255));
+        assert_eq!(gray.get(1, 0), Some(76));
+
+        let rgb = Image::from(&gray);
+        assert_eq!(rgb.get(1, 0), Some(rgb!(76)));
+    }
+
+    #[test]
+    fn test_gray_image_to_pnm_p4() {
+        let mut img = GrayImage::new(9, 1);
+        img.fill_with(255);
+        img.set(0, 0, 0);
+        img.set(8, 0, 0);
+        assert_eq!(img.to_pnm_p4(), b"P4\n9 1\n\x80\x80".to_vec());
+    }
+}
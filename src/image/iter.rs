@@ -0,0 +1,328 @@
+//! Module containing iterators over the pixels and rows of images and views.
+use std::{borrow::Borrow, marker::PhantomData};
+
+use super::{ImageBuffer, ImageView, ImageViewMut, Pixel};
+
+/// Mutable reference to a single pixel of an image, as returned by `pixels_mut`.
+#[derive(Debug)]
+pub struct PixelMut<'a, P: Pixel> {
+    channels: &'a mut [P::Subpixel],
+    pixel: PhantomData<P>,
+}
+
+impl<'a, P: Pixel> PixelMut<'a, P> {
+    fn new(channels: &'a mut [P::Subpixel]) -> Self {
+        Self {
+            channels,
+            pixel: PhantomData,
+        }
+    }
+
+    /// Get the current value of this pixel.
+    pub fn get(&self) -> P {
+        P::from_channels(self.channels)
+    }
+
+    /// Overwrite this pixel.
+    pub fn set(&mut self, pixel: impl Borrow<P>) {
+        pixel.borrow().write_channels(self.channels);
+    }
+
+    /// Get the raw channels of this pixel.
+    pub fn channels_mut(&mut self) -> &mut [P::Subpixel] {
+        self.channels
+    }
+}
+
+impl<P: Pixel> ImageBuffer<P> {
+    /// Iterate over all pixels of this image row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = P> + '_ {
+        self.as_view().pixels()
+    }
+
+    /// Iterate over all pixels of this image row by row together with their coordinates.
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item = (usize, usize, P)> + '_ {
+        self.as_view().enumerate_pixels()
+    }
+
+    /// Iterate mutably over all pixels of this image row by row.
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = PixelMut<'_, P>> {
+        self.as_raw_mut()
+            .chunks_exact_mut(P::CHANNELS)
+            .map(PixelMut::new)
+    }
+
+    /// Iterate over all rows of this image, where every row is an iterator over its pixels. Note,
+    /// that `rows` returns the number of rows instead.
+    pub fn row_iter(&self) -> impl Iterator<Item = impl Iterator<Item = P> + '_> + '_ {
+        self.as_view().row_iter()
+    }
+
+    /// Iterate mutably over all rows of this image, where every row is an iterator over its
+    /// pixels.
+    pub fn row_iter_mut(&mut self) -> impl Iterator<Item = impl Iterator<Item = PixelMut<'_, P>>> {
+        self.raw_rows_mut()
+            .map(|row| row.chunks_exact_mut(P::CHANNELS).map(PixelMut::new))
+    }
+
+    /// Iterate over the raw samples of all rows of this image. See `ImageBuffer::stride` for the
+    /// layout of the rows.
+    pub fn raw_rows(&self) -> impl Iterator<Item = &[P::Subpixel]> + '_ {
+        self.as_view().raw_rows()
+    }
+
+    /// Iterate mutably over the raw samples of all rows of this image.
+    pub fn raw_rows_mut(&mut self) -> impl Iterator<Item = &mut [P::Subpixel]> {
+        let (rows, stride, len) = (self.rows(), self.stride(), self.cols() * P::CHANNELS);
+        split_rows_mut(self.as_raw_mut(), rows, stride, len)
+    }
+
+    /// Create a new image of the same size, where every pixel is mapped with `f`.
+    pub fn map<Q: Pixel>(&self, f: impl Fn(P) -> Q) -> ImageBuffer<Q> {
+        let mut image = ImageBuffer::new(self.cols(), self.rows());
+        for (mut target, pixel) in image.pixels_mut().zip(self.pixels()) {
+            target.set(f(pixel));
+        }
+        image
+    }
+
+    /// Create a new image of the same size, where every pixel is mapped with `f` from the pixels
+    /// at the same position in this and in another image.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the dimensions of both images differ.
+    pub fn zip_map<Q: Pixel, R: Pixel>(
+        &self,
+        other: &ImageBuffer<Q>,
+        f: impl Fn(P, Q) -> R,
+    ) -> ImageBuffer<R> {
+        assert_eq!(
+            (self.cols(), self.rows()),
+            (other.cols(), other.rows()),
+            "images need to have the same dimensions"
+        );
+        let mut image = ImageBuffer::new(self.cols(), self.rows());
+        for (mut target, (a, b)) in image.pixels_mut().zip(self.pixels().zip(other.pixels())) {
+            target.set(f(a, b));
+        }
+        image
+    }
+}
+
+impl<'a, P: Pixel> ImageView<'a, P> {
+    /// Iterate over all rows of this view, where every row is an iterator over its pixels.
+    pub fn row_iter(&self) -> impl Iterator<Item = impl Iterator<Item = P> + 'a> + 'a {
+        self.raw_rows()
+            .map(|row| row.chunks_exact(P::CHANNELS).map(P::from_channels))
+    }
+
+    /// Iterate over the raw samples of all rows of this view.
+    pub fn raw_rows(&self) -> impl Iterator<Item = &'a [P::Subpixel]> + 'a {
+        let view = *self;
+        (0..self.rows()).map(move |y| view.raw_row(y))
+    }
+}
+
+impl<P: Pixel> ImageViewMut<'_, P> {
+    /// Iterate mutably over all pixels of this view row by row.
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = PixelMut<'_, P>> {
+        self.raw_rows_mut()
+            .flat_map(|row| row.chunks_exact_mut(P::CHANNELS).map(PixelMut::new))
+    }
+
+    /// Iterate over all rows of this view, where every row is an iterator over its pixels.
+    pub fn row_iter(&self) -> impl Iterator<Item = impl Iterator<Item = P> + '_> + '_ {
+        self.as_view().row_iter()
+    }
+
+    /// Iterate mutably over all rows of this view, where every row is an iterator over its pixels.
+    pub fn row_iter_mut(&mut self) -> impl Iterator<Item = impl Iterator<Item = PixelMut<'_, P>>> {
+        self.raw_rows_mut()
+            .map(|row| row.chunks_exact_mut(P::CHANNELS).map(PixelMut::new))
+    }
+
+    /// Iterate over the raw samples of all rows of this view.
+    pub fn raw_rows(&self) -> impl Iterator<Item = &[P::Subpixel]> + '_ {
+        self.as_view().raw_rows()
+    }
+
+    /// Iterate mutably over the raw samples of all rows of this view.
+    pub fn raw_rows_mut(&mut self) -> impl Iterator<Item = &mut [P::Subpixel]> {
+        let (rows, stride, len) = (self.rows(), self.stride(), self.cols() * P::CHANNELS);
+        split_rows_mut(self.raw_mut(), rows, stride, len)
+    }
+}
+
+/// Split samples into `rows` rows of `len` samples each, which start every `stride` samples. Rows
+/// of empty images are empty slices, so that there are always `rows` of them.
+fn split_rows_mut<T>(
+    data: &mut [T],
+    rows: usize,
+    stride: usize,
+    len: usize,
+) -> impl Iterator<Item = &mut [T]> {
+    let mut rest = data;
+    (0..rows).map(move |_| {
+        let data = std::mem::take(&mut rest);
+        let (row, tail) = data.split_at_mut(stride.min(data.len()));
+        rest = tail;
+        &mut row[..len]
+    })
+}
+
+#[cfg(feature = "rayon")]
+mod parallel {
+    use rayon::prelude::*;
+
+    use super::{
+        super::{ImageBuffer, Pixel},
+        split_rows_mut,
+    };
+
+    impl<P: Pixel> ImageBuffer<P> {
+        /// Iterate in parallel over the raw samples of all rows of this image. Use `enumerate` to
+        /// get the index of each row.
+        pub fn par_raw_rows(&self) -> impl IndexedParallelIterator<Item = &[P::Subpixel]> + '_ {
+            let (stride, len, data) = (self.stride(), self.cols() * P::CHANNELS, self.as_raw());
+            (0..self.rows())
+                .into_par_iter()
+                .map(move |y| &data[y * stride..y * stride + len])
+        }
+
+        /// Iterate mutably in parallel over the raw samples of all rows of this image. Use
+        /// `enumerate` to get the index of each row.
+        pub fn par_raw_rows_mut(
+            &mut self,
+        ) -> impl IndexedParallelIterator<Item = &mut [P::Subpixel]> + '_ {
+            let (rows, stride, len) = (self.rows(), self.stride(), self.cols() * P::CHANNELS);
+            // the rows are split sequentially, which is cheap compared to processing them
+            let rows: Vec<_> = split_rows_mut(self.as_raw_mut(), rows, stride, len).collect();
+            rows.into_par_iter()
+        }
+
+        /// Create a new image of the same size, where every pixel is mapped with `f` in parallel.
+        pub fn par_map<Q: Pixel>(&self, f: impl Fn(P) -> Q + Sync) -> ImageBuffer<Q> {
+            let mut image = ImageBuffer::new(self.cols(), self.rows());
+            image
+                .par_raw_rows_mut()
+                .zip(self.par_raw_rows())
+                .for_each(|(target, row)| {
+                    let pixels = row.chunks_exact(P::CHANNELS);
+                    for (target, pixel) in target.chunks_exact_mut(Q::CHANNELS).zip(pixels) {
+                        f(P::from_channels(pixel)).write_channels(target);
+                    }
+                });
+            image
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Color,
+        image::{GrayImage, Image},
+        rgb,
+    };
+
+    fn numbered(cols: usize, rows: usize) -> GrayImage {
+        GrayImage::from_raw(cols, rows, (0..(cols * rows) as u8).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_pixels() {
+        let mut img = numbered(3, 2);
+        assert_eq!(img.pixels().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(img.enumerate_pixels().nth(4), Some((1, 1, 4)));
+
+        for mut pixel in img.pixels_mut() {
+            let value = pixel.get();
+            pixel.set(value * 2);
+        }
+        assert_eq!(img.as_raw(), [0, 2, 4, 6, 8, 10]);
+
+        let rows: Vec<&[u8]> = img.raw_rows().collect();
+        assert_eq!(rows, vec![&[0, 2, 4][..], &[6, 8, 10][..]]);
+        for row in img.raw_rows_mut() {
+            row.reverse();
+        }
+        assert_eq!(img.as_raw(), [4, 2, 0, 10, 8, 6]);
+    }
+
+    #[test]
+    fn test_row_iter() {
+        let mut img = numbered(3, 2);
+        let rows: Vec<Vec<u8>> = img.row_iter().map(Iterator::collect).collect();
+        assert_eq!(rows, vec![vec![0, 1, 2], vec![3, 4, 5]]);
+
+        for (y, row) in img.row_iter_mut().enumerate() {
+            for mut pixel in row {
+                let value = pixel.get();
+                pixel.set(value + 10 * y as u8);
+            }
+        }
+        assert_eq!(img.as_raw(), [0, 1, 2, 13, 14, 15]);
+
+        let mut view = img.view_mut(1, 0, 2, 2).unwrap();
+        for mut row in view.row_iter_mut() {
+            row.next().unwrap().set(99);
+        }
+        let rows: Vec<Vec<u8>> = view.row_iter().map(Iterator::collect).collect();
+        assert_eq!(rows, vec![vec![99, 2], vec![99, 15]]);
+        assert_eq!(img.view(0, 1, 3, 1).unwrap().row_iter().count(), 1);
+
+        // empty rows are still rows
+        let mut empty = GrayImage::new(0, 3);
+        assert_eq!(empty.raw_rows().count(), 3);
+        assert_eq!(empty.raw_rows_mut().count(), 3);
+        assert_eq!(empty.row_iter_mut().count(), 3);
+    }
+
+    #[test]
+    fn test_view_pixels_mut() {
+        let mut img = numbered(4, 3);
+        let mut view = img.view_mut(1, 1, 2, 2).unwrap();
+        for mut pixel in view.pixels_mut() {
+            pixel.set(99);
+        }
+        assert_eq!(view.raw_rows().count(), 2);
+        assert_eq!(img.as_raw(), [0, 1, 2, 3, 4, 99, 99, 7, 8, 99, 99, 11]);
+    }
+
+    #[test]
+    fn test_map() {
+        let img = numbered(2, 2);
+        let inverted = img.map(|value| Color::splat(255 - value));
+        assert_eq!(inverted.get(1, 1), Some(rgb!(252)));
+
+        let sum = img.zip_map(&inverted, |a, b| a.saturating_add(b.r));
+        assert_eq!(sum.as_raw(), [255; 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zip_map_dimensions() {
+        Image::new(2, 1).zip_map(&Image::new(1, 2), |a, _| a);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel() {
+        use rayon::prelude::*;
+
+        let mut img = numbered(16, 8);
+        img.par_raw_rows_mut()
+            .enumerate()
+            .for_each(|(y, row)| row.fill(y as u8));
+        assert_eq!(img.get(5, 7), Some(7));
+        assert_eq!(img.par_raw_rows().count(), 8);
+
+        let mut empty = GrayImage::new(0, 3);
+        assert_eq!(empty.par_raw_rows().count(), 3);
+        assert_eq!(empty.par_raw_rows_mut().count(), 3);
+
+        let mapped = img.par_map(|value| value * 2);
+        assert_eq!(mapped, img.map(|value| value * 2));
+    }
+}
@@ -1,35 +1,132 @@
 //! Module for working with images.
 
+mod border;
+mod buffer;
+mod deep;
+mod file;
+mod float;
+mod gray;
+mod iter;
+mod pixel;
 mod representation;
+mod resize;
+mod rgba;
+mod stream;
+mod transform;
+mod view;
+mod warp;
 
-use crate::{files::FileTypes, ppm::parse_ppm6};
+use crate::{
+    bmp::try_parse_bmp,
+    error::Result,
+    farbfeld::try_parse_farbfeld,
+    files::FileTypes,
+    gif::try_parse_gif,
+    hdr::try_parse_hdr,
+    jpeg::try_parse_jpeg,
+    pam::try_parse_pam,
+    png::try_parse_png,
+    ppm::{
+        try_parse_pbm1, try_parse_pbm4, try_parse_pgm2, try_parse_pgm5, try_parse_ppm3,
+        try_parse_ppm6,
+    },
+    qoi::try_parse_qoi,
+    tga::try_parse_tga,
+    tiff::try_parse_tiff,
+};
 
+pub use self::border::*;
+pub use self::buffer::*;
+pub use self::deep::*;
+pub use self::file::*;
+pub use self::float::*;
+pub use self::gray::*;
+pub use self::iter::*;
+pub use self::pixel::*;
 pub use self::representation::*;
+pub use self::resize::*;
+pub use self::rgba::*;
+pub use self::stream::*;
+pub use self::view::*;
 
 /// Try to parse a given image from a vector of u8. The format will be determined from the magic
-/// constant at the head of the file.
+/// constant at the head of the file. This function will panic, if the image is not valid. See
+/// `try_parse_image` for a fallible variant.
 pub fn parse_image(inp: Vec<u8>) -> Image {
-    let mut lines = inp.split(|val| *val == b'\n');
-
-    let Some(magic_constant) = lines.next() else {
-        panic!("No magic constant provided");
-    };
+    try_parse_image(inp).unwrap_or_else(|err| panic!("{}", err))
+}
 
-    let Ok(magic_constant) = FileTypes::try_from(magic_constant) else {
-        panic!("Invalid magic constant provided");
-    };
+/// Try to parse a given image from a vector of u8. The format will be determined from the magic
+/// constant at the head of the file.
+pub fn try_parse_image(inp: Vec<u8>) -> Result<Image> {
+    let magic_constant = FileTypes::try_from(inp.as_slice())?;
 
-    match magic_constant {
-        FileTypes::PPM3 => panic!("PPM ASCII currently not supported"),
-        FileTypes::PPM6 => parse_ppm6(inp),
-    }
+    Ok(match magic_constant {
+        FileTypes::PBM1 => Image::from(&try_parse_pbm1(inp)?),
+        FileTypes::PGM2 => Image::from(&try_parse_pgm2(inp)?),
+        FileTypes::PPM3 => try_parse_ppm3(inp)?,
+        FileTypes::PBM4 => Image::from(&try_parse_pbm4(inp)?),
+        FileTypes::PGM5 => Image::from(&try_parse_pgm5(inp)?),
+        FileTypes::PPM6 => try_parse_ppm6(inp)?,
+        FileTypes::PAM7 => Image::from(&try_parse_pam(inp)?),
+        FileTypes::BMP => try_parse_bmp(inp)?,
+        FileTypes::PNG => Image::from(&try_parse_png(inp)?),
+        FileTypes::QOI => Image::from(&try_parse_qoi(inp)?),
+        FileTypes::JPEG => try_parse_jpeg(inp)?,
+        FileTypes::GIF => try_parse_gif(inp)?.swap_remove(0).image,
+        FileTypes::TIFF => try_parse_tiff(inp)?.swap_remove(0),
+        FileTypes::FARBFELD => Image::from(&try_parse_farbfeld(inp)?),
+        FileTypes::TGA => Image::from(&try_parse_tga(inp)?),
+        FileTypes::HDR => Image::from(&try_parse_hdr(inp)?),
+    })
 }
 
 /// Macro to load an image from the disk and automatically parse it depending on the provided magic
 /// constant.
+#[deprecated(note = "use Image::open instead")]
 #[macro_export]
 macro_rules! load_image {
     ($file_name:expr) => {
-        $crate::image::parse_image($crate::load_file!($file_name));
+        $crate::image::parse_image(
+            std::fs::read($file_name).expect("Should have been able to read the file"),
+        );
+    };
+}
+
+/// Macro to load an image from the disk and automatically parse it depending on the provided magic
+/// constant. Returns an error, if the file could not be read or parsed.
+#[macro_export]
+macro_rules! try_load_image {
+    ($file_name:expr) => {
+        $crate::try_load_file!($file_name).and_then($crate::image::try_parse_image)
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn test_try_parse_image_invalid_magic_constant() {
+        assert!(matches!(
+            try_parse_image(b"P9\n1 1\n".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+        assert!(matches!(
+            try_parse_image(b"P".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+    }
+
+    #[test]
+    fn test_try_load_image() {
+        assert!(matches!(
+            crate::try_load_image!("assets/does_not_exist.ppm"),
+            Err(Error::Io(_))
+        ));
+        let image = crate::try_load_image!("assets/test.ppm").unwrap();
+        assert_eq!(image.cols(), 3);
+        assert_eq!(image.rows(), 2);
+    }
+}
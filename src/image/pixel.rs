@@ -0,0 +1,230 @@
+//! Module containing the traits for the pixels of generic images.
+use std::fmt::Debug;
+
+use crate::color::{Color, Color16, ColorF32, GrayAlpha, Rgba};
+
+/// Trait for the type of a single channel (i.e., sample) of a pixel.
+pub trait Primitive: Copy + Default + PartialEq + Debug + Send + Sync + 'static {
+    /// Value of a channel with full intensity.
+    const MAX: Self;
+
+    /// Convert this sample to a floating point value, where 0 is no and 1 is full intensity.
+    fn to_unit(self) -> f32;
+
+    /// Convert a floating point value (see `to_unit`) to a sample. Integer samples are rounded and
+    /// clamped to their range.
+    fn from_unit(val: f32) -> Self;
+}
+
+impl Primitive for u8 {
+    const MAX: Self = u8::MAX;
+
+    fn to_unit(self) -> f32 {
+        self as f32 / u8::MAX as f32
+    }
+
+    fn from_unit(val: f32) -> Self {
+        (val.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8
+    }
+}
+
+impl Primitive for u16 {
+    const MAX: Self = u16::MAX;
+
+    fn to_unit(self) -> f32 {
+        self as f32 / u16::MAX as f32
+    }
+
+    fn from_unit(val: f32) -> Self {
+        (val.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+    }
+}
+
+impl Primitive for f32 {
+    const MAX: Self = 1.0;
+
+    fn to_unit(self) -> f32 {
+        self
+    }
+
+    /// Floating point samples are not clamped, so they can keep a high dynamic range.
+    fn from_unit(val: f32) -> Self {
+        val
+    }
+}
+
+/// Trait for the pixels of an image, which consist of `CHANNELS` samples of the type `Subpixel`.
+///
+/// Every pixel can be converted from and to RGBA with floating point samples (see
+/// `Primitive::to_unit`), which is used for converting images between pixel types. Converting to
+/// a pixel type with less channels or a smaller sample type is lossy: colors are reduced to their
+/// luma (ITU-R BT.601), alpha channels are dropped and samples are rounded.
+pub trait Pixel: Copy + Default + PartialEq + Debug + Send + Sync + 'static {
+    /// Type of a single channel.
+    type Subpixel: Primitive;
+
+    /// Number of channels of this pixel.
+    const CHANNELS: usize;
+
+    /// Create a pixel from its channels. The slice has to contain at least `CHANNELS` samples.
+    fn from_channels(channels: &[Self::Subpixel]) -> Self;
+
+    /// Write the channels of this pixel to the first `CHANNELS` samples of a slice.
+    fn write_channels(&self, channels: &mut [Self::Subpixel]);
+
+    /// Convert this pixel to RGBA with floating point samples.
+    fn to_rgba_unit(&self) -> [f32; 4];
+
+    /// Create a pixel from RGBA with floating point samples.
+    fn from_rgba_unit(rgba: [f32; 4]) -> Self;
+}
+
+/// Calculate the luma of floating point RGB samples according to ITU-R BT.601.
+fn luma_unit([r, g, b, _]: [f32; 4]) -> f32 {
+    0.299 * r + 0.587 * g + 0.114 * b
+}
+
+/// Implement `Pixel` for a grayscale primitive.
+macro_rules! impl_gray_pixel {
+    ($($t:ty),*) => {
+        $(
+            impl Pixel for $t {
+                type Subpixel = $t;
+                const CHANNELS: usize = 1;
+
+                fn from_channels(channels: &[$t]) -> Self {
+                    channels[0]
+                }
+
+                fn write_channels(&self, channels: &mut [$t]) {
+                    channels[0] = *self;
+                }
+
+                fn to_rgba_unit(&self) -> [f32; 4] {
+                    let val = self.to_unit();
+                    [val, val, val, 1.0]
+                }
+
+                fn from_rgba_unit(rgba: [f32; 4]) -> Self {
+                    <$t>::from_unit(luma_unit(rgba))
+                }
+            }
+        )*
+    };
+}
+
+impl_gray_pixel!(u8, u16, f32);
+
+/// Implement `Pixel` for an RGB color with the given sample type.
+macro_rules! impl_rgb_pixel {
+    ($($color:ty => $t:ty),*) => {
+        $(
+            impl Pixel for $color {
+                type Subpixel = $t;
+                const CHANNELS: usize = 3;
+
+                fn from_channels(channels: &[$t]) -> Self {
+                    Self::new(channels[0], channels[1], channels[2])
+                }
+
+                fn write_channels(&self, channels: &mut [$t]) {
+                    channels[..3].copy_from_slice(&[self.r, self.g, self.b]);
+                }
+
+                fn to_rgba_unit(&self) -> [f32; 4] {
+                    [self.r.to_unit(), self.g.to_unit(), self.b.to_unit(), 1.0]
+                }
+
+                fn from_rgba_unit([r, g, b, _]: [f32; 4]) -> Self {
+                    Self::new(<$t>::from_unit(r), <$t>::from_unit(g), <$t>::from_unit(b))
+                }
+            }
+        )*
+    };
+}
+
+impl_rgb_pixel!(Color => u8, Color16 => u16, ColorF32 => f32);
+
+impl Pixel for Rgba {
+    type Subpixel = u8;
+    const CHANNELS: usize = 4;
+
+    fn from_channels(channels: &[u8]) -> Self {
+        Self::new(channels[0], channels[1], channels[2], channels[3])
+    }
+
+    fn write_channels(&self, channels: &mut [u8]) {
+        channels[..4].copy_from_slice(&[self.r, self.g, self.b, self.a]);
+    }
+
+    fn to_rgba_unit(&self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a].map(u8::to_unit)
+    }
+
+    fn from_rgba_unit(rgba: [f32; 4]) -> Self {
+        let [r, g, b, a] = rgba.map(u8::from_unit);
+        Self::new(r, g, b, a)
+    }
+}
+
+impl Pixel for GrayAlpha {
+    type Subpixel = u8;
+    const CHANNELS: usize = 2;
+
+    fn from_channels(channels: &[u8]) -> Self {
+        Self::new(channels[0], channels[1])
+    }
+
+    fn write_channels(&self, channels: &mut [u8]) {
+        channels[..2].copy_from_slice(&[self.l, self.a]);
+    }
+
+    fn to_rgba_unit(&self) -> [f32; 4] {
+        let l = self.l.to_unit();
+        [l, l, l, self.a.to_unit()]
+    }
+
+    fn from_rgba_unit(rgba: [f32; 4]) -> Self {
+        Self::new(u8::from_unit(luma_unit(rgba)), u8::from_unit(rgba[3]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rgb, rgba};
+
+    use super::*;
+
+    fn convert<P: Pixel, Q: Pixel>(pixel: P) -> Q {
+        Q::from_rgba_unit(pixel.to_rgba_unit())
+    }
+
+    #[test]
+    fn test_pixel_channels() {
+        let mut channels = [0; 4];
+        rgba!(1, 2, 3, 4).write_channels(&mut channels);
+        assert_eq!(channels, [1, 2, 3, 4]);
+        assert_eq!(Color::from_channels(&channels), rgb!(1, 2, 3));
+        assert_eq!(GrayAlpha::from_channels(&channels), GrayAlpha::new(1, 2));
+        assert_eq!(<u8 as Pixel>::from_channels(&channels), 1);
+    }
+
+    #[test]
+    fn test_pixel_conversion() {
+        // lossless conversions
+        let color = rgb!(0, 128, 255);
+        assert_eq!(convert::<_, Color16>(color), Color16::from(color));
+        assert_eq!(convert::<_, Color>(convert::<_, Color16>(color)), color);
+        assert_eq!(convert::<_, Color>(convert::<_, ColorF32>(color)), color);
+        assert_eq!(convert::<_, Rgba>(color), rgba!(0, 128, 255));
+        assert_eq!(convert::<_, u16>(200u8), 200 * 257);
+        assert_eq!(convert::<_, GrayAlpha>(17u8), GrayAlpha::new(17, 255));
+
+        // lossy conversions
+        assert_eq!(convert::<_, u8>(rgb!(255, 0, 0)), rgb!(255, 0, 0).luma());
+        assert_eq!(convert::<_, Color>(rgba!(1, 2, 3, 4)), rgb!(1, 2, 3));
+        assert_eq!(convert::<_, u8>(0x8140u16), 129);
+        assert_eq!(convert::<_, u8>(2.0f32), 255);
+        assert_eq!(convert::<_, f32>(2.0f32), 2.0);
+    }
+}
@@ -1,51 +1,18 @@
 //! Module containing the internal representation of images.
-use crate::{color::Color, geometry::vec::Vec2d, ppm::PNM, shapes::Shape};
+use crate::{
+    color::Color,
+    geometry::vec::Vec2d,
+    ppm::{encode_ascii_pnm, encode_ascii_row, pnm_header, PBM, PGM, PNM},
+    shapes::Shape,
+};
 
-/// Struct for representing an image.
-pub struct Image {
-    cols: usize,
-    rows: usize,
-    pixels: Vec<Vec<Color>>,
-}
-
-impl Image {
-    /// Create a new image with the specified width and height, where all pixels are filled with
-    /// black.
-    pub fn new(cols: usize, rows: usize) -> Self {
-        let pixels = vec![vec![Color::default(); cols]; rows];
-        Self { cols, rows, pixels }
-    }
-
-    /// Get the number of columns of this image.
-    pub fn cols(&self) -> usize {
-        self.cols
-    }
-
-    /// Get the number of rows of this image.
-    pub fn rows(&self) -> usize {
-        self.rows
-    }
-
-    /// Get the color at the specified index, or None, if the index it out of bounds.
-    pub fn get(&self, x: usize, y: usize) -> Option<Color> {
-        let Some(row) = self.pixels.get(y) else {
-             return None;
-        };
-        return row.get(x).copied();
-    }
+use super::{GrayImage, ImageBuffer};
 
-    /// Set the color of a pixel at the specified coordinates.
-    pub fn set(&mut self, x: usize, y: usize, color: &Color) {
-        let Some(row) = self.pixels.get_mut(y) else {
-             return;
-        };
-        if x < row.len() {
-            row[x] = *color;
-        } else {
-            // TODO: Return error if out of bounds
-        }
-    }
+/// Type for representing an image with 8bit RGB pixels, i.e., the default image type of this
+/// library. See `ImageBuffer` for the generic representation.
+pub type Image = ImageBuffer<Color>;
 
+impl Image {
     /// Get the color at a specified pixel.
     /// The semantics are the same as for Image::get.
     pub fn at(&self, vector: Vec2d) -> Option<Color> {
@@ -55,11 +22,6 @@ impl Image {
         }
     }
 
-    /// Fill the entire image with one color.
-    pub fn fill_with(&mut self, color: &Color) {
-        self.pixels = vec![vec![*color; self.cols]; self.rows];
-    }
-
     /// Draw a given shape to the picture.
     pub fn draw(&mut self, shape: &dyn Shape) {
         shape.draw(self);
@@ -68,46 +30,43 @@ impl Image {
 
 impl PNM for Image {
     fn to_pnm_p3(&self) -> String {
-        // add magic number
-        let mut ppm = "P3\n".to_string();
-        // add dimensions of the picture
-        ppm.push_str(&format!("{} {}\n", self.cols, self.rows));
-        // add max value
-        ppm.push_str(&format!("{}\n", u8::max_value()));
-
-        // add rows after each other
-        for row in &self.pixels {
-            let mut row_str = "".to_string();
-            for pixel in row {
-                row_str.push_str(&format!("{} ", pixel.to_pnm_p3()));
-            }
-            ppm.push_str(&format!("{}\n", row_str));
-        }
-        ppm
+        let header = pnm_header("P3", self.cols(), self.rows(), Some(u8::MAX as usize));
+        encode_ascii_pnm(header, self.rows(), |y, out| {
+            encode_ascii_row(self.raw_row(y), out)
+        })
     }
 
     fn to_pnm_p6(&self) -> Vec<u8> {
-        // add magic number
-        let mut ppm = "P6\n".to_string();
-        // add dimensions of the picture
-        ppm.push_str(&format!("{} {}\n", self.cols, self.rows));
-        // add max value
-        ppm.push_str(&format!("{}\n", u8::max_value()));
-        let mut ppm = ppm.as_bytes().to_owned();
-        for row in &self.pixels {
-            let mut row_vec = vec![];
-            for pixel in row {
-                row_vec.append(&mut pixel.to_pnm_p6());
-            }
-            ppm.append(&mut row_vec);
-        }
+        let mut ppm =
+            pnm_header("P6", self.cols(), self.rows(), Some(u8::MAX as usize)).into_bytes();
+        ppm.extend_from_slice(self.as_raw());
         ppm
     }
 }
 
+impl PBM for Image {
+    fn to_pnm_p1(&self) -> String {
+        GrayImage::from(self).to_pnm_p1()
+    }
+
+    fn to_pnm_p4(&self) -> Vec<u8> {
+        GrayImage::from(self).to_pnm_p4()
+    }
+}
+
+impl PGM for Image {
+    fn to_pnm_p2(&self) -> String {
+        GrayImage::from(self).to_pnm_p2()
+    }
+
+    fn to_pnm_p5(&self) -> Vec<u8> {
+        GrayImage::from(self).to_pnm_p5()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{rgb, vec2};
+    use crate::{error::Error, rgb, vec2};
 
     use super::*;
 
@@ -116,10 +75,12 @@ mod tests {
         let img = Image::new(42, 17);
         assert_eq!(img.cols(), 42);
         assert_eq!(img.rows(), 17);
-        assert_eq!(img.pixels, vec![vec![Color::splat(0); 42]; 17]);
+        assert_eq!(img.stride(), 42 * 3);
+        assert_eq!(img.as_raw(), vec![0; 42 * 17 * 3]);
     }
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn test_image_set() {
         let mut img = Image::new(42, 17);
         img.set(10, 10, &rgb!(42, 42, 17));
@@ -129,6 +90,16 @@ mod tests {
         assert_eq!(pixel, Some(rgb!(42, 42, 17)));
     }
 
+    #[test]
+    fn test_image_set_by_value() {
+        let mut img = Image::new(42, 17);
+        let color = rgb!(42, 42, 17);
+        img.set(10, 10, color);
+        img.set(11, 10, color);
+        assert_eq!(img.get(10, 10), Some(color));
+        assert_eq!(img.get(11, 10), Some(color));
+    }
+
     #[test]
     fn test_image_get_out_of_bounds() {
         let img = Image::new(42, 17);
@@ -144,9 +115,39 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn test_image_fill() {
         let mut img = Image::new(42, 17);
         img.fill_with(&rgb!(17, 120, 42));
-        assert_eq!(img.pixels, vec![vec![rgb!(17, 120, 42); 42]; 17]);
+        assert_eq!(img.as_raw(), [17, 120, 42].repeat(42 * 17));
+    }
+
+    #[test]
+    fn test_image_fill_by_value() {
+        let mut img = Image::new(42, 17);
+        img.fill_with(rgb!(17, 120, 42));
+        assert_eq!(img.as_raw(), [17, 120, 42].repeat(42 * 17));
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
+    fn test_image_raw() {
+        let mut img = Image::from_raw(2, 2, (0..12).collect()).unwrap();
+        assert_eq!(img.get(1, 0), Some(rgb!(3, 4, 5)));
+        assert_eq!(img.get(0, 1), Some(rgb!(6, 7, 8)));
+
+        img.set(1, 1, &rgb!(42, 17, 1));
+        img.as_raw_mut()[0] = 99;
+        assert_eq!(img.get(0, 0), Some(rgb!(99, 1, 2)));
+        assert_eq!(img.into_raw()[9..], [42, 17, 1]);
+
+        assert!(matches!(
+            Image::from_raw(2, 2, vec![0; 11]),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            Image::from_raw(usize::MAX, 2, vec![]),
+            Err(Error::DimensionOverflow { .. })
+        ));
     }
 }
@@ -0,0 +1,282 @@
+//! Module containing the resampling of images.
+use std::f32::consts::PI;
+
+use super::{ImageBuffer, Pixel, Primitive};
+
+/// Filters for resampling images.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FilterType {
+    /// Use the nearest pixel. This is the fastest filter, but produces blocky and aliased results.
+    Nearest,
+    /// Average all pixels, which are covered by the area of a pixel. This is the preferred filter
+    /// for downscaling.
+    Area,
+    /// Interpolate linearly between the nearest 2x2 pixels.
+    Bilinear,
+    /// Interpolate with a cubic (Catmull-Rom) spline through the nearest 4x4 pixels.
+    Bicubic,
+    /// Interpolate with a windowed sinc over the nearest 6x6 pixels. This is the sharpest, but
+    /// slowest filter.
+    Lanczos3,
+}
+
+impl FilterType {
+    /// Get the radius of the kernel of this filter (in pixels of the source image) without
+    /// scaling.
+    pub(crate) fn support(&self) -> f32 {
+        match self {
+            FilterType::Nearest | FilterType::Area => 0.5,
+            FilterType::Bilinear => 1.0,
+            FilterType::Bicubic => 2.0,
+            FilterType::Lanczos3 => 3.0,
+        }
+    }
+
+    /// Evaluate the kernel of this filter at the given distance from its center.
+    pub(crate) fn kernel(&self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            FilterType::Nearest | FilterType::Area => {
+                if x < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            FilterType::Bilinear => (1.0 - x).max(0.0),
+            FilterType::Bicubic => {
+                // Catmull-Rom spline, i.e., a cubic convolution with a = -0.5
+                if x < 1.0 {
+                    1.5 * x * x * x - 2.5 * x * x + 1.0
+                } else if x < 2.0 {
+                    -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+                } else {
+                    0.0
+                }
+            }
+            FilterType::Lanczos3 => {
+                if x < 3.0 {
+                    sinc(x) * sinc(x / 3.0)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Normalized sinc function.
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Weights of the source pixels, which contribute to a single target pixel along one axis.
+struct Weights {
+    /// Index of the first contributing source pixel.
+    start: usize,
+    weights: Vec<f32>,
+}
+
+/// Compute the weights for resampling an axis of `src` pixels to `dst` pixels. The centers of the
+/// pixels are aligned, i.e., the target pixel `i` is centered at `(i + 0.5) * src / dst - 0.5` in
+/// the source.
+fn axis_weights(src: usize, dst: usize, filter: FilterType) -> Vec<Weights> {
+    let scale = src as f32 / dst as f32;
+    (0..dst)
+        .map(|i| match filter {
+            FilterType::Nearest => Weights {
+                start: (((i as f32 + 0.5) * scale) as usize).min(src - 1),
+                weights: vec![1.0],
+            },
+            FilterType::Area => {
+                // weight each source pixel by its overlap with the area of the target pixel
+                let (left, right) = (i as f32 * scale, (i + 1) as f32 * scale);
+                let start = (left.floor() as usize).min(src - 1);
+                let end = (right.ceil() as usize).clamp(start + 1, src);
+                let weights = (start..end)
+                    .map(|j| (right.min((j + 1) as f32) - left.max(j as f32)).max(0.0))
+                    .collect();
+                normalized(start, weights)
+            }
+            _ => {
+                // widen the kernel when downscaling, so that all source pixels contribute
+                let filter_scale = scale.max(1.0);
+                let center = (i as f32 + 0.5) * scale - 0.5;
+                let radius = filter.support() * filter_scale;
+                let start = (center - radius).ceil().max(0.0) as usize;
+                let end = ((center + radius).floor() as usize + 1).min(src);
+                let start = start.min(end - 1);
+                let weights = (start..end)
+                    .map(|j| filter.kernel((j as f32 - center) / filter_scale))
+                    .collect();
+                normalized(start, weights)
+            }
+        })
+        .collect()
+}
+
+/// Normalize the weights to a sum of one. Weights outside of the image are dropped, so this keeps
+/// the brightness at the borders.
+fn normalized(start: usize, mut weights: Vec<f32>) -> Weights {
+    let sum: f32 = weights.iter().sum();
+    if sum != 0.0 {
+        weights.iter_mut().for_each(|weight| *weight /= sum);
+    }
+    Weights { start, weights }
+}
+
+impl<P: Pixel> ImageBuffer<P> {
+    /// Create a new image with the given dimensions, which is resampled from this image with the
+    /// given filter. The image is filtered separately in horizontal and vertical direction.
+    pub fn resize(&self, cols: usize, rows: usize, filter: FilterType) -> Self {
+        let mut image = ImageBuffer::new(cols, rows);
+        if self.cols() == 0 || self.rows() == 0 || cols == 0 || rows == 0 {
+            return image;
+        }
+        let channels = P::CHANNELS;
+        let horizontal = axis_weights(self.cols(), cols, filter);
+        let vertical = axis_weights(self.rows(), rows, filter);
+
+        // horizontal pass: `cols` x `self.rows()` pixels with floating point samples
+        let mut temp = Vec::with_capacity(cols * self.rows() * channels);
+        let mut acc = vec![0.0; channels];
+        for row in self.raw_rows() {
+            for Weights { start, weights } in &horizontal {
+                acc.fill(0.0);
+                for (pixel, weight) in row[start * channels..].chunks(channels).zip(weights) {
+                    for (acc, sample) in acc.iter_mut().zip(pixel) {
+                        *acc += sample.to_unit() * weight;
+                    }
+                }
+                temp.extend_from_slice(&acc);
+            }
+        }
+
+        // vertical pass
+        let temp_stride = cols * channels;
+        for (row, Weights { start, weights }) in image.raw_rows_mut().zip(&vertical) {
+            for (i, sample) in row.iter_mut().enumerate() {
+                let value = weights
+                    .iter()
+                    .enumerate()
+                    .map(|(j, weight)| temp[(start + j) * temp_stride + i] * weight)
+                    .sum();
+                *sample = P::Subpixel::from_unit(value);
+            }
+        }
+        image
+    }
+
+    /// Resize this image, so that it fits into a box of the given dimensions while keeping its
+    /// aspect ratio. The resulting image is at least one pixel wide and high, unless this image or
+    /// the box are empty.
+    pub fn resize_to_fit(&self, max_cols: usize, max_rows: usize, filter: FilterType) -> Self {
+        if self.cols() == 0 || self.rows() == 0 || max_cols == 0 || max_rows == 0 {
+            return ImageBuffer::new(0, 0);
+        }
+        let scale =
+            (max_cols as f64 / self.cols() as f64).min(max_rows as f64 / self.rows() as f64);
+        let cols = ((self.cols() as f64 * scale).round() as usize).clamp(1, max_cols);
+        let rows = ((self.rows() as f64 * scale).round() as usize).clamp(1, max_rows);
+        self.resize(cols, rows, filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        image::{GrayImage, Image},
+        rgb,
+    };
+
+    use super::*;
+
+    fn row(values: &[u8]) -> GrayImage {
+        GrayImage::from_raw(values.len(), 1, values.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_resize_nearest() {
+        let img = row(&[0, 10, 20, 30]);
+        assert_eq!(
+            img.resize(8, 1, FilterType::Nearest).into_raw(),
+            [0, 0, 10, 10, 20, 20, 30, 30]
+        );
+        assert_eq!(img.resize(2, 1, FilterType::Nearest).into_raw(), [10, 30]);
+    }
+
+    #[test]
+    fn test_resize_area() {
+        let img = row(&[0, 10, 20, 30]);
+        assert_eq!(img.resize(2, 1, FilterType::Area).into_raw(), [5, 25]);
+        assert_eq!(img.resize(1, 1, FilterType::Area).into_raw(), [15]);
+        // partial overlaps are weighted
+        assert_eq!(
+            row(&[0, 30, 60]).resize(2, 1, FilterType::Area).into_raw(),
+            [10, 50]
+        );
+    }
+
+    #[test]
+    fn test_resize_bilinear() {
+        // pixel centers are aligned, so the borders are not interpolated
+        let img = row(&[0, 255]);
+        assert_eq!(
+            img.resize(4, 1, FilterType::Bilinear).into_raw(),
+            [0, 64, 191, 255]
+        );
+
+        let img = GrayImage::from_raw(2, 2, vec![0, 100, 100, 200]).unwrap();
+        let resized = img.resize(4, 4, FilterType::Bilinear);
+        assert_eq!(resized.get(0, 0), Some(0));
+        assert_eq!(resized.get(1, 1), Some(50));
+        assert_eq!(resized.get(3, 3), Some(200));
+    }
+
+    #[test]
+    fn test_resize_keeps_constant_images() {
+        let mut img = Image::new(7, 5);
+        img.fill_with(rgb!(17, 120, 42));
+        for filter in [
+            FilterType::Nearest,
+            FilterType::Area,
+            FilterType::Bilinear,
+            FilterType::Bicubic,
+            FilterType::Lanczos3,
+        ] {
+            for (cols, rows) in [(3, 2), (7, 5), (16, 11)] {
+                let resized = img.resize(cols, rows, filter);
+                assert_eq!((resized.cols(), resized.rows()), (cols, rows));
+                assert!(resized.pixels().all(|pixel| pixel == rgb!(17, 120, 42)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_resize_identity() {
+        let img = GrayImage::from_raw(3, 2, vec![0, 50, 100, 150, 200, 250]).unwrap();
+        assert_eq!(img.resize(3, 2, FilterType::Bicubic), img);
+        assert_eq!(img.resize(3, 2, FilterType::Lanczos3), img);
+        assert_eq!(img.resize(0, 2, FilterType::Bilinear).cols(), 0);
+        assert_eq!(
+            Image::new(0, 0).resize(2, 2, FilterType::Area),
+            Image::new(2, 2)
+        );
+    }
+
+    #[test]
+    fn test_resize_to_fit() {
+        let img = Image::new(400, 300);
+        let fitted = img.resize_to_fit(200, 200, FilterType::Area);
+        assert_eq!((fitted.cols(), fitted.rows()), (200, 150));
+        let fitted = img.resize_to_fit(1000, 600, FilterType::Bilinear);
+        assert_eq!((fitted.cols(), fitted.rows()), (800, 600));
+        let fitted = Image::new(1000, 1).resize_to_fit(10, 10, FilterType::Nearest);
+        assert_eq!((fitted.cols(), fitted.rows()), (10, 1));
+        assert_eq!(img.resize_to_fit(0, 10, FilterType::Area).cols(), 0);
+    }
+}
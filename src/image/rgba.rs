@@ -0,0 +1,82 @@
+//! Module containing the internal representation of images with an alpha channel.
+use crate::{
+    color::Rgba,
+    ppm::{PBM, PGM, PNM},
+};
+
+use super::{Image, ImageBuffer};
+
+/// Type for representing an 8bit RGB image with an alpha channel.
+pub type RgbaImage = ImageBuffer<Rgba>;
+
+impl From<&Image> for RgbaImage {
+    /// Convert an RGB image into a fully opaque RGBA image.
+    fn from(image: &Image) -> Self {
+        image.map(Rgba::from)
+    }
+}
+
+impl From<&RgbaImage> for Image {
+    /// Convert an RGBA image into an RGB image by dropping the alpha channel.
+    fn from(rgba: &RgbaImage) -> Self {
+        rgba.map(|color| color.rgb())
+    }
+}
+
+impl PNM for RgbaImage {
+    fn to_pnm_p3(&self) -> String {
+        Image::from(self).to_pnm_p3()
+    }
+
+    fn to_pnm_p6(&self) -> Vec<u8> {
+        Image::from(self).to_pnm_p6()
+    }
+}
+
+impl PBM for RgbaImage {
+    fn to_pnm_p1(&self) -> String {
+        Image::from(self).to_pnm_p1()
+    }
+
+    fn to_pnm_p4(&self) -> Vec<u8> {
+        Image::from(self).to_pnm_p4()
+    }
+}
+
+impl PGM for RgbaImage {
+    fn to_pnm_p2(&self) -> String {
+        Image::from(self).to_pnm_p2()
+    }
+
+    fn to_pnm_p5(&self) -> Vec<u8> {
+        Image::from(self).to_pnm_p5()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rgb, rgba};
+
+    use super::*;
+
+    #[test]
+    fn test_rgba_image_new() {
+        let img = RgbaImage::new(42, 17);
+        assert_eq!(img.cols(), 42);
+        assert_eq!(img.rows(), 17);
+        assert_eq!(img.get(41, 16), Some(Rgba::default()));
+    }
+
+    #[test]
+    fn test_rgba_image_conversion() {
+        let mut img = Image::new(2, 1);
+        img.set(1, 0, rgb!(1, 2, 3));
+
+        let mut rgba = RgbaImage::from(&img);
+        assert_eq!(rgba.get(0, 0), Some(rgba!(0, 0, 0)));
+        assert_eq!(rgba.get(1, 0), Some(rgba!(1, 2, 3)));
+
+        rgba.set(0, 0, rgba!(4, 5, 6, 7));
+        assert_eq!(Image::from(&rgba).get(0, 0), Some(rgb!(4, 5, 6)));
+    }
+}
@@ -0,0 +1,573 @@
+//! Module for reading and writing images from and to arbitrary streams.
+use std::io::{Read, Write};
+
+use crate::{
+    bmp::BMP,
+    color::{u16_to_u8, Color},
+    error::{checked_samples, Error, Result},
+    farbfeld::{color_samples, encode_farbfeld_row, farbfeld_header},
+    gif::GIF,
+    hdr::HDR,
+    jpeg::JPEG,
+    pam::{TupleType, PAM},
+    png::PNG,
+    ppm::{
+        check_max_value, check_sample, encode_ascii_row, encode_p1_row, encode_p4_row, pnm_header,
+        scale_to_u8,
+    },
+    qoi::QOI,
+    tga::TGA,
+    tiff::TIFF,
+};
+
+use super::{try_parse_image, Image, ImageF32, SaveOptions};
+
+/// Formats an image can be written in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// ASCII bitmap (P1).
+    Pbm1,
+    /// ASCII graymap (P2).
+    Pgm2,
+    /// ASCII pixmap (P3).
+    Ppm3,
+    /// Binary bitmap (P4).
+    Pbm4,
+    /// Binary graymap (P5).
+    Pgm5,
+    /// Binary pixmap (P6).
+    Ppm6,
+    /// Portable arbitrary map (P7) with the RGB tuple type.
+    Pam,
+    /// 24bit BMP.
+    Bmp,
+    Png,
+    Qoi,
+    /// Baseline JPEG.
+    Jpeg,
+    /// Single frame GIF.
+    Gif,
+    Tiff,
+    Tga,
+    Farbfeld,
+    /// Radiance HDR.
+    Hdr,
+}
+
+impl Image {
+    /// Read an image from a reader. The format will be determined from the magic constant at the
+    /// head of the stream. Binary PNM (P4, P5 & P6) and farbfeld images are decoded row by row,
+    /// whereas all other formats are read to the end of the stream before decoding.
+    ///
+    /// Since the header of PNM files is read byte by byte, the reader should be buffered (e.g.,
+    /// with a `std::io::BufReader`).
+    pub fn read_from(mut reader: impl Read) -> Result<Image> {
+        let mut prefix = [0; 8];
+        let len = read_fully(&mut reader, &mut prefix)?;
+        let prefix = &prefix[..len];
+
+        match prefix {
+            [b'P', magic @ (b'4' | b'5' | b'6'), ..] => {
+                read_pnm(&mut (&prefix[2..]).chain(reader), *magic)
+            }
+            b"farbfeld" => read_farbfeld(&mut reader),
+            _ => {
+                let mut inp = prefix.to_vec();
+                reader.read_to_end(&mut inp)?;
+                try_parse_image(inp)
+            }
+        }
+    }
+
+    /// Write this image in the given format to a writer. PNM and farbfeld images are encoded row by
+    /// row, whereas all other formats are encoded completely before writing.
+    ///
+    /// Returns an error without writing anything, if the dimensions of this image can not be
+    /// represented in the format (e.g., GIF, JPEG and TGA images are limited to 65535x65535
+    /// pixels) or if the format does not allow empty images (BMP, PNG, QOI and JPEG).
+    pub fn write_to(&self, writer: impl Write, format: ImageFormat) -> Result<()> {
+        self.write_with(writer, format, &SaveOptions::default())
+    }
+
+    /// Write this image in the given format to a writer, where the format specific options are
+    /// taken from `options`. See `write_to` for details.
+    pub fn write_with(
+        &self,
+        mut writer: impl Write,
+        format: ImageFormat,
+        options: &SaveOptions,
+    ) -> Result<()> {
+        self.check_dimensions(format)?;
+        let max_value = Some(u8::MAX as usize);
+        match format {
+            ImageFormat::Pbm1 => {
+                writer.write_all(pnm_header("P1", self.cols(), self.rows(), None).as_bytes())?;
+                self.write_rows(&mut writer, |row, line| {
+                    encode_p1_row(row.iter().map(Color::luma), line)
+                })?;
+            }
+            ImageFormat::Pgm2 => {
+                writer
+                    .write_all(pnm_header("P2", self.cols(), self.rows(), max_value).as_bytes())?;
+                self.write_rows(&mut writer, |row, line| {
+                    encode_ascii_row(row.iter().map(Color::luma), line)
+                })?;
+            }
+            ImageFormat::Ppm3 => {
+                writer
+                    .write_all(pnm_header("P3", self.cols(), self.rows(), max_value).as_bytes())?;
+                self.write_rows(&mut writer, |row, line| {
+                    encode_ascii_row(
+                        row.iter().flat_map(|color| [color.r, color.g, color.b]),
+                        line,
+                    )
+                })?;
+            }
+            ImageFormat::Pbm4 => {
+                writer.write_all(pnm_header("P4", self.cols(), self.rows(), None).as_bytes())?;
+                self.write_rows(&mut writer, |row, line| {
+                    encode_p4_row(row.iter().map(Color::luma), line)
+                })?;
+            }
+            ImageFormat::Pgm5 => {
+                writer
+                    .write_all(pnm_header("P5", self.cols(), self.rows(), max_value).as_bytes())?;
+                self.write_rows(&mut writer, |row, line| {
+                    line.extend(row.iter().map(Color::luma));
+                })?;
+            }
+            ImageFormat::Ppm6 => {
+                writer
+                    .write_all(pnm_header("P6", self.cols(), self.rows(), max_value).as_bytes())?;
+                self.write_rows(&mut writer, |row, line| {
+                    line.extend(row.iter().flat_map(|color| [color.r, color.g, color.b]));
+                })?;
+            }
+            ImageFormat::Farbfeld => {
+                writer.write_all(&farbfeld_header(self.cols(), self.rows()))?;
+                self.write_rows(&mut writer, |row, line| {
+                    encode_farbfeld_row(row.iter().copied().map(color_samples), line)
+                })?;
+            }
+            ImageFormat::Pam => writer.write_all(&self.to_pam(TupleType::Rgb))?,
+            ImageFormat::Bmp => writer.write_all(&self.to_bmp24())?,
+            ImageFormat::Png => writer.write_all(&self.to_png())?,
+            ImageFormat::Qoi => writer.write_all(&self.to_qoi())?,
+            ImageFormat::Jpeg => writer.write_all(&self.try_to_jpeg(&options.jpeg)?)?,
+            ImageFormat::Gif => writer.write_all(&self.try_to_gif(&options.gif)?)?,
+            ImageFormat::Tiff => writer.write_all(&self.try_to_tiff(&options.tiff)?)?,
+            ImageFormat::Tga => writer.write_all(&self.try_to_tga(&options.tga)?)?,
+            ImageFormat::Hdr => writer.write_all(&ImageF32::from(self).to_hdr())?,
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Check, that this image can be written in the given format and read back by this crate.
+    fn check_dimensions(&self, format: ImageFormat) -> Result<()> {
+        let (width, height) = (self.cols(), self.rows());
+        if (width == 0 || height == 0)
+            && matches!(
+                format,
+                ImageFormat::Bmp | ImageFormat::Png | ImageFormat::Qoi | ImageFormat::Jpeg
+            )
+        {
+            return Err(Error::UnsupportedFormat(format!(
+                "{:?} images can not be empty ({}x{})",
+                format, width, height
+            )));
+        }
+
+        // the GIF, JPEG, TGA and TIFF encoders check their limits themselves
+        let max_dimension = match format {
+            ImageFormat::Bmp | ImageFormat::Png => i32::MAX as usize,
+            ImageFormat::Qoi | ImageFormat::Farbfeld => u32::MAX as usize,
+            _ => usize::MAX,
+        };
+        // BMP files store offsets and sizes in 32 bits, so estimate the largest file, which can
+        // be written (including the padding of rows)
+        let max_len = match format {
+            ImageFormat::Bmp => {
+                checked_samples(checked_samples(width, 3, 1)?.div_ceil(4) * 4, height, 1)?
+                    .checked_add(54)
+            }
+            _ => Some(0),
+        };
+        if width > max_dimension
+            || height > max_dimension
+            || max_len.is_none_or(|len| len > u32::MAX as usize)
+        {
+            return Err(Error::DimensionOverflow { width, height });
+        }
+        Ok(())
+    }
+
+    /// Encode the rows of this image one after another with `encode` and write them to the
+    /// writer. The line buffer is reused for all rows.
+    fn write_rows(
+        &self,
+        writer: &mut impl Write,
+        encode: impl Fn(&[Color], &mut Vec<u8>),
+    ) -> Result<()> {
+        let mut row = Vec::with_capacity(self.cols());
+        let mut line = vec![];
+        for y in 0..self.rows() {
+            row.clear();
+            row.extend((0..self.cols()).map(|x| self.get(x, y).unwrap_or_default()));
+            line.clear();
+            encode(&row, &mut line);
+            writer.write_all(&line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decode a binary PNM image (without its magic constant) row by row.
+fn read_pnm(reader: &mut impl Read, magic: u8) -> Result<Image> {
+    let width = next_header_number(reader, "Width")?;
+    let height = next_header_number(reader, "Height")?;
+    let max_value = if magic == b'4' {
+        1
+    } else {
+        next_header_number(reader, "Max value")?
+    };
+    check_max_value(max_value)?;
+
+    let channels = if magic == b'6' { 3 } else { 1 };
+    let bytes_per_sample = if max_value > u8::MAX as usize { 2 } else { 1 };
+    let row_len = if magic == b'4' {
+        // every row is padded to a full byte
+        width.div_ceil(8)
+    } else {
+        checked_samples(width, channels, bytes_per_sample)?
+    };
+    let expected = checked_samples(row_len, height, 1)?;
+    // the image has no samples, so there is nothing to read
+    if row_len == 0 {
+        return Image::from_raw(width, height, vec![]);
+    }
+
+    // the buffers grow with the rows, which are actually read, so that a header with huge
+    // dimensions cannot allocate more memory than the input provides
+    let mut data = vec![];
+    let mut row = vec![];
+    for y in 0..height {
+        read_row(reader, &mut row, row_len, y, expected)?;
+        if magic == b'4' {
+            for x in 0..width {
+                let bit = (row[x / 8] >> (7 - x % 8)) & 1;
+                data.extend_from_slice(&[if bit == 1 { 0 } else { u8::MAX }; 3]);
+            }
+            continue;
+        }
+
+        for sample in row.chunks_exact(bytes_per_sample) {
+            let sample = match sample {
+                [hi, lo] => u16::from_be_bytes([*hi, *lo]),
+                _ => sample[0] as u16,
+            };
+            let sample = scale_to_u8(check_sample(sample as usize, max_value)?, max_value);
+            if channels == 3 {
+                data.push(sample);
+            } else {
+                data.extend_from_slice(&[sample; 3]);
+            }
+        }
+    }
+    Image::from_raw(width, height, data)
+}
+
+/// Decode a farbfeld image (without its magic constant) row by row.
+fn read_farbfeld(reader: &mut impl Read) -> Result<Image> {
+    let mut header = [0; 8];
+    let len = read_fully(reader, &mut header)?;
+    if len < header.len() {
+        return Err(Error::TruncatedData {
+            expected: 16,
+            actual: 8 + len,
+        });
+    }
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+
+    let row_len = checked_samples(width, 1, 8)?;
+    let expected = checked_samples(row_len, height, 1)?;
+
+    if row_len == 0 {
+        return Image::from_raw(width, height, vec![]);
+    }
+
+    // see `read_pnm` for why the buffers are not allocated upfront
+    let mut data = vec![];
+    let mut row = vec![];
+    for y in 0..height {
+        read_row(reader, &mut row, row_len, y, expected)?;
+        for pixel in row.chunks_exact(8) {
+            let sample = |c: usize| u16_to_u8(u16::from_be_bytes([pixel[2 * c], pixel[2 * c + 1]]));
+            data.extend_from_slice(&[sample(0), sample(1), sample(2)]);
+        }
+    }
+    Image::from_raw(width, height, data)
+}
+
+/// Read the `y`th row of `row_len` bytes of a raster with `expected` bytes in total. The row buffer
+/// only grows as far as the reader provides data.
+fn read_row(
+    reader: &mut impl Read,
+    row: &mut Vec<u8>,
+    row_len: usize,
+    y: usize,
+    expected: usize,
+) -> Result<()> {
+    row.clear();
+    reader.take(row_len as u64).read_to_end(row)?;
+    if row.len() < row_len {
+        return Err(Error::TruncatedData {
+            expected,
+            actual: y * row_len + row.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Read the next number of a PNM header, skipping leading whitespace and comments. The number has
+/// to be terminated by exactly one whitespace, which is consumed as well.
+fn next_header_number(reader: &mut impl Read, name: &str) -> Result<usize> {
+    let mut byte = next_header_byte(reader)?;
+    loop {
+        if byte == b'#' {
+            while byte != b'\n' && byte != b'\r' {
+                byte = next_header_byte(reader)?;
+            }
+        } else if !byte.is_ascii_whitespace() {
+            break;
+        }
+        byte = next_header_byte(reader)?;
+    }
+
+    let mut number: usize = 0;
+    let mut digits = 0;
+    while byte.is_ascii_digit() {
+        number = number
+            .checked_mul(10)
+            .and_then(|number| number.checked_add((byte - b'0') as usize))
+            .ok_or_else(|| Error::InvalidHeader(format!("{} is too large", name)))?;
+        digits += 1;
+        byte = next_header_byte(reader)?;
+    }
+
+    if digits == 0 || !byte.is_ascii_whitespace() {
+        return Err(Error::InvalidHeader(format!(
+            "{} does not have a valid format! ({})",
+            name, byte as char
+        )));
+    }
+    Ok(number)
+}
+
+/// Read a single byte of a PNM header.
+fn next_header_byte(reader: &mut impl Read) -> Result<u8> {
+    let mut byte = [0];
+    if read_fully(reader, &mut byte)? == 0 {
+        return Err(Error::InvalidHeader("Unexpected end of header".to_string()));
+    }
+    Ok(byte[0])
+}
+
+/// Read from the reader until the buffer is full or the reader is exhausted. Returns the number of
+/// bytes read.
+fn read_fully(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        farbfeld::Farbfeld,
+        ppm::{PBM, PGM, PNM},
+        rgb,
+    };
+
+    use super::*;
+
+    fn gradient(cols: usize, rows: usize) -> Image {
+        let mut img = Image::new(cols, rows);
+        for y in 0..rows {
+            for x in 0..cols {
+                img.set(
+                    x,
+                    y,
+                    rgb!((x * 20) as u8, (y * 30) as u8, ((x + y) * 10) as u8),
+                );
+            }
+        }
+        img
+    }
+
+    fn write(img: &Image, format: ImageFormat) -> Vec<u8> {
+        let mut out = vec![];
+        img.write_to(&mut out, format).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_write_to_matches_pnm() {
+        let img = gradient(11, 5);
+        assert_eq!(write(&img, ImageFormat::Pbm1), img.to_pnm_p1().into_bytes());
+        assert_eq!(write(&img, ImageFormat::Pgm2), img.to_pnm_p2().into_bytes());
+        assert_eq!(write(&img, ImageFormat::Ppm3), img.to_pnm_p3().into_bytes());
+        assert_eq!(write(&img, ImageFormat::Pbm4), img.to_pnm_p4());
+        assert_eq!(write(&img, ImageFormat::Pgm5), img.to_pnm_p5());
+        assert_eq!(write(&img, ImageFormat::Ppm6), img.to_pnm_p6());
+        assert_eq!(write(&img, ImageFormat::Farbfeld), img.to_farbfeld());
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        let img = gradient(11, 5);
+        for format in [
+            ImageFormat::Ppm3,
+            ImageFormat::Ppm6,
+            ImageFormat::Pam,
+            ImageFormat::Bmp,
+            ImageFormat::Png,
+            ImageFormat::Qoi,
+            ImageFormat::Tiff,
+            ImageFormat::Tga,
+            ImageFormat::Farbfeld,
+        ] {
+            let out = write(&img, format);
+            let read = Image::read_from(out.as_slice()).unwrap();
+            assert_eq!((read.cols(), read.rows()), (11, 5), "{:?}", format);
+            for y in 0..5 {
+                for x in 0..11 {
+                    assert_eq!(read.get(x, y), img.get(x, y), "{:?}", format);
+                }
+            }
+        }
+
+        let gray = Image::read_from(write(&img, ImageFormat::Pgm5).as_slice()).unwrap();
+        assert_eq!(
+            gray.get(3, 2),
+            Some(Color::splat(img.get(3, 2).unwrap().luma()))
+        );
+    }
+
+    #[test]
+    fn test_read_from_header_comments() {
+        let inp = b"P5 # comment\n2\t1\n# another comment\n65535\n\xff\xff\x80\x00";
+        let img = Image::read_from(&inp[..]).unwrap();
+        assert_eq!(img.get(0, 0), Some(rgb!(255)));
+        assert_eq!(img.get(1, 0), Some(rgb!(128)));
+
+        let inp = b"P4\n10 1\n\x80\x40";
+        let img = Image::read_from(&inp[..]).unwrap();
+        assert_eq!(img.get(0, 0), Some(rgb!(0)));
+        assert_eq!(img.get(1, 0), Some(rgb!(255)));
+        assert_eq!(img.get(9, 0), Some(rgb!(0)));
+    }
+
+    #[test]
+    fn test_read_from_invalid() {
+        let img = gradient(4, 4);
+        let out = write(&img, ImageFormat::Ppm6);
+        assert!(matches!(
+            Image::read_from(&out[..out.len() - 5]),
+            Err(Error::TruncatedData {
+                expected: 48,
+                actual: 43
+            })
+        ));
+
+        let out = write(&img, ImageFormat::Farbfeld);
+        assert!(matches!(
+            Image::read_from(&out[..20]),
+            Err(Error::TruncatedData { .. })
+        ));
+
+        assert!(matches!(
+            Image::read_from(&b"P6\n4 x\n255\n"[..]),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            Image::read_from(&b"P5\n1 1\n15\n\x10"[..]),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            Image::read_from(&b"P6\n99999999999999999999999 1\n255\n"[..]),
+            Err(Error::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_from_huge_dimensions() {
+        // the headers ask for gigabytes, but no raster data follows
+        assert!(matches!(
+            Image::read_from(&b"P6\n100000 100000\n255\n"[..]),
+            Err(Error::TruncatedData { actual: 0, .. })
+        ));
+        assert!(matches!(
+            Image::read_from(&b"farbfeld\x00\x00\xff\xff\x00\x00\xff\xff"[..]),
+            Err(Error::TruncatedData { actual: 0, .. })
+        ));
+        assert!(matches!(
+            Image::read_from(&b"farbfeld\xff\xff\xff\xff\x00\x00\x00\x01\x00"[..]),
+            Err(Error::TruncatedData { actual: 1, .. })
+        ));
+
+        let img = Image::read_from(&b"P5\n0 99999999999\n255\n"[..]).unwrap();
+        assert_eq!((img.cols(), img.rows()), (0, 99999999999));
+    }
+
+    #[test]
+    fn test_write_to_invalid_dimensions() {
+        for format in [ImageFormat::Gif, ImageFormat::Jpeg, ImageFormat::Tga] {
+            let mut out = vec![];
+            assert!(matches!(
+                Image::new(70000, 1).write_to(&mut out, format),
+                Err(Error::DimensionOverflow {
+                    width: 70000,
+                    height: 1
+                })
+            ));
+            assert!(out.is_empty());
+        }
+
+        // empty images are only written, if they can be read back
+        for format in [
+            ImageFormat::Pbm4,
+            ImageFormat::Ppm6,
+            ImageFormat::Pam,
+            ImageFormat::Gif,
+            ImageFormat::Tiff,
+            ImageFormat::Tga,
+            ImageFormat::Farbfeld,
+            ImageFormat::Hdr,
+        ] {
+            for (cols, rows) in [(0, 0), (0, 5), (5, 0)] {
+                let img = Image::read_from(&write(&Image::new(cols, rows), format)[..]).unwrap();
+                assert_eq!((img.cols(), img.rows()), (cols, rows));
+            }
+        }
+        for format in [
+            ImageFormat::Bmp,
+            ImageFormat::Png,
+            ImageFormat::Qoi,
+            ImageFormat::Jpeg,
+        ] {
+            assert!(matches!(
+                Image::new(0, 5).write_to(vec![], format),
+                Err(Error::UnsupportedFormat(_))
+            ));
+        }
+    }
+}
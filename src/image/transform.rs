@@ -0,0 +1,153 @@
+//! Module containing lossless geometric transforms of images.
+use super::{BorderMode, ImageBuffer, Pixel};
+
+impl<P: Pixel> ImageBuffer<P> {
+    /// Create a new image with the given dimensions, where every pixel is copied from the pixel of
+    /// this image returned by `source`.
+    fn remap(&self, cols: usize, rows: usize, source: impl Fn(usize, usize) -> P) -> Self {
+        let mut image = ImageBuffer::new(cols, rows);
+        for (i, mut target) in image.pixels_mut().enumerate() {
+            target.set(source(i % cols, i / cols));
+        }
+        image
+    }
+
+    /// Get the pixel at the specified index, which has to be in bounds.
+    fn pixel(&self, x: usize, y: usize) -> P {
+        P::from_channels(&self.raw_row(y)[x * P::CHANNELS..])
+    }
+
+    /// Copy a rectangular region of this image into a new image, or return None, if the region is
+    /// not completely inside of this image.
+    pub fn crop(&self, x: usize, y: usize, cols: usize, rows: usize) -> Option<Self> {
+        Some(self.view(x, y, cols, rows)?.to_image())
+    }
+
+    /// Create a new image, which is mirrored at the vertical axis (i.e., left and right are
+    /// swapped).
+    pub fn flip_horizontal(&self) -> Self {
+        let last = self.cols().saturating_sub(1);
+        self.remap(self.cols(), self.rows(), |x, y| self.pixel(last - x, y))
+    }
+
+    /// Create a new image, which is mirrored at the horizontal axis (i.e., top and bottom are
+    /// swapped).
+    pub fn flip_vertical(&self) -> Self {
+        let mut data = Vec::with_capacity(self.as_raw().len());
+        for y in (0..self.rows()).rev() {
+            data.extend_from_slice(self.raw_row(y));
+        }
+        Self::from_raw(self.cols(), self.rows(), data)
+            .expect("flipped image should have the same dimensions")
+    }
+
+    /// Create a new image, which is rotated by 90 degrees clockwise.
+    pub fn rotate90(&self) -> Self {
+        let last = self.rows().saturating_sub(1);
+        self.remap(self.rows(), self.cols(), |x, y| self.pixel(y, last - x))
+    }
+
+    /// Create a new image, which is rotated by 180 degrees.
+    pub fn rotate180(&self) -> Self {
+        let (last_x, last_y) = (self.cols().saturating_sub(1), self.rows().saturating_sub(1));
+        self.remap(self.cols(), self.rows(), |x, y| {
+            self.pixel(last_x - x, last_y - y)
+        })
+    }
+
+    /// Create a new image, which is rotated by 270 degrees clockwise (i.e., 90 degrees
+    /// counterclockwise).
+    pub fn rotate270(&self) -> Self {
+        let last = self.cols().saturating_sub(1);
+        self.remap(self.rows(), self.cols(), |x, y| self.pixel(last - y, x))
+    }
+
+    /// Create a new image, which is mirrored at the main diagonal (i.e., rows and columns are
+    /// swapped).
+    pub fn transpose(&self) -> Self {
+        self.remap(self.rows(), self.cols(), |x, y| self.pixel(y, x))
+    }
+
+    /// Create a new image, which is extended by the given number of pixels at each side. The new
+    /// pixels are determined by the border mode.
+    pub fn pad(
+        &self,
+        top: usize,
+        right: usize,
+        bottom: usize,
+        left: usize,
+        border: BorderMode<P>,
+    ) -> Self {
+        let cols = left + self.cols() + right;
+        let rows = top + self.rows() + bottom;
+        self.remap(cols, rows, |x, y| {
+            self.get_with_border(
+                x as isize - left as isize,
+                y as isize - top as isize,
+                &border,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::{BorderMode, GrayImage};
+
+    fn numbered(cols: usize, rows: usize) -> GrayImage {
+        GrayImage::from_raw(cols, rows, (0..(cols * rows) as u8).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_crop() {
+        let img = numbered(4, 3);
+        assert_eq!(img.crop(1, 1, 2, 2).unwrap().into_raw(), [5, 6, 9, 10]);
+        assert!(img.crop(3, 0, 2, 1).is_none());
+
+        let empty = img.crop(1, 1, 0, 2).unwrap();
+        assert_eq!((empty.cols(), empty.rows()), (0, 2));
+        assert_eq!(
+            GrayImage::new(4, 4).crop(4, 4, 0, 0),
+            Some(GrayImage::new(0, 0))
+        );
+    }
+
+    #[test]
+    fn test_flip() {
+        // 0 1 2
+        // 3 4 5
+        let img = numbered(3, 2);
+        assert_eq!(img.flip_horizontal().into_raw(), [2, 1, 0, 5, 4, 3]);
+        assert_eq!(img.flip_vertical().into_raw(), [3, 4, 5, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let img = numbered(3, 2);
+
+        let rotated = img.rotate90();
+        assert_eq!((rotated.cols(), rotated.rows()), (2, 3));
+        assert_eq!(rotated.as_raw(), [3, 0, 4, 1, 5, 2]);
+        assert_eq!(img.rotate180().into_raw(), [5, 4, 3, 2, 1, 0]);
+        assert_eq!(img.rotate270().into_raw(), [2, 5, 1, 4, 0, 3]);
+        assert_eq!(img.transpose().into_raw(), [0, 3, 1, 4, 2, 5]);
+
+        assert_eq!(rotated.rotate270(), img);
+        assert_eq!(img.rotate90().rotate90(), img.rotate180());
+        assert_eq!(GrayImage::new(0, 2).rotate90().rows(), 0);
+    }
+
+    #[test]
+    fn test_pad() {
+        let img = numbered(2, 2);
+        let padded = img.pad(1, 0, 0, 2, BorderMode::Replicate);
+        assert_eq!((padded.cols(), padded.rows()), (4, 3));
+        assert_eq!(padded.into_raw(), [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 3]);
+
+        let padded = img.pad(0, 1, 1, 0, BorderMode::Constant(9));
+        assert_eq!(padded.into_raw(), [0, 1, 9, 2, 3, 9, 9, 9, 9]);
+
+        let padded = img.pad(0, 2, 0, 0, BorderMode::Wrap);
+        assert_eq!(padded.into_raw(), [0, 1, 0, 1, 2, 3, 2, 3]);
+    }
+}
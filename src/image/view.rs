@@ -0,0 +1,396 @@
+//! Module containing borrowed views of rectangular regions of images.
+use std::{borrow::Borrow, ops::Range};
+
+use crate::{color::Color, shapes::Shape};
+
+use super::{ImageBuffer, Pixel};
+
+/// Get the range of samples and the stride of a region with the given position and size in an
+/// image with the given dimensions, or None, if the region is not completely inside of the image.
+/// Empty regions have no samples and a stride of zero, so that all of their rows are empty.
+fn region(
+    (cols, rows, stride): (usize, usize, usize),
+    channels: usize,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> Option<(Range<usize>, usize)> {
+    if x.checked_add(width)? > cols || y.checked_add(height)? > rows {
+        return None;
+    }
+    if width == 0 || height == 0 {
+        return Some((0..0, 0));
+    }
+    let start = y * stride + x * channels;
+    Some((
+        start..start + (height - 1) * stride + width * channels,
+        stride,
+    ))
+}
+
+/// Read-only view of a rectangular region of an image, which does not copy any pixels.
+#[derive(Debug)]
+pub struct ImageView<'a, P: Pixel> {
+    cols: usize,
+    rows: usize,
+    stride: usize,
+    /// Samples from the first pixel of the view to the last one.
+    data: &'a [P::Subpixel],
+}
+
+/// Mutable view of a rectangular region of an image, which does not copy any pixels.
+#[derive(Debug)]
+pub struct ImageViewMut<'a, P: Pixel> {
+    cols: usize,
+    rows: usize,
+    stride: usize,
+    /// Samples from the first pixel of the view to the last one.
+    data: &'a mut [P::Subpixel],
+}
+
+impl<P: Pixel> Clone for ImageView<'_, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Pixel> Copy for ImageView<'_, P> {}
+
+impl<'a, P: Pixel> ImageView<'a, P> {
+    /// Get the number of columns of this view.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Get the number of rows of this view.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Get the number of samples between the start of two consecutive rows.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Get the pixel at the specified index (relative to this view), or None, if the index it out
+    /// of bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<P> {
+        (x < self.cols && y < self.rows)
+            .then(|| P::from_channels(&self.data[y * self.stride + x * P::CHANNELS..]))
+    }
+
+    /// Get a view of a region of this view, or None, if the region is not completely inside of
+    /// this view.
+    pub fn view(&self, x: usize, y: usize, cols: usize, rows: usize) -> Option<ImageView<'a, P>> {
+        let (range, stride) = region(
+            (self.cols, self.rows, self.stride),
+            P::CHANNELS,
+            x,
+            y,
+            cols,
+            rows,
+        )?;
+        Some(ImageView {
+            cols,
+            rows,
+            stride,
+            data: &self.data[range],
+        })
+    }
+
+    /// Get the samples of a single row. The index has to be in bounds.
+    pub(crate) fn raw_row(&self, y: usize) -> &'a [P::Subpixel] {
+        let data: &'a [P::Subpixel] = self.data;
+        &data[y * self.stride..y * self.stride + self.cols * P::CHANNELS]
+    }
+
+    /// Iterate over all pixels of this view row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = P> + 'a {
+        let view = *self;
+        (0..self.rows).flat_map(move |y| {
+            view.raw_row(y)
+                .chunks_exact(P::CHANNELS)
+                .map(P::from_channels)
+        })
+    }
+
+    /// Iterate over all pixels of this view row by row together with their coordinates (relative
+    /// to this view).
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item = (usize, usize, P)> + 'a {
+        let cols = self.cols.max(1);
+        self.pixels()
+            .enumerate()
+            .map(move |(i, pixel)| (i % cols, i / cols, pixel))
+    }
+
+    /// Copy the pixels of this view into a new image.
+    pub fn to_image(&self) -> ImageBuffer<P> {
+        let mut data = Vec::with_capacity(self.cols * self.rows * P::CHANNELS);
+        for y in 0..self.rows {
+            data.extend_from_slice(self.raw_row(y));
+        }
+        ImageBuffer::from_raw(self.cols, self.rows, data)
+            .expect("view should contain all samples of its region")
+    }
+}
+
+impl<'a, P: Pixel> ImageViewMut<'a, P> {
+    /// Get the number of columns of this view.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Get the number of rows of this view.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Get the number of samples between the start of two consecutive rows.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Get the samples from the first pixel of this view to the last one.
+    pub(crate) fn raw_mut(&mut self) -> &mut [P::Subpixel] {
+        self.data
+    }
+
+    /// Reborrow this view as read-only view.
+    pub fn as_view(&self) -> ImageView<'_, P> {
+        ImageView {
+            cols: self.cols,
+            rows: self.rows,
+            stride: self.stride,
+            data: self.data,
+        }
+    }
+
+    /// Get the pixel at the specified index (relative to this view), or None, if the index it out
+    /// of bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<P> {
+        self.as_view().get(x, y)
+    }
+
+    /// Set a pixel at the specified coordinates (relative to this view). Pixels outside of this
+    /// view are ignored.
+    pub fn set(&mut self, x: usize, y: usize, pixel: impl Borrow<P>) {
+        if x < self.cols && y < self.rows {
+            let offset = y * self.stride + x * P::CHANNELS;
+            pixel.borrow().write_channels(&mut self.data[offset..]);
+        }
+    }
+
+    /// Fill the entire view with one pixel.
+    pub fn fill_with(&mut self, pixel: impl Borrow<P>) {
+        let pixel = pixel.borrow();
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                self.set(x, y, pixel);
+            }
+        }
+    }
+
+    /// Get a read-only view of a region of this view, or None, if the region is not completely
+    /// inside of this view.
+    pub fn view(&self, x: usize, y: usize, cols: usize, rows: usize) -> Option<ImageView<'_, P>> {
+        self.as_view().view(x, y, cols, rows)
+    }
+
+    /// Get a mutable view of a region of this view, or None, if the region is not completely
+    /// inside of this view.
+    pub fn view_mut(
+        &mut self,
+        x: usize,
+        y: usize,
+        cols: usize,
+        rows: usize,
+    ) -> Option<ImageViewMut<'_, P>> {
+        let (range, stride) = region(
+            (self.cols, self.rows, self.stride),
+            P::CHANNELS,
+            x,
+            y,
+            cols,
+            rows,
+        )?;
+        Some(ImageViewMut {
+            cols,
+            rows,
+            stride,
+            data: &mut self.data[range],
+        })
+    }
+
+    /// Iterate over all pixels of this view row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = P> + '_ {
+        self.as_view().pixels()
+    }
+
+    /// Iterate over all pixels of this view row by row together with their coordinates (relative
+    /// to this view).
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item = (usize, usize, P)> + '_ {
+        self.as_view().enumerate_pixels()
+    }
+
+    /// Copy the pixels of this view into a new image.
+    pub fn to_image(&self) -> ImageBuffer<P> {
+        self.as_view().to_image()
+    }
+}
+
+impl ImageViewMut<'_, Color> {
+    /// Draw a given shape to this view. The shape is positioned relative to the view and clipped
+    /// at its borders.
+    pub fn draw(&mut self, shape: &dyn Shape) {
+        shape.draw(self);
+    }
+}
+
+impl<P: Pixel> ImageBuffer<P> {
+    /// Get a read-only view of the whole image.
+    pub fn as_view(&self) -> ImageView<'_, P> {
+        ImageView {
+            cols: self.cols(),
+            rows: self.rows(),
+            stride: self.stride(),
+            data: self.as_raw(),
+        }
+    }
+
+    /// Get a mutable view of the whole image.
+    pub fn as_view_mut(&mut self) -> ImageViewMut<'_, P> {
+        ImageViewMut {
+            cols: self.cols(),
+            rows: self.rows(),
+            stride: self.stride(),
+            data: self.as_raw_mut(),
+        }
+    }
+
+    /// Get a read-only view of a region of this image, or None, if the region is not completely
+    /// inside of this image.
+    pub fn view(&self, x: usize, y: usize, cols: usize, rows: usize) -> Option<ImageView<'_, P>> {
+        self.as_view().view(x, y, cols, rows)
+    }
+
+    /// Get a mutable view of a region of this image, or None, if the region is not completely
+    /// inside of this image.
+    pub fn view_mut(
+        &mut self,
+        x: usize,
+        y: usize,
+        cols: usize,
+        rows: usize,
+    ) -> Option<ImageViewMut<'_, P>> {
+        let (range, stride) = region(
+            (self.cols(), self.rows(), self.stride()),
+            P::CHANNELS,
+            x,
+            y,
+            cols,
+            rows,
+        )?;
+        Some(ImageViewMut {
+            cols,
+            rows,
+            stride,
+            data: &mut self.as_raw_mut()[range],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        image::{GrayImage, Image},
+        rgb,
+        shapes::Rectangle,
+        vec2,
+    };
+
+    fn numbered(cols: usize, rows: usize) -> GrayImage {
+        GrayImage::from_raw(cols, rows, (0..(cols * rows) as u8).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_view() {
+        let img = numbered(6, 5);
+        let view = img.view(1, 2, 4, 3).unwrap();
+        assert_eq!((view.cols(), view.rows(), view.stride()), (4, 3, 6));
+        assert_eq!(view.get(0, 0), Some(13));
+        assert_eq!(view.get(3, 2), Some(28));
+        assert_eq!(view.get(4, 0), None);
+
+        // views can be nested
+        let nested = view.view(2, 1, 2, 2).unwrap();
+        assert_eq!(nested.get(0, 0), Some(21));
+        assert_eq!(nested.pixels().collect::<Vec<_>>(), vec![21, 22, 27, 28]);
+        assert_eq!(nested.enumerate_pixels().last(), Some((1, 1, 28)));
+        assert_eq!(nested.to_image().into_raw(), vec![21, 22, 27, 28]);
+
+        assert!(img.view(3, 0, 4, 1).is_none());
+        assert!(view.view(0, 0, 4, 4).is_none());
+        assert!(img.view(usize::MAX, 0, 2, 1).is_none());
+        assert_eq!(img.view(6, 5, 0, 0).unwrap().pixels().count(), 0);
+    }
+
+    #[test]
+    fn test_empty_view() {
+        let mut img = numbered(4, 4);
+        for (cols, rows) in [(0, 3), (3, 0), (0, 0)] {
+            let view = img.view(1, 0, cols, rows).unwrap();
+            assert_eq!((view.cols(), view.rows()), (cols, rows));
+            assert_eq!(view.pixels().count(), 0);
+            assert_eq!(view.raw_rows().count(), rows);
+            assert!(view.raw_rows().all(|row| row.is_empty()));
+            assert_eq!(view.get(0, 0), None);
+
+            let image = view.to_image();
+            assert_eq!((image.cols(), image.rows()), (cols, rows));
+
+            let mut view = img.view_mut(1, 0, cols, rows).unwrap();
+            view.fill_with(99);
+            assert_eq!(view.pixels_mut().count(), 0);
+            assert_eq!(view.raw_rows_mut().count(), rows);
+            assert_eq!(view.row_iter_mut().count(), rows);
+            assert_eq!(view.to_image().cols(), cols);
+        }
+        assert_eq!(img, numbered(4, 4));
+    }
+
+    #[test]
+    fn test_view_mut() {
+        let mut img = numbered(6, 5);
+        {
+            let mut view = img.view_mut(1, 1, 4, 3).unwrap();
+            view.set(0, 0, 100);
+            view.set(4, 0, 100);
+
+            let mut nested = view.view_mut(1, 1, 2, 2).unwrap();
+            nested.fill_with(200);
+            assert_eq!(nested.get(1, 1), Some(200));
+            assert_eq!(view.get(1, 1), Some(200));
+        }
+        assert_eq!(img.get(1, 1), Some(100));
+        assert_eq!(img.get(5, 1), Some(11));
+        assert_eq!(img.get(2, 2), Some(200));
+        assert_eq!(img.get(3, 3), Some(200));
+        assert_eq!(img.get(4, 3), Some(22));
+        assert_eq!(img.get(2, 4), Some(26));
+    }
+
+    #[test]
+    fn test_view_draw() {
+        let mut img = Image::new(10, 10);
+        let mut view = img.view_mut(2, 3, 4, 4).unwrap();
+        view.draw(&Rectangle::new(vec2![2.0, 2.0], 10, 10, rgb!(255)));
+
+        assert_eq!(img.get(4, 5), Some(rgb!(255)));
+        assert_eq!(img.get(5, 6), Some(rgb!(255)));
+        // the rectangle is clipped at the borders of the view
+        assert_eq!(img.get(6, 6), Some(rgb!(0)));
+        assert_eq!(img.get(5, 7), Some(rgb!(0)));
+        assert_eq!(img.get(3, 4), Some(rgb!(0)));
+    }
+}
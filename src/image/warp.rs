@@ -0,0 +1,238 @@
+//! Module containing affine and perspective warping of images.
+use crate::{
+    geometry::{
+        mat::{HomogeneousMatrix, Mat},
+        vec::Vec2d,
+    },
+    vec2,
+};
+
+use super::{BorderMode, FilterType, ImageBuffer, Pixel, Primitive};
+
+impl<P: Pixel> ImageBuffer<P> {
+    /// Interpolate the pixel at a position between pixel centers. Pixels outside of the image are
+    /// determined by the border mode. `samples` and `channels` are scratch buffers with
+    /// `P::CHANNELS` elements.
+    fn interpolate(
+        &self,
+        (x, y): (f64, f64),
+        filter: FilterType,
+        border: &BorderMode<P>,
+        samples: &mut [f32],
+        channels: &mut [P::Subpixel],
+    ) -> P {
+        let filter = match filter {
+            FilterType::Nearest | FilterType::Area => {
+                return self.get_with_border(x.round() as isize, y.round() as isize, border)
+            }
+            filter => filter,
+        };
+        let support = filter.support() as f64;
+        let (x0, x1) = (
+            (x - support).ceil() as isize,
+            (x + support).floor() as isize,
+        );
+        let (y0, y1) = (
+            (y - support).ceil() as isize,
+            (y + support).floor() as isize,
+        );
+
+        samples.fill(0.0);
+        let mut sum = 0.0;
+        for j in y0..=y1 {
+            let weight_y = filter.kernel((j as f64 - y) as f32);
+            for i in x0..=x1 {
+                let weight = weight_y * filter.kernel((i as f64 - x) as f32);
+                if weight == 0.0 {
+                    continue;
+                }
+                self.get_with_border(i, j, border).write_channels(channels);
+                for (sample, channel) in samples.iter_mut().zip(channels.iter()) {
+                    *sample += channel.to_unit() * weight;
+                }
+                sum += weight;
+            }
+        }
+        for (channel, sample) in channels.iter_mut().zip(samples.iter()) {
+            *channel = P::Subpixel::from_unit(if sum != 0.0 { sample / sum } else { 0.0 });
+        }
+        P::from_channels(channels)
+    }
+
+    /// Create a new image with the given dimensions, where every pixel is mapped back into this
+    /// image with `source` and interpolated there.
+    fn warp(
+        &self,
+        cols: usize,
+        rows: usize,
+        filter: FilterType,
+        border: BorderMode<P>,
+        source: impl Fn(Vec2d) -> Option<Vec2d>,
+    ) -> Self {
+        let mut image = ImageBuffer::new(cols, rows);
+        let mut samples = vec![0.0; P::CHANNELS];
+        let mut channels = vec![P::Subpixel::default(); P::CHANNELS];
+        let outside = match border {
+            BorderMode::Constant(pixel) => pixel,
+            _ => P::default(),
+        };
+        for (i, mut target) in image.pixels_mut().enumerate() {
+            let pixel = match source(vec2![(i % cols) as f64, (i / cols) as f64]) {
+                Some(position) => self.interpolate(
+                    (position.x, position.y),
+                    filter,
+                    &border,
+                    &mut samples,
+                    &mut channels,
+                ),
+                None => outside,
+            };
+            target.set(pixel);
+        }
+        image
+    }
+
+    /// Create a new image with the given dimensions by applying an affine transform, which maps
+    /// pixel coordinates of this image to pixel coordinates of the new image. The last row of the
+    /// matrix is ignored (i.e., assumed to be `[0, 0, 1]`).
+    ///
+    /// Every pixel of the new image is mapped back with the inverse transform and interpolated
+    /// with the given filter (`Area` behaves like `Nearest`). Pixels outside of this image are
+    /// determined by the border mode. Returns None, if the transform is not invertible.
+    pub fn warp_affine(
+        &self,
+        transform: &Mat<f64, 3, 3>,
+        cols: usize,
+        rows: usize,
+        filter: FilterType,
+        border: BorderMode<P>,
+    ) -> Option<Self> {
+        let mut affine = *transform;
+        affine.matrix[2] = [0.0, 0.0, 1.0];
+        let inverse = affine.inverse()?;
+        Some(self.warp(cols, rows, filter, border, |position| {
+            Some(Vec2d::from(inverse * HomogeneousMatrix::from(position)))
+        }))
+    }
+
+    /// Create a new image with the given dimensions by applying a perspective transform (i.e., a
+    /// homography), which maps pixel coordinates of this image to pixel coordinates of the new
+    /// image.
+    ///
+    /// Every pixel of the new image is mapped back with the inverse transform and interpolated
+    /// with the given filter (`Area` behaves like `Nearest`). Pixels outside of this image are
+    /// determined by the border mode. Returns None, if the transform is not invertible.
+    pub fn warp_perspective(
+        &self,
+        transform: &Mat<f64, 3, 3>,
+        cols: usize,
+        rows: usize,
+        filter: FilterType,
+        border: BorderMode<P>,
+    ) -> Option<Self> {
+        let inverse = transform.inverse()?;
+        Some(self.warp(cols, rows, filter, border, |position| {
+            let mapped = inverse * HomogeneousMatrix::from(position);
+            let w = mapped.matrix[2][0];
+            // points on the horizon have no position in this image
+            (w.abs() > f64::EPSILON).then(|| Vec2d::from(mapped) / w)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{image::GrayImage, mat};
+
+    use super::*;
+
+    fn numbered(cols: usize, rows: usize) -> GrayImage {
+        GrayImage::from_raw(cols, rows, (0..(cols * rows) as u8).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_warp_affine_translate() {
+        let img = numbered(3, 3);
+        let translate = mat!([[1.0, 0.0, 1.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        let warped = img
+            .warp_affine(
+                &translate,
+                3,
+                3,
+                FilterType::Bilinear,
+                BorderMode::Constant(9),
+            )
+            .unwrap();
+        assert_eq!(warped.into_raw(), [9, 0, 1, 9, 3, 4, 9, 6, 7]);
+
+        let warped = img
+            .warp_affine(&translate, 3, 1, FilterType::Nearest, BorderMode::Wrap)
+            .unwrap();
+        assert_eq!(warped.into_raw(), [2, 0, 1]);
+    }
+
+    #[test]
+    fn test_warp_affine_matches_rotate() {
+        let img = numbered(4, 3);
+        // rotation by 90 degrees clockwise: (x, y) -> (rows - 1 - y, x)
+        let rotate = mat!([[0.0, -1.0, 2.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+        for filter in [
+            FilterType::Nearest,
+            FilterType::Bicubic,
+            FilterType::Lanczos3,
+        ] {
+            let warped = img
+                .warp_affine(&rotate, 3, 4, filter, BorderMode::Replicate)
+                .unwrap();
+            assert_eq!(warped, img.rotate90());
+        }
+    }
+
+    #[test]
+    fn test_warp_affine_interpolates() {
+        let img = GrayImage::from_raw(2, 1, vec![0, 200]).unwrap();
+        let scale = mat!([[2.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        let warped = img
+            .warp_affine(&scale, 3, 1, FilterType::Bilinear, BorderMode::Replicate)
+            .unwrap();
+        assert_eq!(warped.into_raw(), [0, 100, 200]);
+
+        let singular = mat!([[1.0, 2.0, 0.0], [2.0, 4.0, 0.0], [0.0, 0.0, 1.0]]);
+        assert!(img
+            .warp_affine(&singular, 2, 2, FilterType::Nearest, BorderMode::Replicate)
+            .is_none());
+    }
+
+    #[test]
+    fn test_warp_perspective() {
+        let img = numbered(4, 4);
+        // a homography with a scaled last row equals the identity
+        let scaled = mat!([[2.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 2.0]]);
+        let warped = img
+            .warp_perspective(&scaled, 4, 4, FilterType::Bicubic, BorderMode::Replicate)
+            .unwrap();
+        assert_eq!(warped, img);
+
+        // warp_affine ignores the last row
+        let affine = img
+            .warp_affine(&scaled, 4, 4, FilterType::Nearest, BorderMode::Replicate)
+            .unwrap();
+        assert_eq!(affine.get(2, 2), img.get(1, 1));
+
+        // (x, y) -> (x, y) / (1 + x / 4)
+        let perspective = mat!([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.25, 0.0, 1.0]]);
+        let warped = img
+            .warp_perspective(
+                &perspective,
+                4,
+                4,
+                FilterType::Nearest,
+                BorderMode::Constant(99),
+            )
+            .unwrap();
+        assert_eq!(warped.get(0, 0), Some(0));
+        assert_eq!(warped.get(1, 1), Some(img.get(1, 1).unwrap()));
+        // (2, 0) in the new image is mapped back to (4, 0), which is outside of the image
+        assert_eq!(warped.get(2, 0), Some(99));
+    }
+}
@@ -0,0 +1,1616 @@
+//! Reading and writing images in the baseline JPEG format.
+
+use crate::{
+    bytes::{read_bytes, read_u8},
+    color::Color,
+    deflate::huffman_lengths,
+    error::{checked_samples, Error, Result},
+    image::{GrayImage, Image},
+};
+
+const SOF0: u8 = 0xc0;
+const SOF1: u8 = 0xc1;
+const DHT: u8 = 0xc4;
+const RST0: u8 = 0xd0;
+const RST7: u8 = 0xd7;
+const SOI: u8 = 0xd8;
+const EOI: u8 = 0xd9;
+const SOS: u8 = 0xda;
+const DQT: u8 = 0xdb;
+const DRI: u8 = 0xdd;
+const APP0: u8 = 0xe0;
+const APP14: u8 = 0xee;
+const TEM: u8 = 0x01;
+
+/// Natural (row-major) index of the coefficients in zigzag order.
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// Number of bits, which are decoded with a single table lookup.
+const LOOKUP_BITS: u32 = 9;
+
+/// Maximum magnitude of a dequantized coefficient. Valid files stay far below this value, which
+/// only protects against overflows in corrupt files.
+const MAX_COEFFICIENT: i32 = 1 << 20;
+
+/// Read a big endian u16 at the given offset.
+fn read_u16(inp: &[u8], offset: usize) -> Result<u16> {
+    let bytes = read_bytes(inp, offset, 2)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Standard luminance quantization table (in natural order) for a quality of 50.
+const LUMINANCE_QUANT_TABLE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// Standard chrominance quantization table (in natural order) for a quality of 50.
+const CHROMINANCE_QUANT_TABLE: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+/// Number of codes per length of the standard luminance DC Huffman table.
+const LUMINANCE_DC_COUNTS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+
+/// Number of codes per length of the standard chrominance DC Huffman table.
+const CHROMINANCE_DC_COUNTS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+
+/// Symbols of the standard DC Huffman tables.
+const DC_SYMBOLS: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+/// Number of codes per length of the standard luminance AC Huffman table.
+const LUMINANCE_AC_COUNTS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+
+/// Symbols of the standard luminance AC Huffman table.
+const LUMINANCE_AC_SYMBOLS: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08, 0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+    0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+    0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+    0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
+/// Number of codes per length of the standard chrominance AC Huffman table.
+const CHROMINANCE_AC_COUNTS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+
+/// Symbols of the standard chrominance AC Huffman table.
+const CHROMINANCE_AC_SYMBOLS: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33, 0x52, 0xf0,
+    0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34, 0xe1, 0x25, 0xf1, 0x17, 0x18, 0x19, 0x1a, 0x26,
+    0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5,
+    0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3,
+    0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda,
+    0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
+/// Chroma subsampling of written JPEG files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChromaSubsampling {
+    /// Chroma is stored with the full resolution.
+    Yuv444,
+    /// Chroma is stored with half the resolution in both directions.
+    Yuv420,
+}
+
+/// Options for writing JPEG files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct JpegOptions {
+    /// Quality between 1 (smallest files) and 100 (best quality), which scales the standard
+    /// quantization tables.
+    pub quality: u8,
+    pub subsampling: ChromaSubsampling,
+    /// Build Huffman tables, which are optimized for the image, instead of using the standard
+    /// tables. This results in smaller files, but takes an additional pass over the image.
+    pub optimize_huffman: bool,
+}
+
+impl JpegOptions {
+    /// Create options with the given quality and the default settings otherwise.
+    pub fn new(quality: u8) -> Self {
+        Self {
+            quality,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self {
+            quality: 75,
+            subsampling: ChromaSubsampling::Yuv420,
+            optimize_huffman: false,
+        }
+    }
+}
+
+/// Trait for transforming any values into their JPEG representation.
+pub trait JPEG {
+    /// Convert a value into its JPEG representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the width or height exceeds 65535 pixels. See `try_to_jpeg` for a fallible
+    /// variant.
+    fn to_jpeg(&self, options: &JpegOptions) -> Vec<u8>;
+
+    /// Try to convert a value into its JPEG representation. Returns an error, if the width or
+    /// height exceeds 65535 pixels. The default implementation calls `to_jpeg`.
+    fn try_to_jpeg(&self, options: &JpegOptions) -> Result<Vec<u8>> {
+        Ok(self.to_jpeg(options))
+    }
+}
+
+/// Macro for saving an image in its JPEG representation to the disk.
+#[macro_export]
+macro_rules! save_jpeg {
+    ($file_name:expr, $img:expr, $options:expr) => {
+        $crate::try_write_file!($file_name, &$crate::jpeg::JPEG::to_jpeg(&$img, &$options))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its JPEG representation to the disk. Returns an error, if the
+/// file could not be written.
+#[macro_export]
+macro_rules! try_save_jpeg {
+    ($file_name:expr, $img:expr, $options:expr) => {
+        $crate::jpeg::JPEG::try_to_jpeg(&$img, &$options)
+            .and_then(|jpeg| $crate::try_write_file!($file_name, &jpeg))
+    };
+}
+
+/// Writer for the MSB-first bit stream of entropy-coded segments with byte stuffing.
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            out: vec![],
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, len: u32) {
+        self.bits = (self.bits << len) | (value & ((1 << len) - 1));
+        self.count += len;
+        while self.count >= 8 {
+            let byte = (self.bits >> (self.count - 8)) as u8;
+            self.out.push(byte);
+            if byte == 0xff {
+                self.out.push(0);
+            }
+            self.count -= 8;
+        }
+        self.bits &= (1 << self.count) - 1;
+    }
+
+    /// Pad the last byte with ones.
+    fn flush(&mut self) {
+        if self.count > 0 {
+            self.write(0x7f, 8 - self.count);
+        }
+    }
+}
+
+/// Huffman table for encoding DC or AC symbols.
+struct HuffmanEncoder {
+    counts: [u8; 16],
+    symbols: Vec<u8>,
+    codes: [u16; 256],
+    lengths: [u8; 256],
+}
+
+impl HuffmanEncoder {
+    /// Create a table from the number of codes per length (1..=16) and the symbols ordered by
+    /// their codes.
+    fn new(counts: [u8; 16], symbols: Vec<u8>) -> Self {
+        let mut codes = [0u16; 256];
+        let mut lengths = [0u8; 256];
+        let mut code = 0u16;
+        let mut symbols_iter = symbols.iter();
+        for (len, count) in counts.iter().enumerate() {
+            for symbol in symbols_iter.by_ref().take(*count as usize) {
+                codes[*symbol as usize] = code;
+                lengths[*symbol as usize] = len as u8 + 1;
+                code += 1;
+            }
+            code <<= 1;
+        }
+        Self {
+            counts,
+            symbols,
+            codes,
+            lengths,
+        }
+    }
+
+    /// Create a table, which is optimized for the given symbol frequencies.
+    fn optimized(freqs: &[u32; 256]) -> Self {
+        // a reserved symbol with the lowest frequency ensures, that no code consists of ones only
+        let mut freqs = freqs.to_vec();
+        freqs.push(1);
+        let lengths = huffman_lengths(&freqs, 16);
+
+        let mut symbols: Vec<usize> = (0..256).filter(|symbol| lengths[*symbol] > 0).collect();
+        symbols.sort_by_key(|symbol| lengths[*symbol]);
+        let mut counts = [0u8; 16];
+        for symbol in &symbols {
+            counts[lengths[*symbol] as usize - 1] += 1;
+        }
+        Self::new(
+            counts,
+            symbols.into_iter().map(|symbol| symbol as u8).collect(),
+        )
+    }
+
+    fn write(&self, writer: &mut BitWriter, symbol: u8) {
+        writer.write(
+            self.codes[symbol as usize] as u32,
+            self.lengths[symbol as usize] as u32,
+        );
+    }
+}
+
+/// Get the size category and the bits of a coefficient.
+fn magnitude(value: i32) -> (u8, u32) {
+    let size = 32 - value.unsigned_abs().leading_zeros();
+    // negative values are stored as the one's complement
+    let bits = if value < 0 { value - 1 } else { value } as u32;
+    (size as u8, bits)
+}
+
+/// Emit the Huffman symbols of a block of quantized coefficients (in zigzag order) together with
+/// their additional bits and the number of additional bits.
+fn block_symbols(
+    coefficients: &[i32; 64],
+    prediction: &mut i32,
+    mut emit: impl FnMut(bool, u8, u32, u8),
+) {
+    let (size, bits) = magnitude(coefficients[0] - *prediction);
+    *prediction = coefficients[0];
+    emit(true, size, bits, size);
+
+    let mut run = 0;
+    for coefficient in &coefficients[1..] {
+        if *coefficient == 0 {
+            run += 1;
+            continue;
+        }
+        while run >= 16 {
+            // run of 16 zeros
+            emit(false, 0xf0, 0, 0);
+            run -= 16;
+        }
+        let (size, bits) = magnitude(*coefficient);
+        emit(false, (run << 4) | size, bits, size);
+        run = 0;
+    }
+    if run > 0 {
+        // end of block
+        emit(false, 0x00, 0, 0);
+    }
+}
+
+/// Scale a quantization table according to the quality (as defined by the IJG).
+fn scale_quant_table(table: &[u16; 64], quality: u8) -> [u16; 64] {
+    let quality = quality.clamp(1, 100) as u32;
+    let scale = if quality < 50 {
+        5000 / quality
+    } else {
+        200 - 2 * quality
+    };
+    table.map(|value| ((value as u32 * scale + 50) / 100).clamp(1, 255) as u16)
+}
+
+/// Transform a block of samples (in natural order) and quantize the coefficients. Returns the
+/// quantized coefficients in zigzag order.
+fn fdct_quantize(samples: &[f32; 64], factors: &[[f32; 8]; 8], table: &[u16; 64]) -> [i32; 64] {
+    let mut tmp = [0f32; 64];
+    // rows
+    for y in 0..8 {
+        let row = &samples[y * 8..y * 8 + 8];
+        for u in 0..8 {
+            tmp[y * 8 + u] = (0..8).map(|x| factors[x][u] * (row[x] - 128.0)).sum();
+        }
+    }
+    // columns
+    let mut coefficients = [0i32; 64];
+    for (k, coefficient) in coefficients.iter_mut().enumerate() {
+        let (v, u) = (ZIGZAG[k] / 8, ZIGZAG[k] % 8);
+        let value: f32 = (0..8).map(|y| factors[y][v] * tmp[y * 8 + u]).sum();
+        *coefficient = (value / table[ZIGZAG[k]] as f32).round() as i32;
+    }
+    coefficients
+}
+
+/// Append a marker segment with its length to the output.
+fn write_segment(jpeg: &mut Vec<u8>, marker: u8, data: &[u8]) {
+    jpeg.extend_from_slice(&[0xff, marker]);
+    jpeg.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+    jpeg.extend_from_slice(data);
+}
+
+/// Encode one (grayscale) or three (YCbCr) full resolution sample planes as baseline JPEG.
+fn encode_jpeg(
+    cols: usize,
+    rows: usize,
+    planes: &[Vec<u8>],
+    options: &JpegOptions,
+) -> Result<Vec<u8>> {
+    if cols > u16::MAX as usize || rows > u16::MAX as usize {
+        return Err(Error::DimensionOverflow {
+            width: cols,
+            height: rows,
+        });
+    }
+
+    // horizontal and vertical sampling factor of every component
+    let sampling: Vec<(usize, usize)> = match (planes.len(), options.subsampling) {
+        (3, ChromaSubsampling::Yuv420) => vec![(2, 2), (1, 1), (1, 1)],
+        (count, _) => vec![(1, 1); count],
+    };
+    let (h_max, v_max) = sampling[0];
+    let mcus_x = cols.div_ceil(8 * h_max);
+    let mcus_y = rows.div_ceil(8 * v_max);
+
+    let quant_tables = [
+        scale_quant_table(&LUMINANCE_QUANT_TABLE, options.quality),
+        scale_quant_table(&CHROMINANCE_QUANT_TABLE, options.quality),
+    ];
+    let factors = dct_factors();
+
+    // quantized blocks in the order of the scan, together with their component
+    let mut blocks = vec![];
+    for mcu in 0..mcus_x * mcus_y {
+        let (mcu_x, mcu_y) = (mcu % mcus_x, mcu / mcus_x);
+        for (c, (h, v)) in sampling.iter().enumerate() {
+            let (scale_x, scale_y) = (h_max / h, v_max / v);
+            for block in 0..h * v {
+                let (block_x, block_y) = (mcu_x * h + block % h, mcu_y * v + block / h);
+
+                // samples outside of the image are replicated from the edges and subsampled
+                // components average the samples of the full resolution
+                let mut samples = [0f32; 64];
+                for (i, sample) in samples.iter_mut().enumerate() {
+                    let (x, y) = (block_x * 8 + i % 8, block_y * 8 + i / 8);
+                    let mut sum = 0.0;
+                    for dy in 0..scale_y {
+                        for dx in 0..scale_x {
+                            let sx = (x * scale_x + dx).min(cols - 1);
+                            let sy = (y * scale_y + dy).min(rows - 1);
+                            sum += planes[c][sy * cols + sx] as f32;
+                        }
+                    }
+                    *sample = sum / (scale_x * scale_y) as f32;
+                }
+
+                let table = &quant_tables[c.min(1)];
+                blocks.push((c, fdct_quantize(&samples, &factors, table)));
+            }
+        }
+    }
+
+    // DC and AC tables for luminance and chrominance
+    let [dc_tables, ac_tables] = if options.optimize_huffman {
+        let mut dc_freqs = [[0u32; 256]; 2];
+        let mut ac_freqs = [[0u32; 256]; 2];
+        let mut predictions = vec![0; planes.len()];
+        for (c, coefficients) in &blocks {
+            let table = (*c).min(1);
+            block_symbols(coefficients, &mut predictions[*c], |dc, symbol, _, _| {
+                if dc {
+                    dc_freqs[table][symbol as usize] += 1;
+                } else {
+                    ac_freqs[table][symbol as usize] += 1;
+                }
+            });
+        }
+        [dc_freqs, ac_freqs].map(|freqs| freqs.map(|freqs| HuffmanEncoder::optimized(&freqs)))
+    } else {
+        [
+            [
+                HuffmanEncoder::new(LUMINANCE_DC_COUNTS, DC_SYMBOLS.to_vec()),
+                HuffmanEncoder::new(CHROMINANCE_DC_COUNTS, DC_SYMBOLS.to_vec()),
+            ],
+            [
+                HuffmanEncoder::new(LUMINANCE_AC_COUNTS, LUMINANCE_AC_SYMBOLS.to_vec()),
+                HuffmanEncoder::new(CHROMINANCE_AC_COUNTS, CHROMINANCE_AC_SYMBOLS.to_vec()),
+            ],
+        ]
+    };
+    // grayscale images only need the luminance tables
+    let table_count = planes.len().min(2);
+
+    let mut jpeg = vec![0xff, SOI];
+    write_segment(
+        &mut jpeg,
+        APP0,
+        &[b'J', b'F', b'I', b'F', 0, 1, 1, 0, 0, 1, 0, 1, 0, 0],
+    );
+
+    let mut dqt = vec![];
+    for (id, table) in quant_tables.iter().take(table_count).enumerate() {
+        dqt.push(id as u8);
+        dqt.extend(ZIGZAG.iter().map(|n| table[*n] as u8));
+    }
+    write_segment(&mut jpeg, DQT, &dqt);
+
+    let mut sof = vec![8];
+    sof.extend_from_slice(&(rows as u16).to_be_bytes());
+    sof.extend_from_slice(&(cols as u16).to_be_bytes());
+    sof.push(planes.len() as u8);
+    for (c, (h, v)) in sampling.iter().enumerate() {
+        sof.extend_from_slice(&[c as u8 + 1, (*h as u8) << 4 | *v as u8, c.min(1) as u8]);
+    }
+    write_segment(&mut jpeg, SOF0, &sof);
+
+    let mut dht = vec![];
+    for (class, tables) in [&dc_tables, &ac_tables].iter().enumerate() {
+        for (id, table) in tables.iter().take(table_count).enumerate() {
+            dht.push((class as u8) << 4 | id as u8);
+            dht.extend_from_slice(&table.counts);
+            dht.extend_from_slice(&table.symbols);
+        }
+    }
+    write_segment(&mut jpeg, DHT, &dht);
+
+    let mut sos = vec![planes.len() as u8];
+    for c in 0..planes.len() {
+        let table = c.min(1) as u8;
+        sos.extend_from_slice(&[c as u8 + 1, table << 4 | table]);
+    }
+    sos.extend_from_slice(&[0, 63, 0]);
+    write_segment(&mut jpeg, SOS, &sos);
+
+    let mut writer = BitWriter::new();
+    let mut predictions = vec![0; planes.len()];
+    for (c, coefficients) in &blocks {
+        let table = (*c).min(1);
+        block_symbols(
+            coefficients,
+            &mut predictions[*c],
+            |dc, symbol, bits, size| {
+                let encoder = if dc {
+                    &dc_tables[table]
+                } else {
+                    &ac_tables[table]
+                };
+                encoder.write(&mut writer, symbol);
+                writer.write(bits, size as u32);
+            },
+        );
+    }
+    writer.flush();
+    jpeg.append(&mut writer.out);
+    jpeg.extend_from_slice(&[0xff, EOI]);
+    Ok(jpeg)
+}
+
+/// Convert an RGB color into YCbCr (as defined by JFIF).
+fn rgb_to_ycbcr(color: &Color) -> [u8; 3] {
+    let (r, g, b) = (color.r as f32, color.g as f32, color.b as f32);
+    let clamp = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+    [
+        clamp(0.299 * r + 0.587 * g + 0.114 * b),
+        clamp(-0.168_736 * r - 0.331_264 * g + 0.5 * b + 128.0),
+        clamp(0.5 * r - 0.418_688 * g - 0.081_312 * b + 128.0),
+    ]
+}
+
+impl JPEG for Image {
+    fn to_jpeg(&self, options: &JpegOptions) -> Vec<u8> {
+        self.try_to_jpeg(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_jpeg(&self, options: &JpegOptions) -> Result<Vec<u8>> {
+        let mut planes: Vec<Vec<u8>> = (0..3)
+            .map(|_| Vec::with_capacity(self.cols() * self.rows()))
+            .collect();
+        for y in 0..self.rows() {
+            for x in 0..self.cols() {
+                let samples = rgb_to_ycbcr(&self.get(x, y).unwrap_or_default());
+                for (plane, sample) in planes.iter_mut().zip(samples) {
+                    plane.push(sample);
+                }
+            }
+        }
+        encode_jpeg(self.cols(), self.rows(), &planes, options)
+    }
+}
+
+impl JPEG for GrayImage {
+    fn to_jpeg(&self, options: &JpegOptions) -> Vec<u8> {
+        self.try_to_jpeg(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_jpeg(&self, options: &JpegOptions) -> Result<Vec<u8>> {
+        let plane = (0..self.rows())
+            .flat_map(|y| (0..self.cols()).map(move |x| (x, y)))
+            .map(|(x, y)| self.get(x, y).unwrap_or_default())
+            .collect();
+        encode_jpeg(self.cols(), self.rows(), &[plane], options)
+    }
+}
+
+/// Huffman table for decoding DC or AC symbols.
+struct HuffmanTable {
+    /// Symbol and code length for all codes of at most `LOOKUP_BITS` bits, indexed by the next
+    /// `LOOKUP_BITS` bits of the input. Entries with a length of 0 belong to longer codes.
+    lookup: Vec<(u8, u8)>,
+    /// Largest code of every code length (or -1, if there is no code of this length).
+    max_code: [i32; 17],
+    /// Offset of the first symbol of every code length, relative to the smallest code.
+    offsets: [i32; 17],
+    symbols: Vec<u8>,
+}
+
+impl HuffmanTable {
+    /// Create a table from the number of codes per length (1..=16) and the symbols ordered by
+    /// their codes.
+    fn new(counts: &[u8; 16], symbols: Vec<u8>) -> Result<Self> {
+        let mut lookup = vec![(0u8, 0u8); 1 << LOOKUP_BITS];
+        let mut max_code = [-1i32; 17];
+        let mut offsets = [0i32; 17];
+
+        let mut code = 0i32;
+        let mut index = 0usize;
+        for len in 1..=16 {
+            let count = counts[len - 1] as usize;
+            offsets[len] = index as i32 - code;
+            for _ in 0..count {
+                if code >= 1 << len {
+                    return Err(Error::InvalidData(
+                        "Over-subscribed Huffman table".to_string(),
+                    ));
+                }
+                if len as u32 <= LOOKUP_BITS {
+                    let shift = LOOKUP_BITS - len as u32;
+                    let start = (code as usize) << shift;
+                    lookup[start..start + (1 << shift)].fill((symbols[index], len as u8));
+                }
+                code += 1;
+                index += 1;
+            }
+            if count > 0 {
+                max_code[len] = code - 1;
+            }
+            code <<= 1;
+        }
+
+        Ok(Self {
+            lookup,
+            max_code,
+            offsets,
+            symbols,
+        })
+    }
+
+    fn decode(&self, reader: &mut EntropyReader) -> Result<u8> {
+        let (symbol, len) = self.lookup[reader.peek(LOOKUP_BITS) as usize];
+        if len > 0 {
+            reader.consume(len as u32)?;
+            return Ok(symbol);
+        }
+
+        // slow path for long codes
+        let mut code = reader.peek(LOOKUP_BITS) as i32;
+        reader.consume(LOOKUP_BITS)?;
+        for len in LOOKUP_BITS as usize + 1..=16 {
+            code = (code << 1) | reader.read(1)? as i32;
+            if code <= self.max_code[len] {
+                return Ok(self.symbols[(self.offsets[len] + code) as usize]);
+            }
+        }
+        Err(Error::InvalidData("Invalid Huffman code".to_string()))
+    }
+}
+
+/// Reader for the MSB-first bit stream of entropy-coded segments, which removes stuffed zero bytes
+/// and stops at markers.
+struct EntropyReader<'a> {
+    inp: &'a [u8],
+    pos: usize,
+    bits: u64,
+    count: u32,
+    /// Number of zero bits at the end of the buffer, which were inserted after the end of the
+    /// segment.
+    padding: u32,
+    /// Marker, which ended the segment.
+    marker: Option<u8>,
+}
+
+impl<'a> EntropyReader<'a> {
+    fn new(inp: &'a [u8], pos: usize) -> Self {
+        Self {
+            inp,
+            pos,
+            bits: 0,
+            count: 0,
+            padding: 0,
+            marker: None,
+        }
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 56 {
+            let byte = self.next_byte();
+            self.bits |= (byte as u64) << (56 - self.count);
+            self.count += 8;
+        }
+    }
+
+    /// Get the next byte of the segment. After the end of the segment, zeros are returned.
+    fn next_byte(&mut self) -> u8 {
+        if self.marker.is_some() || self.pos >= self.inp.len() {
+            self.padding += 8;
+            return 0;
+        }
+
+        let byte = self.inp[self.pos];
+        if byte != 0xff {
+            self.pos += 1;
+            return byte;
+        }
+        match self.inp.get(self.pos + 1) {
+            // stuffed zero byte
+            Some(0x00) => {
+                self.pos += 2;
+                0xff
+            }
+            // keep the position at the start of the marker
+            next => {
+                self.marker = Some(next.copied().unwrap_or(EOI));
+                self.padding += 8;
+                0
+            }
+        }
+    }
+
+    fn peek(&mut self, n: u32) -> u32 {
+        if self.count < n {
+            self.fill();
+        }
+        (self.bits >> (64 - n)) as u32
+    }
+
+    fn consume(&mut self, n: u32) -> Result<()> {
+        self.bits <<= n;
+        self.count -= n;
+        if self.count < self.padding {
+            return Err(Error::TruncatedData {
+                expected: self.inp.len() + 1,
+                actual: self.inp.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, n: u32) -> Result<u32> {
+        if n == 0 {
+            return Ok(0);
+        }
+        let value = self.peek(n);
+        self.consume(n)?;
+        Ok(value)
+    }
+
+    /// Read `n` bits and extend them to a signed value.
+    fn receive_extend(&mut self, n: u32) -> Result<i32> {
+        let value = self.read(n)? as i32;
+        if n > 0 && value < 1 << (n - 1) {
+            Ok(value - (1 << n) + 1)
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Skip to the next restart marker and reset the bit buffer.
+    fn restart(&mut self) -> Result<()> {
+        self.bits = 0;
+        self.count = 0;
+        self.padding = 0;
+        self.marker = None;
+
+        // skip any fill bytes up to the marker
+        while self.pos + 1 < self.inp.len() {
+            if self.inp[self.pos] == 0xff && (RST0..=RST7).contains(&self.inp[self.pos + 1]) {
+                self.pos += 2;
+                return Ok(());
+            }
+            self.pos += 1;
+        }
+        Err(Error::InvalidData("Missing restart marker".to_string()))
+    }
+}
+
+/// Component of a frame.
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quant_table: usize,
+    /// Decoded samples of this component, padded to complete MCUs.
+    samples: Vec<u8>,
+    /// Width and height of the (padded) sample plane.
+    stride: usize,
+    plane_height: usize,
+    /// Number of blocks per line and per column, which contain actual image data.
+    blocks_x: usize,
+    blocks_y: usize,
+    dc_prediction: i32,
+}
+
+impl Component {
+    /// Allocate the sample plane of this component, if it has not been allocated yet.
+    fn allocate(&mut self) {
+        if self.samples.is_empty() {
+            self.samples = vec![0; self.stride * self.plane_height];
+        }
+    }
+}
+
+/// Header information of a frame.
+struct Frame {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+    h_max: usize,
+    v_max: usize,
+    mcus_x: usize,
+    mcus_y: usize,
+}
+
+/// Parse the SOF0/SOF1 segment.
+fn parse_frame(segment: &[u8]) -> Result<Frame> {
+    let precision = read_u8(segment, 0)?;
+    let height = read_u16(segment, 1)? as usize;
+    let width = read_u16(segment, 3)? as usize;
+    let component_count = read_u8(segment, 5)? as usize;
+
+    if precision != 8 {
+        return Err(Error::InvalidHeader(format!(
+            "Unsupported sample precision ({})",
+            precision
+        )));
+    }
+    if width == 0 || height == 0 {
+        return Err(Error::InvalidHeader(format!(
+            "Invalid image dimensions ({}x{})",
+            width, height
+        )));
+    }
+    if component_count != 1 && component_count != 3 {
+        return Err(Error::InvalidHeader(format!(
+            "Unsupported number of components ({})",
+            component_count
+        )));
+    }
+
+    let mut components = vec![];
+    for i in 0..component_count {
+        let spec = read_bytes(segment, 6 + i * 3, 3)?;
+        let (h, v) = ((spec[1] >> 4) as usize, (spec[1] & 0x0f) as usize);
+        if !(1..=4).contains(&h) || !(1..=4).contains(&v) || spec[2] > 3 {
+            return Err(Error::InvalidHeader(format!(
+                "Invalid component specification ({:?})",
+                spec
+            )));
+        }
+        components.push(Component {
+            id: spec[0],
+            h,
+            v,
+            quant_table: spec[2] as usize,
+            samples: vec![],
+            stride: 0,
+            plane_height: 0,
+            blocks_x: 0,
+            blocks_y: 0,
+            dc_prediction: 0,
+        });
+    }
+
+    let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+    let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+    let mcus_x = width.div_ceil(8 * h_max);
+    let mcus_y = height.div_ceil(8 * v_max);
+
+    for component in components.iter_mut() {
+        component.stride = mcus_x * component.h * 8;
+        component.plane_height = mcus_y * component.v * 8;
+        // the samples are allocated by the first scan of the component, see `decode_scan`
+        checked_samples(component.stride, component.plane_height, 1)?;
+        component.blocks_x = (width * component.h).div_ceil(h_max).div_ceil(8);
+        component.blocks_y = (height * component.v).div_ceil(v_max).div_ceil(8);
+    }
+
+    Ok(Frame {
+        width,
+        height,
+        components,
+        h_max,
+        v_max,
+        mcus_x,
+        mcus_y,
+    })
+}
+
+/// Parse a DQT segment, which may contain multiple tables.
+fn parse_quant_tables(segment: &[u8], tables: &mut [Option<[u16; 64]>; 4]) -> Result<()> {
+    let mut pos = 0;
+    while pos < segment.len() {
+        let spec = segment[pos];
+        let (precision, id) = (spec >> 4, (spec & 0x0f) as usize);
+        if precision > 1 || id > 3 {
+            return Err(Error::InvalidHeader(format!(
+                "Invalid quantization table ({:#04x})",
+                spec
+            )));
+        }
+        let mut table = [0u16; 64];
+        for (k, value) in table.iter_mut().enumerate() {
+            *value = if precision == 0 {
+                read_u8(segment, pos + 1 + k)? as u16
+            } else {
+                read_u16(segment, pos + 1 + 2 * k)?
+            };
+        }
+        tables[id] = Some(table);
+        pos += 1 + 64 * (precision as usize + 1);
+    }
+    Ok(())
+}
+
+/// Parse a DHT segment, which may contain multiple tables.
+fn parse_huffman_tables(
+    segment: &[u8],
+    dc_tables: &mut [Option<HuffmanTable>; 4],
+    ac_tables: &mut [Option<HuffmanTable>; 4],
+) -> Result<()> {
+    let mut pos = 0;
+    while pos < segment.len() {
+        let spec = segment[pos];
+        let (class, id) = (spec >> 4, (spec & 0x0f) as usize);
+        if class > 1 || id > 3 {
+            return Err(Error::InvalidHeader(format!(
+                "Invalid Huffman table ({:#04x})",
+                spec
+            )));
+        }
+        let mut counts = [0u8; 16];
+        counts.copy_from_slice(read_bytes(segment, pos + 1, 16)?);
+        let total = counts.iter().map(|count| *count as usize).sum();
+        let symbols = read_bytes(segment, pos + 17, total)?.to_vec();
+        let table = HuffmanTable::new(&counts, symbols)?;
+        if class == 0 {
+            dc_tables[id] = Some(table);
+        } else {
+            ac_tables[id] = Some(table);
+        }
+        pos += 17 + total;
+    }
+    Ok(())
+}
+
+/// Precomputed factors for the one-dimensional (inverse) DCT, indexed by sample and frequency.
+fn dct_factors() -> [[f32; 8]; 8] {
+    let mut factors = [[0f32; 8]; 8];
+    for (x, row) in factors.iter_mut().enumerate() {
+        for (u, factor) in row.iter_mut().enumerate() {
+            let c = if u == 0 {
+                std::f32::consts::FRAC_1_SQRT_2
+            } else {
+                1.0
+            };
+            *factor = c / 2.0 * ((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0).cos();
+        }
+    }
+    factors
+}
+
+/// Transform a block of dequantized coefficients (in natural order) back into samples.
+fn idct(coefficients: &[i32; 64], factors: &[[f32; 8]; 8], out: &mut [u8], stride: usize) {
+    let mut tmp = [0f32; 64];
+    // rows
+    for v in 0..8 {
+        let row = &coefficients[v * 8..v * 8 + 8];
+        for x in 0..8 {
+            tmp[v * 8 + x] = (0..8).map(|u| factors[x][u] * row[u] as f32).sum();
+        }
+    }
+    // columns
+    for x in 0..8 {
+        for y in 0..8 {
+            let value: f32 = (0..8).map(|v| factors[y][v] * tmp[v * 8 + x]).sum();
+            out[y * stride + x] = (value + 128.0).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+/// Tables and settings, which are used while decoding a scan.
+struct ScanContext<'a> {
+    quant_tables: &'a [Option<[u16; 64]>; 4],
+    dc_tables: &'a [Option<HuffmanTable>; 4],
+    ac_tables: &'a [Option<HuffmanTable>; 4],
+    restart_interval: usize,
+    factors: [[f32; 8]; 8],
+}
+
+/// Decode a single block and write its samples into the component at the given block position.
+fn decode_block(
+    reader: &mut EntropyReader,
+    component: &mut Component,
+    dc_table: &HuffmanTable,
+    ac_table: &HuffmanTable,
+    quant_table: &[u16; 64],
+    factors: &[[f32; 8]; 8],
+    (block_x, block_y): (usize, usize),
+) -> Result<()> {
+    let mut coefficients = [0i32; 64];
+
+    let size = dc_table.decode(reader)? as u32;
+    if size > 11 {
+        return Err(Error::InvalidData(format!(
+            "Invalid DC coefficient size ({})",
+            size
+        )));
+    }
+    // corrupt files may accumulate huge predictions, which must not overflow
+    component.dc_prediction = component
+        .dc_prediction
+        .wrapping_add(reader.receive_extend(size)?);
+    coefficients[0] = component
+        .dc_prediction
+        .saturating_mul(quant_table[0] as i32)
+        .clamp(-MAX_COEFFICIENT, MAX_COEFFICIENT);
+
+    let mut k = 1;
+    while k < 64 {
+        let symbol = ac_table.decode(reader)?;
+        let (run, size) = ((symbol >> 4) as usize, (symbol & 0x0f) as u32);
+        if size == 0 {
+            if run == 15 {
+                // run of 16 zeros
+                k += 16;
+                continue;
+            }
+            // end of block
+            break;
+        }
+        k += run;
+        if k > 63 {
+            return Err(Error::InvalidData(
+                "AC coefficient index out of bounds".to_string(),
+            ));
+        }
+        coefficients[ZIGZAG[k]] = reader
+            .receive_extend(size)?
+            .saturating_mul(quant_table[k] as i32)
+            .clamp(-MAX_COEFFICIENT, MAX_COEFFICIENT);
+        k += 1;
+    }
+
+    let offset = block_y * 8 * component.stride + block_x * 8;
+    let stride = component.stride;
+    idct(
+        &coefficients,
+        factors,
+        &mut component.samples[offset..],
+        stride,
+    );
+    Ok(())
+}
+
+/// Decode the entropy-coded data of a scan. Returns the offset of the first byte after the scan.
+fn decode_scan(
+    inp: &[u8],
+    header: &[u8],
+    start: usize,
+    frame: &mut Frame,
+    context: &ScanContext,
+) -> Result<usize> {
+    let component_count = read_u8(header, 0)? as usize;
+    if component_count == 0 || component_count > frame.components.len() {
+        return Err(Error::InvalidHeader(format!(
+            "Invalid number of scan components ({})",
+            component_count
+        )));
+    }
+
+    // index of the component, DC table and AC table of every scan component
+    let mut scan_components = vec![];
+    for i in 0..component_count {
+        let spec = read_bytes(header, 1 + i * 2, 2)?;
+        let Some(index) = frame.components.iter().position(|c| c.id == spec[0]) else {
+            return Err(Error::InvalidHeader(format!(
+                "Unknown scan component ({})",
+                spec[0]
+            )));
+        };
+        let (dc, ac) = ((spec[1] >> 4) as usize, (spec[1] & 0x0f) as usize);
+        let component = &frame.components[index];
+        let (Some(Some(dc_table)), Some(Some(ac_table)), Some(quant_table)) = (
+            context.dc_tables.get(dc),
+            context.ac_tables.get(ac),
+            context.quant_tables[component.quant_table].as_ref(),
+        ) else {
+            return Err(Error::InvalidHeader(
+                "Scan references undefined tables".to_string(),
+            ));
+        };
+        scan_components.push((index, dc_table, ac_table, quant_table));
+    }
+
+    let selection = read_bytes(header, 1 + component_count * 2, 3)?;
+    if selection != [0, 63, 0] {
+        return Err(Error::InvalidHeader(
+            "Only baseline sequential scans are supported".to_string(),
+        ));
+    }
+
+    // non-interleaved scans contain single blocks of one component as MCUs
+    let (mcus_x, mcus_y) = if component_count == 1 {
+        let component = &frame.components[scan_components[0].0];
+        (component.blocks_x, component.blocks_y)
+    } else {
+        (frame.mcus_x, frame.mcus_y)
+    };
+
+    // every block takes at least two bits (DC coefficient and end of block), so the remaining
+    // input bounds the number of blocks before their planes are allocated
+    let blocks_per_mcu = if component_count == 1 {
+        1
+    } else {
+        scan_components
+            .iter()
+            .map(|(index, ..)| frame.components[*index].h * frame.components[*index].v)
+            .sum()
+    };
+    let blocks = mcus_x.saturating_mul(mcus_y).saturating_mul(blocks_per_mcu);
+    let remaining = inp.len().saturating_sub(start);
+    if blocks > remaining.saturating_mul(4) {
+        return Err(Error::TruncatedData {
+            expected: start.saturating_add(blocks.div_ceil(4)),
+            actual: inp.len(),
+        });
+    }
+
+    for (index, ..) in &scan_components {
+        frame.components[*index].allocate();
+        frame.components[*index].dc_prediction = 0;
+    }
+
+    let mut reader = EntropyReader::new(inp, start);
+    for mcu in 0..mcus_x * mcus_y {
+        if context.restart_interval > 0 && mcu > 0 && mcu % context.restart_interval == 0 {
+            reader.restart()?;
+            for (index, ..) in &scan_components {
+                frame.components[*index].dc_prediction = 0;
+            }
+        }
+
+        let (mcu_x, mcu_y) = (mcu % mcus_x, mcu / mcus_x);
+        for (index, dc_table, ac_table, quant_table) in &scan_components {
+            let component = &mut frame.components[*index];
+            let (h, v) = if component_count == 1 {
+                (1, 1)
+            } else {
+                (component.h, component.v)
+            };
+            for block_y in 0..v {
+                for block_x in 0..h {
+                    decode_block(
+                        &mut reader,
+                        component,
+                        dc_table,
+                        ac_table,
+                        quant_table,
+                        &context.factors,
+                        (mcu_x * h + block_x, mcu_y * v + block_y),
+                    )?;
+                }
+            }
+        }
+    }
+
+    // skip to the next marker, which is not a restart marker
+    let mut pos = reader.pos;
+    while pos + 1 < inp.len() {
+        let next = inp[pos + 1];
+        if inp[pos] == 0xff && next != 0 && next != 0xff && !(RST0..=RST7).contains(&next) {
+            break;
+        }
+        pos += 1;
+    }
+    Ok(pos)
+}
+
+/// Get the sample of a component for an image pixel by linear interpolation between the nearest
+/// (subsampled) samples.
+fn upsample(frame: &Frame, component: &Component, x: usize, y: usize) -> u8 {
+    if component.h == frame.h_max && component.v == frame.v_max {
+        return component.samples[y * component.stride + x];
+    }
+
+    let max_x = (frame.width * component.h).div_ceil(frame.h_max) - 1;
+    let max_y = (frame.height * component.v).div_ceil(frame.v_max) - 1;
+    let position = |pos: usize, factor: usize, max_factor: usize, max: usize| {
+        let pos =
+            ((pos as f32 + 0.5) * factor as f32 / max_factor as f32 - 0.5).clamp(0.0, max as f32);
+        let low = pos.floor() as usize;
+        (low, (low + 1).min(max), pos - low as f32)
+    };
+    let (x0, x1, fx) = position(x, component.h, frame.h_max, max_x);
+    let (y0, y1, fy) = position(y, component.v, frame.v_max, max_y);
+
+    let sample = |x: usize, y: usize| component.samples[y * component.stride + x] as f32;
+    let top = sample(x0, y0) * (1.0 - fx) + sample(x1, y0) * fx;
+    let bottom = sample(x0, y1) * (1.0 - fx) + sample(x1, y1) * fx;
+    (top * (1.0 - fy) + bottom * fy).round() as u8
+}
+
+/// Convert a YCbCr color (as defined by JFIF) into RGB.
+fn ycbcr_to_rgb(y: u8, cb: u8, cr: u8) -> Color {
+    let (y, cb, cr) = (y as f32, cb as f32 - 128.0, cr as f32 - 128.0);
+    let clamp = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+    Color::new(
+        clamp(y + 1.402 * cr),
+        clamp(y - 0.344_136 * cb - 0.714_136 * cr),
+        clamp(y + 1.772 * cb),
+    )
+}
+
+/// Parse a vector of u8 into a valid JPEG image. This function will panic, if the format is not
+/// valid. See `try_parse_jpeg` for a fallible variant.
+pub fn parse_jpeg(inp: Vec<u8>) -> Image {
+    try_parse_jpeg(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid JPEG image. Only baseline (and extended sequential
+/// 8bit) Huffman-coded images with one (grayscale) or three (YCbCr) components are supported.
+pub fn try_parse_jpeg(inp: Vec<u8>) -> Result<Image> {
+    if inp.get(0..2) != Some(&[0xff, SOI]) {
+        return Err(Error::InvalidMagicConstant(
+            "No valid magic constant".to_string(),
+        ));
+    }
+
+    let mut quant_tables = [None; 4];
+    let mut dc_tables = [None, None, None, None];
+    let mut ac_tables = [None, None, None, None];
+    let mut restart_interval = 0;
+    let mut frame: Option<Frame> = None;
+    let mut adobe_transform = None;
+    let mut scans = 0;
+    let mut pos = 2;
+
+    loop {
+        if read_u8(&inp, pos)? != 0xff {
+            return Err(Error::InvalidData(format!(
+                "Expected marker at offset {}",
+                pos
+            )));
+        }
+        let marker = read_u8(&inp, pos + 1)?;
+        pos += 2;
+
+        match marker {
+            // fill bytes before a marker
+            0xff => pos -= 1,
+            EOI => break,
+            TEM | RST0..=RST7 => {}
+            _ => {
+                let len = read_u16(&inp, pos)? as usize;
+                if len < 2 {
+                    return Err(Error::InvalidHeader(format!(
+                        "Invalid segment length ({})",
+                        len
+                    )));
+                }
+                let segment = read_bytes(&inp, pos + 2, len - 2)?;
+                pos += len;
+
+                match marker {
+                    SOF0 | SOF1 => {
+                        if frame.is_some() {
+                            return Err(Error::InvalidHeader("Multiple frames".to_string()));
+                        }
+                        frame = Some(parse_frame(segment)?);
+                    }
+                    // progressive, lossless, hierarchical and arithmetic-coded frames
+                    0xc2 | 0xc3 | 0xc5..=0xc7 | 0xc9..=0xcb | 0xcd..=0xcf => {
+                        return Err(Error::InvalidHeader(format!(
+                            "Unsupported JPEG process (SOF{})",
+                            marker - SOF0
+                        )));
+                    }
+                    DHT => parse_huffman_tables(segment, &mut dc_tables, &mut ac_tables)?,
+                    DQT => parse_quant_tables(segment, &mut quant_tables)?,
+                    DRI => restart_interval = read_u16(segment, 0)? as usize,
+                    APP14 if segment.starts_with(b"Adobe") => {
+                        adobe_transform = segment.get(11).copied();
+                    }
+                    SOS => {
+                        let Some(frame) = frame.as_mut() else {
+                            return Err(Error::InvalidHeader(
+                                "Scan before frame header".to_string(),
+                            ));
+                        };
+                        let context = ScanContext {
+                            quant_tables: &quant_tables,
+                            dc_tables: &dc_tables,
+                            ac_tables: &ac_tables,
+                            restart_interval,
+                            factors: dct_factors(),
+                        };
+                        pos = decode_scan(&inp, segment, pos, frame, &context)?;
+                        scans += 1;
+                    }
+                    // application data, comments and other segments are ignored
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let Some(mut frame) = frame else {
+        return Err(Error::InvalidData("Missing frame header".to_string()));
+    };
+    if scans == 0 {
+        return Err(Error::InvalidData("Missing scan".to_string()));
+    }
+    // components without any scan stay empty
+    for component in frame.components.iter_mut() {
+        component.allocate();
+    }
+
+    let mut image = Image::new(frame.width, frame.height);
+    for y in 0..frame.height {
+        for x in 0..frame.width {
+            let color = match frame.components.as_slice() {
+                [gray] => Color::splat(upsample(&frame, gray, x, y)),
+                [first, second, third] => {
+                    let samples = [first, second, third].map(|c| upsample(&frame, c, x, y));
+                    // Adobe files without color transform store RGB
+                    if adobe_transform == Some(0) {
+                        Color::from_u8_array(&samples)
+                    } else {
+                        ycbcr_to_rgb(samples[0], samples[1], samples[2])
+                    }
+                }
+                _ => unreachable!("number of components is validated by the frame header"),
+            };
+            image.set(x, y, color);
+        }
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a JPEG, where every block only consists of a DC coefficient. `blocks` returns the
+    /// flat sample value of a block for the given component, MCU and block within the MCU.
+    fn build_jpeg(
+        width: u16,
+        height: u16,
+        sampling: &[(u8, u8)],
+        restart_interval: u16,
+        blocks: impl Fn(usize, usize, usize) -> u8,
+    ) -> Vec<u8> {
+        let mut jpeg = vec![0xff, SOI];
+
+        // all quantization factors are 1
+        jpeg.extend_from_slice(&[0xff, DQT, 0, 67, 0]);
+        jpeg.extend_from_slice(&[1; 64]);
+
+        // DC table: sizes 0..=11 with 4bit codes, AC table: only end of block with a 1bit code
+        jpeg.extend_from_slice(&[0xff, DHT, 0, 31, 0x00, 0, 0, 0, 12]);
+        jpeg.extend_from_slice(&[0; 12]);
+        jpeg.extend_from_slice(&(0..12).collect::<Vec<u8>>());
+        jpeg.extend_from_slice(&[0xff, DHT, 0, 20, 0x10, 1]);
+        jpeg.extend_from_slice(&[0; 15]);
+        jpeg.push(0x00);
+
+        if restart_interval > 0 {
+            jpeg.extend_from_slice(&[0xff, DRI, 0, 4]);
+            jpeg.extend_from_slice(&restart_interval.to_be_bytes());
+        }
+
+        let count = sampling.len() as u8;
+        jpeg.extend_from_slice(&[0xff, SOF0, 0, 8 + 3 * count, 8]);
+        jpeg.extend_from_slice(&height.to_be_bytes());
+        jpeg.extend_from_slice(&width.to_be_bytes());
+        jpeg.push(count);
+        for (i, (h, v)) in sampling.iter().enumerate() {
+            jpeg.extend_from_slice(&[i as u8 + 1, h << 4 | v, 0]);
+        }
+
+        jpeg.extend_from_slice(&[0xff, SOS, 0, 6 + 2 * count, count]);
+        for i in 0..count {
+            jpeg.extend_from_slice(&[i + 1, 0x00]);
+        }
+        jpeg.extend_from_slice(&[0, 63, 0]);
+
+        let h_max = sampling.iter().map(|s| s.0).max().unwrap() as usize;
+        let v_max = sampling.iter().map(|s| s.1).max().unwrap() as usize;
+        let mcus = (width as usize).div_ceil(8 * h_max) * (height as usize).div_ceil(8 * v_max);
+
+        let mut writer = BitWriter::new();
+        let mut predictions = vec![0i32; sampling.len()];
+        for mcu in 0..mcus {
+            if restart_interval > 0 && mcu > 0 && mcu % restart_interval as usize == 0 {
+                writer.flush();
+                writer.out.extend_from_slice(&[
+                    0xff,
+                    RST0 + ((mcu / restart_interval as usize - 1) % 8) as u8,
+                ]);
+                predictions.fill(0);
+            }
+            for (c, (h, v)) in sampling.iter().enumerate() {
+                for block in 0..(*h * *v) as usize {
+                    // a flat block of value s has the DC coefficient (s - 128) * 8
+                    let dc = (blocks(c, mcu, block) as i32 - 128) * 8;
+                    let diff = dc - predictions[c];
+                    predictions[c] = dc;
+                    let (size, bits) = magnitude(diff);
+                    writer.write(size as u32, 4);
+                    writer.write(bits, size as u32);
+                    // end of block
+                    writer.write(0, 1);
+                }
+            }
+        }
+        writer.flush();
+        jpeg.append(&mut writer.out);
+        jpeg.extend_from_slice(&[0xff, EOI]);
+        jpeg
+    }
+
+    #[test]
+    fn test_parse_jpeg_grayscale() {
+        let jpeg = build_jpeg(12, 10, &[(1, 1)], 0, |_, mcu, _| [10, 60, 110, 250][mcu]);
+        let image = parse_jpeg(jpeg);
+        assert_eq!(image.cols(), 12);
+        assert_eq!(image.rows(), 10);
+        assert_eq!(image.get(0, 0), Some(Color::splat(10)));
+        assert_eq!(image.get(11, 0), Some(Color::splat(60)));
+        assert_eq!(image.get(0, 9), Some(Color::splat(110)));
+        assert_eq!(image.get(8, 8), Some(Color::splat(250)));
+    }
+
+    #[test]
+    fn test_parse_jpeg_subsampling() {
+        // a gray image with constant chroma results in the same colors for every subsampling
+        for sampling in [
+            [(1, 1), (1, 1), (1, 1)],
+            [(2, 1), (1, 1), (1, 1)],
+            [(2, 2), (1, 1), (1, 1)],
+        ] {
+            let luma = [20, 120, 200, 240];
+            let jpeg = build_jpeg(16, 16, &sampling, 0, |c, mcu, block| {
+                match (c, sampling[0]) {
+                    (0, (1, 1)) => luma[(mcu % 2) + (mcu / 2) * 2],
+                    (0, (2, 1)) => luma[block + mcu * 2],
+                    (0, _) => luma[block],
+                    _ => 128,
+                }
+            });
+            let image = parse_jpeg(jpeg);
+            assert_eq!(image.get(0, 0), Some(Color::splat(20)), "{:?}", sampling);
+            assert_eq!(image.get(15, 0), Some(Color::splat(120)), "{:?}", sampling);
+            assert_eq!(image.get(0, 15), Some(Color::splat(200)), "{:?}", sampling);
+            assert_eq!(image.get(15, 15), Some(Color::splat(240)), "{:?}", sampling);
+        }
+    }
+
+    #[test]
+    fn test_parse_jpeg_chroma() {
+        // pure red: Y = 76, Cb = 85, Cr = 255
+        let jpeg = build_jpeg(16, 16, &[(2, 2), (1, 1), (1, 1)], 0, |c, _, _| {
+            [76, 85, 255][c]
+        });
+        let image = parse_jpeg(jpeg);
+        for (x, y) in [(0, 0), (7, 9), (15, 15)] {
+            let color = image.get(x, y).unwrap();
+            assert!(
+                color.r >= 253 && color.g <= 2 && color.b <= 2,
+                "{:?}",
+                color
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_jpeg_restart_markers() {
+        let values = |mcu: usize| (mcu * 25 + 5) as u8;
+        let jpeg = build_jpeg(40, 16, &[(1, 1)], 3, |_, mcu, _| values(mcu));
+        let image = parse_jpeg(jpeg);
+        for mcu in 0..10 {
+            let (x, y) = ((mcu % 5) * 8 + 3, (mcu / 5) * 8 + 3);
+            assert_eq!(image.get(x, y), Some(Color::splat(values(mcu))));
+        }
+    }
+
+    #[test]
+    fn test_parse_jpeg_errors() {
+        assert!(matches!(
+            try_parse_jpeg(b"\xff\xd9".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+        assert!(matches!(
+            try_parse_jpeg(b"\xff\xd8\xff\xd9".to_vec()),
+            Err(Error::InvalidData(_))
+        ));
+
+        let valid = build_jpeg(8, 8, &[(1, 1)], 0, |_, _, _| 100);
+        assert!(matches!(
+            try_parse_jpeg(valid[..valid.len() - 4].to_vec()),
+            Err(Error::TruncatedData { .. })
+        ));
+
+        let mut progressive = valid.clone();
+        let sof = progressive
+            .windows(2)
+            .position(|marker| marker == [0xff, SOF0])
+            .unwrap();
+        progressive[sof + 1] = 0xc2;
+        assert!(matches!(
+            try_parse_jpeg(progressive),
+            Err(Error::InvalidHeader(_))
+        ));
+    }
+
+    /// Get the offset of the first segment with the given marker.
+    fn find_marker(jpeg: &[u8], marker: u8) -> usize {
+        jpeg.windows(2).position(|m| m == [0xff, marker]).unwrap()
+    }
+
+    #[test]
+    fn test_parse_jpeg_huge_frame() {
+        // the frame header claims 65535x65535 pixels, but the scan is empty
+        let mut jpeg = build_jpeg(8, 8, &[(1, 1)], 0, |_, _, _| 100);
+        let sof = find_marker(&jpeg, SOF0);
+        jpeg[sof + 5..sof + 9].copy_from_slice(&[0xff; 4]);
+        let sos = find_marker(&jpeg, SOS);
+        jpeg.truncate(sos + 10);
+        assert!(matches!(
+            try_parse_jpeg(jpeg),
+            Err(Error::TruncatedData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_jpeg_dc_overflow() {
+        let mut jpeg = build_jpeg(320, 8, &[(1, 1)], 0, |_, _, _| 128);
+
+        // replace the quantization table with a 16bit one
+        let dqt = find_marker(&jpeg, DQT);
+        let mut table = vec![0xff, DQT, 0, 131, 0x10];
+        table.extend_from_slice(&[0xff; 128]);
+        jpeg.splice(dqt..dqt + 69, table);
+
+        // every block adds the maximum difference of 2047 to the DC prediction
+        let sos = find_marker(&jpeg, SOS);
+        let start = sos + 10;
+        let end = jpeg.len() - 2;
+        jpeg.splice(start..end, [0xbf, 0xfe].repeat(40));
+
+        let image = parse_jpeg(jpeg);
+        assert_eq!(image.get(319, 7), Some(Color::splat(255)));
+    }
+    /// Mean absolute difference of all samples of two images.
+    fn mean_error(a: &Image, b: &Image) -> f64 {
+        let mut sum = 0u64;
+        for y in 0..a.rows() {
+            for x in 0..a.cols() {
+                let (a, b) = (a.get(x, y).unwrap(), b.get(x, y).unwrap());
+                sum +=
+                    a.r.abs_diff(b.r) as u64 + a.g.abs_diff(b.g) as u64 + a.b.abs_diff(b.b) as u64;
+            }
+        }
+        sum as f64 / (a.cols() * a.rows() * 3) as f64
+    }
+
+    #[test]
+    fn test_scale_quant_table() {
+        assert_eq!(
+            scale_quant_table(&LUMINANCE_QUANT_TABLE, 50),
+            LUMINANCE_QUANT_TABLE
+        );
+        assert_eq!(scale_quant_table(&LUMINANCE_QUANT_TABLE, 100), [1; 64]);
+        assert_eq!(scale_quant_table(&CHROMINANCE_QUANT_TABLE, 1), [255; 64]);
+        assert_eq!(scale_quant_table(&LUMINANCE_QUANT_TABLE, 75)[0], 8);
+    }
+
+    #[test]
+    fn test_jpeg_roundtrip() {
+        let image = crate::ppm::parse_ppm6(std::fs::read("assets/mona_lisa_small.ppm").unwrap());
+        for subsampling in [ChromaSubsampling::Yuv444, ChromaSubsampling::Yuv420] {
+            for optimize_huffman in [false, true] {
+                let options = JpegOptions {
+                    quality: 90,
+                    subsampling,
+                    optimize_huffman,
+                };
+                let parsed = parse_jpeg(image.to_jpeg(&options));
+                assert_eq!(parsed.cols(), image.cols());
+                assert_eq!(parsed.rows(), image.rows());
+                let error = mean_error(&image, &parsed);
+                assert!(error < 4.0, "{:?}: {}", options, error);
+            }
+        }
+    }
+
+    #[test]
+    fn test_jpeg_quality_and_optimization() {
+        let image = crate::ppm::parse_ppm6(std::fs::read("assets/mona_lisa_small.ppm").unwrap());
+        let low = image.to_jpeg(&JpegOptions::new(10));
+        let high = image.to_jpeg(&JpegOptions::new(90));
+        assert!(low.len() < high.len());
+        let high_len = high.len();
+        assert!(mean_error(&image, &parse_jpeg(low)) > mean_error(&image, &parse_jpeg(high)));
+
+        let optimized = image.to_jpeg(&JpegOptions {
+            optimize_huffman: true,
+            ..JpegOptions::new(90)
+        });
+        assert!(optimized.len() < high_len);
+    }
+
+    #[test]
+    fn test_jpeg_roundtrip_grayscale_odd_size() {
+        let mut gray = GrayImage::new(13, 7);
+        for y in 0..7 {
+            for x in 0..13 {
+                gray.set(x, y, (x * 15 + y * 5) as u8);
+            }
+        }
+        let parsed = parse_jpeg(gray.to_jpeg(&JpegOptions::new(100)));
+        assert_eq!(parsed.cols(), 13);
+        assert_eq!(parsed.rows(), 7);
+        for y in 0..7 {
+            for x in 0..13 {
+                let value = parsed.get(x, y).unwrap();
+                assert!(value.r.abs_diff(gray.get(x, y).unwrap()) <= 2);
+                assert_eq!(value.r, value.g);
+            }
+        }
+
+        let image = Image::from(&gray);
+        let options = JpegOptions {
+            quality: 100,
+            subsampling: ChromaSubsampling::Yuv420,
+            optimize_huffman: true,
+        };
+        let parsed = parse_jpeg(image.to_jpeg(&options));
+        assert!(mean_error(&image, &parsed) < 1.0);
+    }
+}
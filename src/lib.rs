@@ -41,18 +41,25 @@
 //! img.draw(&rect);
 //! ```
 //!
-//! This will draw the rectangle to the provided image. To finally save the image to your disk, you can use another utility macro:
+//! This will draw the rectangle to the provided image. To finally save the image to your disk, you can use `Image::save`, which picks the format from the extension of the path and returns a `Result`. Format specific options (e.g., the ASCII representation P3 instead of the binary P6 for `.ppm` files) can be passed with `Image::save_with`:
 //!
 //! ```rust, no_run
-//! # use rustvision::{image::Image, shapes::Rectangle, rgb, ppm::PNM, vec2, save_pnm_p6};
-//! # let mut img = Image::new(400, 400);
-//! # let rect = Rectangle::new(vec2![50.0, 40.0], 100, 70, rgb!(255, 0, 0));
-//! # img.draw(&rect);
-//! save_pnm_p6!("path_to_image.ppm", img);
+//! use rustvision::image::{Image, ImageFormat, SaveOptions};
+//!
+//! # fn main() -> rustvision::error::Result<()> {
+//! let img = Image::new(400, 400);
+//! img.save("path_to_image.png")?;
+//! img.save_with(
+//!     "path_to_image.ppm",
+//!     &SaveOptions {
+//!         format: Some(ImageFormat::Ppm3),
+//!         ..Default::default()
+//!     },
+//! )?;
+//! # Ok(())
+//! # }
 //! ```
 //!
-//! The image will be saved in the P6 (binary PPM) representation. If you prefer the ASCII representation (P3), this library provides another utility macro as well.
-//!
 //! ### Polygons
 //!
 //! Similar to rectangles, you can create more complex polygons:
@@ -126,22 +133,133 @@
 //! # let mut img = Image::new(400, 400);
 //! img.draw(&polygon);
 //!    ```
+//! ### Iterating over pixels
+//!
+//! Instead of looping over all coordinates, you can iterate over the pixels of an image with `pixels`, `enumerate_pixels` and `pixels_mut`, over its rows of pixels with `row_iter` and `row_iter_mut` (`rows` is the number of rows), or over the raw samples of its rows with `raw_rows` and `raw_rows_mut`. `map` and `zip_map` create new images from the pixels of one or two images:
+//!
+//! ```rust
+//! use rustvision::{image::Image, rgb};
+//!
+//! let img = Image::new(400, 400);
+//! let inverted = img.map(|color| rgb!(255 - color.r, 255 - color.g, 255 - color.b));
+//! ```
+//!
+//! With the `rayon` feature enabled, `par_raw_rows`, `par_raw_rows_mut` and `par_map` process the rows of an image in parallel.
+//!
+//! ### Views
+//!
+//! Regions of an image can be borrowed as `ImageView` or `ImageViewMut` without copying any pixels. Views can be nested and shapes can be drawn to mutable views, where they are clipped at the borders of the view:
+//!
+//! ```rust
+//! use rustvision::{image::Image, shapes::Rectangle, rgb, vec2};
+//!
+//! let mut img = Image::new(400, 400);
+//! let mut tile = img.view_mut(100, 100, 50, 50).unwrap();
+//! tile.draw(&Rectangle::new(vec2![10.0, 10.0], 100, 100, rgb!(255, 0, 0)));
+//! assert_eq!(img.get(160, 160), Some(rgb!(0, 0, 0)));
+//! ```
+//!
+//! ### Transforms
+//!
+//! Images can be cropped with `crop`, mirrored with `flip_horizontal` and `flip_vertical`, rotated losslessly with `rotate90`, `rotate180` and `rotate270` and transposed with `transpose`. `pad` extends an image at its borders, where the new pixels are determined by a `BorderMode` (`Constant`, `Replicate`, `Reflect` or `Wrap`):
+//!
+//! ```rust
+//! use rustvision::{image::{BorderMode, Image}, rgb};
+//!
+//! let img = Image::new(400, 300);
+//! let rotated = img.rotate90();
+//! let padded = rotated.pad(10, 10, 10, 10, BorderMode::Constant(rgb!(255, 255, 255)));
+//! assert_eq!((padded.cols(), padded.rows()), (320, 420));
+//! ```
+//!
+//! ### Resizing
+//!
+//! `resize` resamples an image to new dimensions with one of the filters of `FilterType` (`Nearest`, `Area`, `Bilinear`, `Bicubic` or `Lanczos3`). `Area` averages all covered pixels and is best suited for downscaling. `resize_to_fit` fits an image into a box while keeping its aspect ratio:
+//!
+//! ```rust
+//! use rustvision::image::{FilterType, Image};
+//!
+//! let img = Image::new(1920, 1080);
+//! let small = img.resize(640, 360, FilterType::Area);
+//! let thumbnail = img.resize_to_fit(256, 256, FilterType::Bilinear);
+//! assert_eq!((thumbnail.cols(), thumbnail.rows()), (256, 144));
+//! ```
+//!
+//! ### Warping
+//!
+//! `warp_affine` and `warp_perspective` transform an image with a 3x3 matrix, which maps pixel coordinates of the image to pixel coordinates of the result. Every resulting pixel is mapped back with the inverse matrix, interpolated with a `FilterType` and pixels outside of the image are determined by a `BorderMode`:
+//!
+//! ```rust
+//! use rustvision::{image::{BorderMode, FilterType, Image}, mat};
+//!
+//! let img = Image::new(400, 300);
+//! let shear = mat!([[1.0, 0.2, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+//! let warped = img.warp_affine(&shear, 460, 300, FilterType::Bilinear, BorderMode::Replicate);
+//! assert!(warped.is_some());
+//! ```
+//!
+//! ### Pixel types
+//!
+//! `Image` is an alias for `ImageBuffer<Color>`, i.e., an image with 8bit RGB pixels. Images with other pixels are available as aliases as well: `GrayImage`, `GrayAlphaImage`, `RgbaImage`, `Image16`, `GrayImage16`, `ImageF32` and `GrayImageF32`. You can convert between them with `convert`:
+//!
+//! ```rust
+//! use rustvision::{color::Rgba, image::{GrayImage, Image, RgbaImage}};
+//!
+//! let img = Image::new(400, 400);
+//! let gray: GrayImage = img.convert();
+//! let overlay: RgbaImage = img.convert::<Rgba>();
+//! ```
+//!
 //! ## Reading Files
 //!
-//! You can read files and convert them to the internal image representation by using the `load_image` macro:
+//! You can read files and convert them to the internal image representation with `Image::open`, which determines the format from the content of the file:
 //!
 //! ```rust, no_run
-//! use rustvision::{load_image, save_pnm_p3};
+//! use rustvision::image::Image;
 //!
-//! let image = load_image!("assets/mona_lisa_small.ppm");
+//! # fn main() -> rustvision::error::Result<()> {
+//! let image = Image::open("assets/mona_lisa_small.ppm")?;
+//! # Ok(())
+//! # }
 //! ```
 //!
-//! **Note:** Currently, this library only support images in PPM6 (i.e., binary PPM) representation. Trying to read any other file will lead to a panic!
+//! **Note:** Currently, this library support images in the PBM (P1 & P4), PGM (P2 & P5), PPM (P3 & P6) and PAM (P7) representations, as well as BMP, PNG, QOI, baseline JPEG, GIF and TIFF (first frame each), TGA, farbfeld and Radiance HDR (clamped). Trying to read any other file will return an error.
+//!
+//! The `load_image`, `load_file`, `write_file` and `save_pnm_*` macros, which panic on errors, are deprecated in favor of `Image::open` and `Image::save`.
+//!
+//! ### Streams
+//!
+//! Images can also be read from and written to any `std::io::Read` or `std::io::Write` (e.g., stdin, stdout or a socket). Binary PNM and farbfeld images are processed row by row:
+//!
+//! ```rust, no_run
+//! use std::io::{stdin, stdout, BufReader};
+//! use rustvision::image::{Image, ImageFormat};
+//!
+//! # fn main() -> rustvision::error::Result<()> {
+//! let image = Image::read_from(BufReader::new(stdin()))?;
+//! image.write_to(stdout().lock(), ImageFormat::Ppm6)?;
+//! # Ok(())
+//! # }
+//! ```
 
+pub mod bmp;
 pub mod color;
+pub mod error;
+pub mod farbfeld;
 pub mod geometry;
+pub mod gif;
+pub mod hdr;
 pub mod image;
+pub mod jpeg;
+pub mod pam;
+pub mod png;
 pub mod ppm;
+pub mod qoi;
 pub mod shapes;
+pub mod tga;
+pub mod tiff;
+pub mod y4m;
 
+mod bytes;
+mod deflate;
 mod files;
@@ -0,0 +1,496 @@
+//! Reading and writing images in the PAM (P7) format of the Netpbm family.
+
+use crate::{
+    color::Rgba,
+    error::{checked_samples, Error, Result},
+    image::{GrayImage, GrayImage16, Image, Image16, RgbaImage},
+    ppm::{check_data_len, check_max_value, check_sample, scale_to_u8},
+};
+
+/// Enum representing the tuple types of PAM files supported by this library.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TupleType {
+    BlackAndWhite,
+    Grayscale,
+    Rgb,
+    BlackAndWhiteAlpha,
+    GrayscaleAlpha,
+    RgbAlpha,
+}
+
+impl TupleType {
+    /// Get the number of samples per pixel of this tuple type.
+    pub fn depth(&self) -> usize {
+        use TupleType::*;
+
+        match self {
+            BlackAndWhite | Grayscale => 1,
+            BlackAndWhiteAlpha | GrayscaleAlpha => 2,
+            Rgb => 3,
+            RgbAlpha => 4,
+        }
+    }
+
+    /// Get the name of this tuple type as used in the `TUPLTYPE` header field.
+    pub fn name(&self) -> &'static str {
+        use TupleType::*;
+
+        match self {
+            BlackAndWhite => "BLACKANDWHITE",
+            Grayscale => "GRAYSCALE",
+            Rgb => "RGB",
+            BlackAndWhiteAlpha => "BLACKANDWHITE_ALPHA",
+            GrayscaleAlpha => "GRAYSCALE_ALPHA",
+            RgbAlpha => "RGB_ALPHA",
+        }
+    }
+
+    /// Get the tuple type with the given name, or None, if the tuple type is not supported.
+    pub fn from_name(name: &str) -> Option<Self> {
+        use TupleType::*;
+
+        match name {
+            "BLACKANDWHITE" => Some(BlackAndWhite),
+            "GRAYSCALE" => Some(Grayscale),
+            "RGB" => Some(Rgb),
+            "BLACKANDWHITE_ALPHA" => Some(BlackAndWhiteAlpha),
+            "GRAYSCALE_ALPHA" => Some(GrayscaleAlpha),
+            "RGB_ALPHA" => Some(RgbAlpha),
+            _ => None,
+        }
+    }
+
+    /// Guess the tuple type from the number of samples per pixel.
+    fn from_depth(depth: usize) -> Option<Self> {
+        use TupleType::*;
+
+        match depth {
+            1 => Some(Grayscale),
+            2 => Some(GrayscaleAlpha),
+            3 => Some(Rgb),
+            4 => Some(RgbAlpha),
+            _ => None,
+        }
+    }
+
+    fn has_alpha(&self) -> bool {
+        matches!(
+            self,
+            TupleType::BlackAndWhiteAlpha | TupleType::GrayscaleAlpha | TupleType::RgbAlpha
+        )
+    }
+}
+
+/// Trait for transforming any values into their PAM representation.
+pub trait PAM {
+    /// Convert a value into its PAM representation with the given tuple type. Colors are
+    /// converted to grayscale and alpha channels are added or dropped as required by the tuple
+    /// type.
+    fn to_pam(&self, tuple_type: TupleType) -> Vec<u8>;
+}
+
+/// Macro for saving an image in its PAM representation with the given tuple type to the disk.
+#[macro_export]
+macro_rules! save_pam {
+    ($file_name:expr, $img:expr, $tuple_type:expr) => {
+        $crate::try_write_file!($file_name, &$crate::pam::PAM::to_pam(&$img, $tuple_type))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its PAM representation with the given tuple type to the disk.
+/// Returns an error, if the file could not be written.
+#[macro_export]
+macro_rules! try_save_pam {
+    ($file_name:expr, $img:expr, $tuple_type:expr) => {
+        $crate::try_write_file!($file_name, &$crate::pam::PAM::to_pam(&$img, $tuple_type))
+    };
+}
+
+/// Encode an image as PAM. The pixels are provided as RGBA samples in the range of the given
+/// max value.
+fn encode_pam(
+    cols: usize,
+    rows: usize,
+    max_value: u16,
+    tuple_type: TupleType,
+    pixel: impl Fn(usize, usize) -> [u16; 4],
+) -> Vec<u8> {
+    use TupleType::*;
+
+    let bitmap = matches!(tuple_type, BlackAndWhite | BlackAndWhiteAlpha);
+    let out_max_value = if bitmap { 1 } else { max_value };
+
+    let mut pam = format!(
+        "P7\nWIDTH {}\nHEIGHT {}\nDEPTH {}\nMAXVAL {}\nTUPLTYPE {}\nENDHDR\n",
+        cols,
+        rows,
+        tuple_type.depth(),
+        out_max_value,
+        tuple_type.name()
+    )
+    .into_bytes();
+
+    // in contrast to PBM, 0 is black and 1 is white in BLACKANDWHITE tuples
+    let threshold = |sample: u16| (sample as u32 * 2 > max_value as u32) as u16;
+
+    for y in 0..rows {
+        for x in 0..cols {
+            let [r, g, b, a] = pixel(x, y);
+            let luma = ((299 * r as u64 + 587 * g as u64 + 114 * b as u64 + 500) / 1000) as u16;
+
+            let mut samples = match tuple_type {
+                BlackAndWhite | BlackAndWhiteAlpha => vec![threshold(luma)],
+                Grayscale | GrayscaleAlpha => vec![luma],
+                Rgb | RgbAlpha => vec![r, g, b],
+            };
+            if tuple_type.has_alpha() {
+                samples.push(if bitmap { threshold(a) } else { a });
+            }
+
+            for sample in samples {
+                if out_max_value > u8::MAX as u16 {
+                    pam.extend_from_slice(&sample.to_be_bytes());
+                } else {
+                    pam.push(sample as u8);
+                }
+            }
+        }
+    }
+    pam
+}
+
+impl PAM for Image {
+    fn to_pam(&self, tuple_type: TupleType) -> Vec<u8> {
+        encode_pam(self.cols(), self.rows(), 255, tuple_type, |x, y| {
+            let color = self.get(x, y).unwrap_or_default();
+            [color.r as u16, color.g as u16, color.b as u16, 255]
+        })
+    }
+}
+
+impl PAM for GrayImage {
+    fn to_pam(&self, tuple_type: TupleType) -> Vec<u8> {
+        encode_pam(self.cols(), self.rows(), 255, tuple_type, |x, y| {
+            let value = self.get(x, y).unwrap_or_default() as u16;
+            [value, value, value, 255]
+        })
+    }
+}
+
+impl PAM for RgbaImage {
+    fn to_pam(&self, tuple_type: TupleType) -> Vec<u8> {
+        encode_pam(self.cols(), self.rows(), 255, tuple_type, |x, y| {
+            let color = self.get(x, y).unwrap_or_default();
+            [
+                color.r as u16,
+                color.g as u16,
+                color.b as u16,
+                color.a as u16,
+            ]
+        })
+    }
+}
+
+impl PAM for Image16 {
+    fn to_pam(&self, tuple_type: TupleType) -> Vec<u8> {
+        encode_pam(self.cols(), self.rows(), u16::MAX, tuple_type, |x, y| {
+            let color = self.get(x, y).unwrap_or_default();
+            [color.r, color.g, color.b, u16::MAX]
+        })
+    }
+}
+
+impl PAM for GrayImage16 {
+    fn to_pam(&self, tuple_type: TupleType) -> Vec<u8> {
+        encode_pam(self.cols(), self.rows(), u16::MAX, tuple_type, |x, y| {
+            let value = self.get(x, y).unwrap_or_default();
+            [value, value, value, u16::MAX]
+        })
+    }
+}
+
+/// Header of a PAM file.
+struct PamHeader {
+    width: usize,
+    height: usize,
+    max_value: usize,
+    tuple_type: TupleType,
+    /// Offset of the first byte after the header.
+    data_start: usize,
+}
+
+/// Parse a header field of a PAM file as a number.
+fn parse_field(name: &str, value: &str) -> Result<usize> {
+    if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(Error::InvalidHeader(format!(
+            "{} does not have a valid format! ({})",
+            name, value
+        )));
+    }
+    value.parse::<usize>().map_err(|_| {
+        Error::InvalidHeader(format!(
+            "{} does not have a valid format! ({})",
+            name, value
+        ))
+    })
+}
+
+/// Parse the header of a PAM file, which consists of lines with a keyword and a value,
+/// terminated by a line containing `ENDHDR`.
+fn parse_pam_header(inp: &[u8]) -> Result<PamHeader> {
+    match inp.get(0..3) {
+        Some([b'P', b'7', whitespace]) if whitespace.is_ascii_whitespace() => {}
+        _ => {
+            return Err(Error::InvalidMagicConstant(
+                "No valid magic constant".to_string(),
+            ))
+        }
+    }
+
+    let mut pos = 3;
+    let mut width = None;
+    let mut height = None;
+    let mut depth = None;
+    let mut max_value = None;
+    let mut tuple_type: Option<String> = None;
+
+    loop {
+        let Some(line_len) = inp[pos..].iter().position(|&item| item == b'\n') else {
+            return Err(Error::InvalidHeader("No ENDHDR provided".to_string()));
+        };
+        let line = &inp[pos..pos + line_len];
+        pos += line_len + 1;
+
+        let Ok(line) = std::str::from_utf8(line) else {
+            return Err(Error::InvalidHeader("Header needs to be UTF8".to_string()));
+        };
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, value) = line
+            .split_once(|c: char| c.is_ascii_whitespace())
+            .map(|(keyword, value)| (keyword, value.trim()))
+            .unwrap_or((line, ""));
+
+        match keyword {
+            "ENDHDR" => break,
+            "WIDTH" => width = Some(parse_field("Width", value)?),
+            "HEIGHT" => height = Some(parse_field("Height", value)?),
+            "DEPTH" => depth = Some(parse_field("Depth", value)?),
+            "MAXVAL" => max_value = Some(parse_field("Max value", value)?),
+            // multiple tuple type lines are concatenated
+            "TUPLTYPE" => match tuple_type.as_mut() {
+                Some(tuple_type) => {
+                    tuple_type.push(' ');
+                    tuple_type.push_str(value);
+                }
+                None => tuple_type = Some(value.to_string()),
+            },
+            x => {
+                return Err(Error::InvalidHeader(format!(
+                    "Unknown header field ({})",
+                    x
+                )))
+            }
+        }
+    }
+
+    let missing = |name: &str| Error::InvalidHeader(format!("No {} provided", name));
+    let width = width.ok_or_else(|| missing("width"))?;
+    let height = height.ok_or_else(|| missing("height"))?;
+    let depth = depth.ok_or_else(|| missing("depth"))?;
+    let max_value = max_value.ok_or_else(|| missing("max value"))?;
+
+    check_max_value(max_value)?;
+
+    let tuple_type = match tuple_type {
+        Some(name) => match TupleType::from_name(&name) {
+            Some(tuple_type) => tuple_type,
+            // unknown tuple types are interpreted by their depth
+            None => TupleType::from_depth(depth).ok_or_else(|| {
+                Error::InvalidHeader(format!("Unsupported tuple type ({})", name))
+            })?,
+        },
+        None => TupleType::from_depth(depth)
+            .ok_or_else(|| Error::InvalidHeader(format!("Unsupported depth ({})", depth)))?,
+    };
+
+    if tuple_type.depth() != depth {
+        return Err(Error::InvalidHeader(format!(
+            "Depth {} does not match tuple type {}",
+            depth,
+            tuple_type.name()
+        )));
+    }
+
+    Ok(PamHeader {
+        width,
+        height,
+        max_value,
+        tuple_type,
+        data_start: pos,
+    })
+}
+
+/// Parse a vector of u8 into a valid PAM image. Samples are rescaled to 8bit according to the
+/// max value of the file and images without an alpha channel are fully opaque. This function
+/// will panic, if the format is not valid. See `try_parse_pam` for a fallible variant.
+pub fn parse_pam(inp: Vec<u8>) -> RgbaImage {
+    try_parse_pam(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PAM image.
+pub fn try_parse_pam(inp: Vec<u8>) -> Result<RgbaImage> {
+    use TupleType::*;
+
+    let PamHeader {
+        width,
+        height,
+        max_value,
+        tuple_type,
+        data_start,
+    } = parse_pam_header(&inp)?;
+
+    let depth = tuple_type.depth();
+    let bytes_per_sample = if max_value > u8::MAX as usize { 2 } else { 1 };
+    let expected = checked_samples(width, height, checked_samples(depth, bytes_per_sample, 1)?)?;
+    check_data_len(&inp, data_start, expected)?;
+
+    let samples = inp[data_start..data_start + expected]
+        .chunks_exact(bytes_per_sample)
+        .map(|sample| {
+            let sample = match sample {
+                [high, low] => u16::from_be_bytes([*high, *low]),
+                _ => sample[0] as u16,
+            };
+            check_sample(sample as usize, max_value).map(|sample| scale_to_u8(sample, max_value))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut image = RgbaImage::new(width, height);
+
+    for (i, pixel) in samples.chunks_exact(depth).enumerate() {
+        let color = match tuple_type {
+            BlackAndWhite | Grayscale => Rgba::new(pixel[0], pixel[0], pixel[0], u8::MAX),
+            BlackAndWhiteAlpha | GrayscaleAlpha => {
+                Rgba::new(pixel[0], pixel[0], pixel[0], pixel[1])
+            }
+            Rgb => Rgba::new(pixel[0], pixel[1], pixel[2], u8::MAX),
+            RgbAlpha => Rgba::new(pixel[0], pixel[1], pixel[2], pixel[3]),
+        };
+        image.set(i % width, i / width, color);
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color16, rgb, rgba};
+
+    #[test]
+    fn test_parse_pam_rgb_alpha() {
+        let mut inp =
+            b"P7\n# comment\nWIDTH 2\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n"
+                .to_vec();
+        inp.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let image = parse_pam(inp);
+        assert_eq!(image.cols(), 2);
+        assert_eq!(image.rows(), 1);
+        assert_eq!(image.get(0, 0), Some(rgba!(1, 2, 3, 4)));
+        assert_eq!(image.get(1, 0), Some(rgba!(5, 6, 7, 8)));
+    }
+
+    #[test]
+    fn test_parse_pam_grayscale_alpha_16bit() {
+        let mut inp =
+            b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 2\nMAXVAL 65535\nTUPLTYPE GRAYSCALE_ALPHA\nENDHDR\n"
+                .to_vec();
+        inp.extend_from_slice(&[0xff, 0xff, 0x80, 0x00]);
+        let image = parse_pam(inp);
+        assert_eq!(image.get(0, 0), Some(rgba!(255, 255, 255, 128)));
+    }
+
+    #[test]
+    fn test_parse_pam_without_tuple_type() {
+        let mut inp = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 15\nENDHDR\n".to_vec();
+        inp.extend_from_slice(&[15, 0, 7]);
+        assert_eq!(parse_pam(inp).get(0, 0), Some(rgba!(255, 0, 119)));
+    }
+
+    #[test]
+    fn test_parse_pam_errors() {
+        assert!(matches!(
+            try_parse_pam(b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\n".to_vec()),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            try_parse_pam(
+                b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n"
+                    .to_vec()
+            ),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            try_parse_pam(b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 1\nMAXVAL 255\nENDHDR\n\x00".to_vec()),
+            Err(Error::TruncatedData {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            try_parse_pam(b"P6\n1 1\n255\n".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+    }
+
+    #[test]
+    fn test_pam_roundtrip() {
+        let mut image = RgbaImage::new(3, 2);
+        image.set(0, 0, rgba!(1, 2, 3, 4));
+        image.set(2, 1, rgba!(255, 128, 0, 255));
+
+        let parsed = parse_pam(image.to_pam(TupleType::RgbAlpha));
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(parsed.get(x, y), image.get(x, y));
+            }
+        }
+
+        let parsed = parse_pam(image.to_pam(TupleType::Rgb));
+        assert_eq!(parsed.get(0, 0), Some(rgba!(1, 2, 3, 255)));
+
+        let parsed = parse_pam(image.to_pam(TupleType::GrayscaleAlpha));
+        assert_eq!(parsed.get(2, 1), Some(rgba!(151, 151, 151, 255)));
+        assert_eq!(parsed.get(0, 0), Some(rgba!(2, 2, 2, 4)));
+    }
+
+    #[test]
+    fn test_pam_black_and_white() {
+        let mut image = Image::new(2, 1);
+        image.set(1, 0, rgb!(255));
+        let pam = image.to_pam(TupleType::BlackAndWhite);
+        assert!(pam.ends_with(b"MAXVAL 1\nTUPLTYPE BLACKANDWHITE\nENDHDR\n\x00\x01"));
+        let parsed = parse_pam(pam);
+        assert_eq!(parsed.get(0, 0), Some(rgba!(0, 0, 0)));
+        assert_eq!(parsed.get(1, 0), Some(rgba!(255, 255, 255)));
+    }
+
+    #[test]
+    fn test_pam_16bit() {
+        let mut image = Image16::new(1, 1);
+        image.set(0, 0, Color16::new(0x0102, 0x0304, 0x0506));
+        let pam = image.to_pam(TupleType::Rgb);
+        assert!(pam.ends_with(b"MAXVAL 65535\nTUPLTYPE RGB\nENDHDR\n\x01\x02\x03\x04\x05\x06"));
+
+        let mut gray = GrayImage::new(1, 1);
+        gray.set(0, 0, 42);
+        assert!(gray
+            .to_pam(TupleType::Grayscale)
+            .ends_with(b"DEPTH 1\nMAXVAL 255\nTUPLTYPE GRAYSCALE\nENDHDR\n\x2a"));
+    }
+}
@@ -0,0 +1,895 @@
+//! Reading and writing images in the PNG format.
+
+use crate::{
+    bytes::{read_bytes, read_u32_be},
+    color::{u16_to_u8, Color, Rgba},
+    deflate::{zlib_compress, zlib_decompress},
+    error::{checked_samples, Error, Result},
+    image::{GrayImage, GrayImage16, Image, Image16, RgbaImage},
+};
+
+/// Signature at the start of every PNG file.
+pub(crate) const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+const COLOR_TYPE_GRAY: u8 = 0;
+const COLOR_TYPE_RGB: u8 = 2;
+const COLOR_TYPE_PALETTE: u8 = 3;
+const COLOR_TYPE_GRAY_ALPHA: u8 = 4;
+const COLOR_TYPE_RGBA: u8 = 6;
+
+const FILTER_NONE: u8 = 0;
+const FILTER_SUB: u8 = 1;
+const FILTER_UP: u8 = 2;
+const FILTER_AVERAGE: u8 = 3;
+const FILTER_PAETH: u8 = 4;
+
+/// Origin and spacing (x, y, dx, dy) of the seven passes of Adam7 interlacing.
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Lookup table for the CRC-32 used by PNG chunks.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+/// Calculate the CRC-32 of the given data.
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(u32::MAX, |crc, byte| {
+        CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Trait for transforming any values into their PNG representation.
+pub trait PNG {
+    /// Convert a value into its PNG representation.
+    fn to_png(&self) -> Vec<u8>;
+}
+
+/// Macro for saving an image in its PNG representation to the disk.
+#[macro_export]
+macro_rules! save_png {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::png::PNG::to_png(&$img))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its PNG representation to the disk. Returns an error, if the
+/// file could not be written.
+#[macro_export]
+macro_rules! try_save_png {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::png::PNG::to_png(&$img))
+    };
+}
+
+/// Append a chunk with its length and CRC to the output.
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// The Paeth predictor, which picks the neighbour closest to `a + b - c`.
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Get the prediction of a filter for the byte at index `i` of a row. `prev` is empty for the
+/// first row of an image (or pass).
+fn predict(filter: u8, row: &[u8], prev: &[u8], i: usize, bpp: usize) -> u8 {
+    let a = if i >= bpp { row[i - bpp] } else { 0 };
+    let b = prev.get(i).copied().unwrap_or(0);
+    let c = if i >= bpp {
+        prev.get(i - bpp).copied().unwrap_or(0)
+    } else {
+        0
+    };
+    match filter {
+        FILTER_SUB => a,
+        FILTER_UP => b,
+        FILTER_AVERAGE => ((a as u16 + b as u16) / 2) as u8,
+        FILTER_PAETH => paeth(a, b, c),
+        _ => 0,
+    }
+}
+
+/// Apply a filter to a row and append the filter type and the filtered bytes to the output.
+fn filter_row(filter: u8, row: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    out.push(filter);
+    for i in 0..row.len() {
+        out.push(row[i].wrapping_sub(predict(filter, row, prev, i, bpp)));
+    }
+}
+
+/// Encode raw scanlines as PNG. Every row is filtered with the filter, which results in the
+/// smallest sum of absolute differences.
+fn encode_png(
+    cols: usize,
+    rows: usize,
+    color_type: u8,
+    bit_depth: u8,
+    scanline: impl Fn(usize) -> Vec<u8>,
+) -> Vec<u8> {
+    let channels = match color_type {
+        COLOR_TYPE_GRAY => 1,
+        COLOR_TYPE_GRAY_ALPHA => 2,
+        COLOR_TYPE_RGB => 3,
+        _ => 4,
+    };
+    let bpp = channels * bit_depth as usize / 8;
+
+    let mut filtered = vec![];
+    let mut prev = vec![];
+    let mut candidate = vec![];
+    for y in 0..rows {
+        let row = scanline(y);
+        let mut best: Option<(u64, Vec<u8>)> = None;
+        for filter in FILTER_NONE..=FILTER_PAETH {
+            candidate.clear();
+            filter_row(filter, &row, &prev, bpp, &mut candidate);
+            let cost = candidate[1..]
+                .iter()
+                .map(|byte| (*byte as i8).unsigned_abs() as u64)
+                .sum();
+            if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                best = Some((cost, candidate.clone()));
+            }
+        }
+        if let Some((_, best)) = best {
+            filtered.extend_from_slice(&best);
+        }
+        prev = row;
+    }
+
+    let mut header = vec![];
+    header.extend_from_slice(&(cols as u32).to_be_bytes());
+    header.extend_from_slice(&(rows as u32).to_be_bytes());
+    // bit depth, color type, compression, filter method and interlace method
+    header.extend_from_slice(&[bit_depth, color_type, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_compress(&filtered));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+impl PNG for Image {
+    fn to_png(&self) -> Vec<u8> {
+        encode_png(self.cols(), self.rows(), COLOR_TYPE_RGB, 8, |y| {
+            (0..self.cols())
+                .flat_map(|x| {
+                    let color = self.get(x, y).unwrap_or_default();
+                    [color.r, color.g, color.b]
+                })
+                .collect()
+        })
+    }
+}
+
+impl PNG for RgbaImage {
+    fn to_png(&self) -> Vec<u8> {
+        encode_png(self.cols(), self.rows(), COLOR_TYPE_RGBA, 8, |y| {
+            (0..self.cols())
+                .flat_map(|x| {
+                    let color = self.get(x, y).unwrap_or_default();
+                    [color.r, color.g, color.b, color.a]
+                })
+                .collect()
+        })
+    }
+}
+
+impl PNG for GrayImage {
+    fn to_png(&self) -> Vec<u8> {
+        encode_png(self.cols(), self.rows(), COLOR_TYPE_GRAY, 8, |y| {
+            (0..self.cols())
+                .map(|x| self.get(x, y).unwrap_or_default())
+                .collect()
+        })
+    }
+}
+
+impl PNG for Image16 {
+    fn to_png(&self) -> Vec<u8> {
+        encode_png(self.cols(), self.rows(), COLOR_TYPE_RGB, 16, |y| {
+            (0..self.cols())
+                .flat_map(|x| {
+                    let color = self.get(x, y).unwrap_or_default();
+                    [color.r, color.g, color.b]
+                })
+                .flat_map(u16::to_be_bytes)
+                .collect()
+        })
+    }
+}
+
+impl PNG for GrayImage16 {
+    fn to_png(&self) -> Vec<u8> {
+        encode_png(self.cols(), self.rows(), COLOR_TYPE_GRAY, 16, |y| {
+            (0..self.cols())
+                .flat_map(|x| self.get(x, y).unwrap_or_default().to_be_bytes())
+                .collect()
+        })
+    }
+}
+
+/// Transparency information of the tRNS chunk.
+enum Transparency {
+    None,
+    /// Gray sample, which is fully transparent.
+    Gray(u16),
+    /// RGB samples, which are fully transparent.
+    Rgb([u16; 3]),
+    /// Alpha values of the palette entries.
+    Palette(Vec<u8>),
+}
+
+/// Header and ancillary information of a PNG file.
+struct PngInfo {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+    palette: Vec<Color>,
+    transparency: Transparency,
+}
+
+impl PngInfo {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            COLOR_TYPE_GRAY | COLOR_TYPE_PALETTE => 1,
+            COLOR_TYPE_GRAY_ALPHA => 2,
+            COLOR_TYPE_RGB => 3,
+            _ => 4,
+        }
+    }
+
+    /// Get the number of bytes of a row with the given width (without the filter type).
+    fn stride(&self, width: usize) -> Result<usize> {
+        Ok(checked_samples(width, self.channels(), self.bit_depth as usize)?.div_ceil(8))
+    }
+
+    /// Get the number of bytes of the complete filtered image data.
+    fn raw_len(&self) -> Result<usize> {
+        let passes = if self.interlaced {
+            ADAM7.to_vec()
+        } else {
+            vec![(0, 0, 1, 1)]
+        };
+        passes.into_iter().try_fold(0usize, |len, pass| {
+            let (width, height) = pass_size(self.width, self.height, pass);
+            if width == 0 || height == 0 {
+                return Ok(len);
+            }
+            let pass_len = checked_samples(self.stride(width)? + 1, height, 1)?;
+            len.checked_add(pass_len).ok_or(Error::DimensionOverflow {
+                width: self.width,
+                height: self.height,
+            })
+        })
+    }
+}
+
+/// Get the size of the reduced image of an interlacing pass.
+fn pass_size(
+    width: usize,
+    height: usize,
+    (x0, y0, dx, dy): (usize, usize, usize, usize),
+) -> (usize, usize) {
+    (
+        width.saturating_sub(x0).div_ceil(dx),
+        height.saturating_sub(y0).div_ceil(dy),
+    )
+}
+
+/// Parse and validate the IHDR chunk.
+fn parse_ihdr(data: &[u8]) -> Result<PngInfo> {
+    if data.len() != 13 {
+        return Err(Error::InvalidHeader(format!(
+            "IHDR chunk has invalid length ({})",
+            data.len()
+        )));
+    }
+    let width = read_u32_be(data, 0)? as usize;
+    let height = read_u32_be(data, 4)? as usize;
+    let [bit_depth, color_type, compression, filter, interlace] =
+        [8, 9, 10, 11, 12].map(|i| data[i]);
+
+    if width == 0 || height == 0 || width > i32::MAX as usize || height > i32::MAX as usize {
+        return Err(Error::InvalidHeader(format!(
+            "Invalid image dimensions ({}x{})",
+            width, height
+        )));
+    }
+
+    let valid_depth = match color_type {
+        COLOR_TYPE_GRAY => matches!(bit_depth, 1 | 2 | 4 | 8 | 16),
+        COLOR_TYPE_PALETTE => matches!(bit_depth, 1 | 2 | 4 | 8),
+        COLOR_TYPE_RGB | COLOR_TYPE_GRAY_ALPHA | COLOR_TYPE_RGBA => matches!(bit_depth, 8 | 16),
+        _ => {
+            return Err(Error::InvalidHeader(format!(
+                "Unsupported color type ({})",
+                color_type
+            )));
+        }
+    };
+    if !valid_depth {
+        return Err(Error::InvalidHeader(format!(
+            "Invalid bit depth {} for color type {}",
+            bit_depth, color_type
+        )));
+    }
+    if compression != 0 || filter != 0 || interlace > 1 {
+        return Err(Error::InvalidHeader(format!(
+            "Unsupported compression, filter or interlace method ({}, {}, {})",
+            compression, filter, interlace
+        )));
+    }
+
+    Ok(PngInfo {
+        width,
+        height,
+        bit_depth,
+        color_type,
+        interlaced: interlace == 1,
+        palette: vec![],
+        transparency: Transparency::None,
+    })
+}
+
+/// Parse the chunks of a PNG file. Returns the header information and the concatenated content of
+/// all IDAT chunks.
+fn parse_chunks(inp: &[u8]) -> Result<(PngInfo, Vec<u8>)> {
+    if inp.get(0..8) != Some(&SIGNATURE[..]) {
+        return Err(Error::InvalidMagicConstant(
+            "No valid PNG signature".to_string(),
+        ));
+    }
+
+    let mut info: Option<PngInfo> = None;
+    let mut idat = vec![];
+    let mut pos = SIGNATURE.len();
+
+    loop {
+        let len = read_u32_be(inp, pos)? as usize;
+        if len > i32::MAX as usize {
+            return Err(Error::InvalidData(format!(
+                "Invalid chunk length ({})",
+                len
+            )));
+        }
+        let chunk = read_bytes(inp, pos + 4, len + 4)?;
+        let crc = read_u32_be(inp, pos + 8 + len)?;
+        if crc != crc32(chunk) {
+            return Err(Error::InvalidData(format!(
+                "CRC mismatch in chunk '{}'",
+                String::from_utf8_lossy(&chunk[..4])
+            )));
+        }
+        pos += len + 12;
+
+        let (chunk_type, data) = chunk.split_at(4);
+        if chunk_type == b"IHDR" {
+            if info.is_some() {
+                return Err(Error::InvalidData("Duplicate IHDR chunk".to_string()));
+            }
+            info = Some(parse_ihdr(data)?);
+            continue;
+        }
+
+        let Some(info) = info.as_mut() else {
+            return Err(Error::InvalidData(
+                "First chunk is not an IHDR chunk".to_string(),
+            ));
+        };
+
+        match chunk_type {
+            b"PLTE" => {
+                if !len.is_multiple_of(3) || len / 3 > 256 || !idat.is_empty() {
+                    return Err(Error::InvalidData("Invalid PLTE chunk".to_string()));
+                }
+                info.palette = data.chunks_exact(3).map(Color::from_u8_array).collect();
+            }
+            b"tRNS" => {
+                info.transparency = match (info.color_type, data) {
+                    (COLOR_TYPE_GRAY, [high, low]) => {
+                        Transparency::Gray(u16::from_be_bytes([*high, *low]))
+                    }
+                    (COLOR_TYPE_RGB, [r0, r1, g0, g1, b0, b1]) => Transparency::Rgb([
+                        u16::from_be_bytes([*r0, *r1]),
+                        u16::from_be_bytes([*g0, *g1]),
+                        u16::from_be_bytes([*b0, *b1]),
+                    ]),
+                    (COLOR_TYPE_PALETTE, alpha) if alpha.len() <= info.palette.len() => {
+                        Transparency::Palette(alpha.to_vec())
+                    }
+                    _ => return Err(Error::InvalidData("Invalid tRNS chunk".to_string())),
+                };
+            }
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {
+                // bit 5 of the first byte is set for ancillary chunks, which can be ignored
+                if chunk_type[0] & 0x20 == 0 {
+                    return Err(Error::InvalidData(format!(
+                        "Unsupported critical chunk '{}'",
+                        String::from_utf8_lossy(chunk_type)
+                    )));
+                }
+            }
+        }
+    }
+
+    let Some(info) = info else {
+        return Err(Error::InvalidData("Missing IHDR chunk".to_string()));
+    };
+    if info.color_type == COLOR_TYPE_PALETTE && info.palette.is_empty() {
+        return Err(Error::InvalidData("Missing PLTE chunk".to_string()));
+    }
+    if idat.is_empty() {
+        return Err(Error::InvalidData("Missing IDAT chunk".to_string()));
+    }
+    Ok((info, idat))
+}
+
+/// Reverse the filters of all rows of an image (or pass). Returns the unfiltered rows without
+/// filter types.
+fn unfilter(data: &[u8], stride: usize, height: usize, bpp: usize) -> Result<Vec<u8>> {
+    let mut out = vec![0u8; stride * height];
+    for (y, line) in data.chunks_exact(stride + 1).take(height).enumerate() {
+        let filter = line[0];
+        if filter > FILTER_PAETH {
+            return Err(Error::InvalidData(format!(
+                "Invalid filter type ({})",
+                filter
+            )));
+        }
+        let (prev, row) = out.split_at_mut(y * stride);
+        let prev = &prev[prev.len().saturating_sub(stride)..];
+        let row = &mut row[..stride];
+        for i in 0..stride {
+            row[i] = line[i + 1].wrapping_add(predict(filter, row, prev, i, bpp));
+        }
+    }
+    Ok(out)
+}
+
+/// Read the sample with the given index from an unfiltered row.
+fn read_sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[2 * index], row[2 * index + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            // samples of less than 8 bits are packed starting with the most significant bit
+            let bit = index * bit_depth as usize;
+            let shift = 8 - bit_depth as usize - bit % 8;
+            ((row[bit / 8] >> shift) & ((1 << bit_depth) - 1)) as u16
+        }
+    }
+}
+
+/// Scale a sample of the given bit depth to 8 bits.
+fn scale_sample(sample: u16, bit_depth: u8) -> u8 {
+    match bit_depth {
+        16 => u16_to_u8(sample),
+        8 => sample as u8,
+        _ => (sample as u32 * 255 / ((1 << bit_depth) - 1)) as u8,
+    }
+}
+
+/// Convert the samples of a single pixel into a color.
+fn to_rgba(info: &PngInfo, samples: &[u16]) -> Result<Rgba> {
+    let scale = |sample: u16| scale_sample(sample, info.bit_depth);
+    Ok(match info.color_type {
+        COLOR_TYPE_GRAY => {
+            let value = scale(samples[0]);
+            let alpha = match info.transparency {
+                Transparency::Gray(key) if key == samples[0] => 0,
+                _ => u8::MAX,
+            };
+            Rgba::new(value, value, value, alpha)
+        }
+        COLOR_TYPE_RGB => {
+            let alpha = match info.transparency {
+                Transparency::Rgb(key) if key == samples[..3] => 0,
+                _ => u8::MAX,
+            };
+            Rgba::new(
+                scale(samples[0]),
+                scale(samples[1]),
+                scale(samples[2]),
+                alpha,
+            )
+        }
+        COLOR_TYPE_PALETTE => {
+            let index = samples[0] as usize;
+            let Some(color) = info.palette.get(index) else {
+                return Err(Error::InvalidData(format!(
+                    "Palette index {} out of bounds",
+                    index
+                )));
+            };
+            let alpha = match &info.transparency {
+                Transparency::Palette(alpha) => alpha.get(index).copied().unwrap_or(u8::MAX),
+                _ => u8::MAX,
+            };
+            Rgba::new(color.r, color.g, color.b, alpha)
+        }
+        COLOR_TYPE_GRAY_ALPHA => {
+            let value = scale(samples[0]);
+            Rgba::new(value, value, value, scale(samples[1]))
+        }
+        _ => Rgba::new(
+            scale(samples[0]),
+            scale(samples[1]),
+            scale(samples[2]),
+            scale(samples[3]),
+        ),
+    })
+}
+
+/// Parse a vector of u8 into a valid PNG image. Samples are rescaled to 8bit and images without
+/// transparency information are fully opaque. This function will panic, if the format is not
+/// valid. See `try_parse_png` for a fallible variant.
+pub fn parse_png(inp: Vec<u8>) -> RgbaImage {
+    try_parse_png(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PNG image.
+pub fn try_parse_png(inp: Vec<u8>) -> Result<RgbaImage> {
+    let (info, idat) = parse_chunks(&inp)?;
+
+    let expected = info.raw_len()?;
+    let raw = zlib_decompress(&idat, expected)?;
+    if raw.len() < expected {
+        return Err(Error::TruncatedData {
+            expected,
+            actual: raw.len(),
+        });
+    }
+
+    let passes = if info.interlaced {
+        ADAM7.to_vec()
+    } else {
+        vec![(0, 0, 1, 1)]
+    };
+    let channels = info.channels();
+    let bpp = (channels * info.bit_depth as usize / 8).max(1);
+    let mut image = RgbaImage::new(info.width, info.height);
+    let mut samples = vec![0u16; channels];
+    let mut pos = 0;
+
+    for pass in passes {
+        let (width, height) = pass_size(info.width, info.height, pass);
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let stride = info.stride(width)?;
+        let pass_len = (stride + 1) * height;
+        let rows = unfilter(&raw[pos..pos + pass_len], stride, height, bpp)?;
+        pos += pass_len;
+
+        let (x0, y0, dx, dy) = pass;
+        for (y, row) in rows.chunks_exact(stride).enumerate() {
+            for x in 0..width {
+                for (c, sample) in samples.iter_mut().enumerate() {
+                    *sample = read_sample(row, x * channels + c, info.bit_depth);
+                }
+                image.set(x0 + x * dx, y0 + y * dy, to_rgba(&info, &samples)?);
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color16, ppm::parse_ppm6, rgb, rgba};
+
+    /// Build a PNG file from unfiltered raw rows (with filter type 0 prepended to every row).
+    fn build_png(
+        width: u32,
+        height: u32,
+        bit_depth: u8,
+        color_type: u8,
+        interlace: u8,
+        chunks: &[(&[u8; 4], &[u8])],
+        raw: &[u8],
+    ) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[bit_depth, color_type, 0, 0, interlace]);
+
+        let mut png = SIGNATURE.to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        for (chunk_type, data) in chunks {
+            write_chunk(&mut png, chunk_type, data);
+        }
+        write_chunk(&mut png, b"IDAT", &zlib_compress(raw));
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+    }
+
+    #[test]
+    fn test_paeth() {
+        assert_eq!(paeth(10, 20, 15), 15);
+        assert_eq!(paeth(10, 20, 5), 20);
+        assert_eq!(paeth(10, 20, 25), 10);
+    }
+
+    #[test]
+    fn test_parse_png_all_filters() {
+        // 5 rows of 2 RGB pixels, every row with a different filter
+        let rows: Vec<Vec<u8>> = (0..5u8)
+            .map(|y| (0..6u8).map(|i| y * 40 + i * 7 + 3).collect())
+            .collect();
+        let mut raw = vec![];
+        let mut prev: &[u8] = &[];
+        for (filter, row) in rows.iter().enumerate() {
+            filter_row(filter as u8, row, prev, 3, &mut raw);
+            prev = row;
+        }
+
+        let mut png = build_png(2, 5, 8, COLOR_TYPE_RGB, 0, &[], &[]);
+        // replace the IDAT chunk with the filtered data
+        png.truncate(8 + 25);
+        write_chunk(&mut png, b"IDAT", &zlib_compress(&raw));
+        write_chunk(&mut png, b"IEND", &[]);
+
+        let image = parse_png(png);
+        for (y, row) in rows.iter().enumerate() {
+            for x in 0..2 {
+                assert_eq!(
+                    image.get(x, y),
+                    Some(rgba!(row[3 * x], row[3 * x + 1], row[3 * x + 2], 255))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_png_palette_with_transparency() {
+        // 2bit indices: 0, 1, 2, 3, 1
+        let png = build_png(
+            5,
+            1,
+            2,
+            COLOR_TYPE_PALETTE,
+            0,
+            &[
+                (b"PLTE", &[255, 0, 0, 0, 255, 0, 0, 0, 255, 9, 9, 9]),
+                (b"tRNS", &[0, 128]),
+            ],
+            &[0, 0b0001_1011, 0b0100_0000],
+        );
+        let image = parse_png(png);
+        assert_eq!(image.get(0, 0), Some(rgba!(255, 0, 0, 0)));
+        assert_eq!(image.get(1, 0), Some(rgba!(0, 255, 0, 128)));
+        assert_eq!(image.get(2, 0), Some(rgba!(0, 0, 255, 255)));
+        assert_eq!(image.get(3, 0), Some(rgba!(9, 9, 9, 255)));
+        assert_eq!(image.get(4, 0), Some(rgba!(0, 255, 0, 128)));
+    }
+
+    #[test]
+    fn test_parse_png_low_bit_depth_gray() {
+        let png = build_png(
+            10,
+            2,
+            1,
+            COLOR_TYPE_GRAY,
+            0,
+            &[(b"tRNS", &[0, 0])],
+            &[0, 0b1010_1010, 0b1100_0000, 0, 0xff, 0xff],
+        );
+        let image = parse_png(png);
+        assert_eq!(image.get(0, 0), Some(rgba!(255, 255, 255, 255)));
+        assert_eq!(image.get(1, 0), Some(rgba!(0, 0, 0, 0)));
+        assert_eq!(image.get(8, 0), Some(rgba!(255, 255, 255, 255)));
+        assert_eq!(image.get(9, 0), Some(rgba!(255, 255, 255, 255)));
+        assert_eq!(image.get(9, 1), Some(rgba!(255, 255, 255, 255)));
+
+        let png = build_png(2, 1, 4, COLOR_TYPE_GRAY, 0, &[], &[0, 0x5f]);
+        let image = parse_png(png);
+        assert_eq!(image.get(0, 0), Some(rgba!(85, 85, 85, 255)));
+        assert_eq!(image.get(1, 0), Some(rgba!(255, 255, 255, 255)));
+    }
+
+    #[test]
+    fn test_parse_png_16bit_gray_alpha() {
+        let png = build_png(
+            1,
+            1,
+            16,
+            COLOR_TYPE_GRAY_ALPHA,
+            0,
+            &[],
+            &[0, 0xff, 0xff, 0x80, 0x00],
+        );
+        assert_eq!(parse_png(png).get(0, 0), Some(rgba!(255, 255, 255, 128)));
+    }
+
+    #[test]
+    fn test_parse_png_adam7() {
+        let (width, height) = (11, 9);
+        let color = |x: usize, y: usize| rgba!((x * 20) as u8, (y * 25) as u8, 7, (x + y) as u8);
+
+        let mut raw = vec![];
+        for (x0, y0, dx, dy) in ADAM7 {
+            for y in (y0..height).step_by(dy) {
+                let columns: Vec<usize> = (x0..width).step_by(dx).collect();
+                if columns.is_empty() {
+                    continue;
+                }
+                raw.push(FILTER_NONE);
+                for x in columns {
+                    let color = color(x, y);
+                    raw.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+                }
+            }
+        }
+
+        let png = build_png(
+            width as u32,
+            height as u32,
+            8,
+            COLOR_TYPE_RGBA,
+            1,
+            &[],
+            &raw,
+        );
+        let image = parse_png(png);
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(image.get(x, y), Some(color(x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_png_adam7_tiny() {
+        // a single pixel image only contains data in the first pass
+        let png = build_png(1, 1, 8, COLOR_TYPE_GRAY, 1, &[], &[0, 42]);
+        assert_eq!(parse_png(png).get(0, 0), Some(rgba!(42, 42, 42, 255)));
+    }
+
+    #[test]
+    fn test_parse_png_errors() {
+        assert!(matches!(
+            try_parse_png(b"not a png".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+
+        let valid = build_png(1, 1, 8, COLOR_TYPE_GRAY, 0, &[], &[0, 42]);
+
+        let mut corrupted = valid.clone();
+        corrupted[20] ^= 0x01;
+        assert!(matches!(
+            try_parse_png(corrupted),
+            Err(Error::InvalidData(_))
+        ));
+
+        assert!(matches!(
+            try_parse_png(valid[..valid.len() - 6].to_vec()),
+            Err(Error::TruncatedData { .. })
+        ));
+
+        let unknown = build_png(1, 1, 8, COLOR_TYPE_GRAY, 0, &[(b"ABCD", &[])], &[0, 42]);
+        assert!(matches!(try_parse_png(unknown), Err(Error::InvalidData(_))));
+
+        let ancillary = build_png(1, 1, 8, COLOR_TYPE_GRAY, 0, &[(b"tEXt", b"a\0b")], &[0, 42]);
+        assert!(try_parse_png(ancillary).is_ok());
+
+        let invalid_depth = build_png(1, 1, 4, COLOR_TYPE_RGB, 0, &[], &[0, 0]);
+        assert!(matches!(
+            try_parse_png(invalid_depth),
+            Err(Error::InvalidHeader(_))
+        ));
+
+        let missing_palette = build_png(1, 1, 8, COLOR_TYPE_PALETTE, 0, &[], &[0, 0]);
+        assert!(matches!(
+            try_parse_png(missing_palette),
+            Err(Error::InvalidData(_))
+        ));
+
+        let short_data = build_png(2, 2, 8, COLOR_TYPE_GRAY, 0, &[], &[0, 1, 2]);
+        assert!(matches!(
+            try_parse_png(short_data),
+            Err(Error::TruncatedData { .. })
+        ));
+
+        let invalid_filter = build_png(1, 1, 8, COLOR_TYPE_GRAY, 0, &[], &[5, 0]);
+        assert!(matches!(
+            try_parse_png(invalid_filter),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn test_png_roundtrip() {
+        let image = parse_ppm6(std::fs::read("assets/mona_lisa_small.ppm").unwrap());
+        let parsed = Image::from(&parse_png(image.to_png()));
+        assert_eq!(parsed.cols(), image.cols());
+        assert_eq!(parsed.rows(), image.rows());
+        for y in 0..image.rows() {
+            for x in 0..image.cols() {
+                assert_eq!(parsed.get(x, y), image.get(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_png_roundtrip_other_types() {
+        let mut rgba = RgbaImage::new(3, 2);
+        rgba.set(1, 0, rgba!(1, 2, 3, 4));
+        rgba.set(2, 1, rgba!(200, 100, 50, 255));
+        let parsed = parse_png(rgba.to_png());
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(parsed.get(x, y), rgba.get(x, y));
+            }
+        }
+
+        let mut gray = GrayImage::new(2, 2);
+        gray.set(1, 1, 77);
+        let parsed = parse_png(gray.to_png());
+        assert_eq!(parsed.get(1, 1), Some(rgba!(77, 77, 77, 255)));
+        assert_eq!(parsed.get(0, 0), Some(rgba!(0, 0, 0, 255)));
+
+        let mut deep = Image16::new(1, 1);
+        deep.set(0, 0, Color16::new(0xffff, 0x8080, 0));
+        let parsed = parse_png(deep.to_png());
+        assert_eq!(parsed.get(0, 0), Some(rgb!(255, 128, 0).into()));
+
+        let mut gray16 = GrayImage16::new(1, 1);
+        gray16.set(0, 0, 0x1010);
+        let parsed = parse_png(gray16.to_png());
+        assert_eq!(parsed.get(0, 0), Some(rgba!(16, 16, 16, 255)));
+    }
+}
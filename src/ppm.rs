@@ -1,6 +1,12 @@
 //! Utility macros for saving images in PPM format.
 
-use crate::{color::Color, image::Image};
+use std::fmt::Display;
+
+use crate::{
+    color::{Color, Color16},
+    error::{checked_samples, Error, Result},
+    image::{GrayImage, GrayImage16, Image, Image16},
+};
 
 /// Trait for transforming any values into their PNM represenataion.
 pub trait PNM {
@@ -11,81 +17,985 @@ pub trait PNM {
     fn to_pnm_p6(&self) -> Vec<u8>;
 }
 
+/// Trait for transforming images into their PBM (bitmap) represenataion, where dark pixels are
+/// represented by set bits.
+pub trait PBM {
+    /// Convert a value into its P1 (ASCII PBM) represenataion.
+    fn to_pnm_p1(&self) -> String;
+
+    /// Convert a value into its P4 (binary PBM) represenataion.
+    fn to_pnm_p4(&self) -> Vec<u8>;
+}
+
+/// Trait for transforming images into their PGM (graymap) represenataion.
+pub trait PGM {
+    /// Convert a value into its P2 (ASCII PGM) represenataion.
+    fn to_pnm_p2(&self) -> String;
+
+    /// Convert a value into its P5 (binary PGM) represenataion.
+    fn to_pnm_p5(&self) -> Vec<u8>;
+}
+
+/// Macro for saving an image in its ASCII PBM represenataion to the disk.
+#[deprecated(note = "use Image::save or Image::save_with instead")]
+#[macro_export]
+macro_rules! save_pnm_p1 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, $crate::ppm::PBM::to_pnm_p1(&$img).as_bytes())
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its ASCII PGM represenataion to the disk.
+#[deprecated(note = "use Image::save or Image::save_with instead")]
+#[macro_export]
+macro_rules! save_pnm_p2 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, $crate::ppm::PGM::to_pnm_p2(&$img).as_bytes())
+            .expect("Could not write to file");
+    };
+}
+
 /// Macro for saving an image in its ASCII PPM represenataion to the disk.
+#[deprecated(note = "use Image::save or Image::save_with instead")]
 #[macro_export]
 macro_rules! save_pnm_p3 {
-    ($file_name:expr, $img:ident) => {
-        $crate::write_file!($file_name, $crate::ppm::PNM::to_pnm_p3(&$img).as_bytes());
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, $crate::ppm::PNM::to_pnm_p3(&$img).as_bytes())
+            .expect("Could not write to file");
     };
 }
 
-/// Macro for saving an image in its binary PPM represenataion to the disk.
+/// Macro for saving an image in its binary PBM represenataion to the disk.
+#[deprecated(note = "use Image::save or Image::save_with instead")]
 #[macro_export]
-macro_rules! save_pnm_p6 {
-    ($file_name:expr, $img:ident) => {
-        $crate::write_file!($file_name, &$crate::ppm::PNM::to_pnm_p6(&$img));
+macro_rules! save_pnm_p4 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::ppm::PBM::to_pnm_p4(&$img))
+            .expect("Could not write to file");
     };
 }
 
-/// Parse a vector of u8 into a valid PPM6 image. This function will panic, if the format is not
-/// valid.
-pub fn parse_ppm6(inp: Vec<u8>) -> Image {
-    let mut iter = inp.iter();
-    let Some(first_line_break) = iter.position(|&item| item == b'\n') else {
-        panic!("No valid magic constant");
+/// Macro for saving an image in its binary PGM represenataion to the disk.
+#[deprecated(note = "use Image::save or Image::save_with instead")]
+#[macro_export]
+macro_rules! save_pnm_p5 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::ppm::PGM::to_pnm_p5(&$img))
+            .expect("Could not write to file");
     };
+}
 
-    let Some(second_line_break) = iter.position(|&item| item == b'\n') else {
-        panic!("No dimensions provided");
+/// Macro for saving an image in its binary PPM represenataion to the disk.
+#[deprecated(note = "use Image::save or Image::save_with instead")]
+#[macro_export]
+macro_rules! save_pnm_p6 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::ppm::PNM::to_pnm_p6(&$img))
+            .expect("Could not write to file");
     };
+}
 
-    let second_line_break = second_line_break + first_line_break + 1;
-
-    let dimensions = &inp[first_line_break + 1..second_line_break];
+/// Macro for saving an image in its ASCII PBM represenataion to the disk. Returns an error, if the
+/// file could not be written.
+#[macro_export]
+macro_rules! try_save_pnm_p1 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, $crate::ppm::PBM::to_pnm_p1(&$img).as_bytes())
+    };
+}
 
-    let Ok(dimensions) = std::str::from_utf8(dimensions) else {
-        panic!("Dimensions need to be UTF8");
+/// Macro for saving an image in its ASCII PGM represenataion to the disk. Returns an error, if the
+/// file could not be written.
+#[macro_export]
+macro_rules! try_save_pnm_p2 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, $crate::ppm::PGM::to_pnm_p2(&$img).as_bytes())
     };
+}
 
-    let Some((left, right)) = dimensions.trim().split_once(' ') else {
-        panic!("Dimensions to not have valid format!");
+/// Macro for saving an image in its ASCII PPM represenataion to the disk. Returns an error, if the
+/// file could not be written.
+#[macro_export]
+macro_rules! try_save_pnm_p3 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, $crate::ppm::PNM::to_pnm_p3(&$img).as_bytes())
     };
+}
 
-    let Ok(width) = left.parse::<usize>() else {
-        panic!("Width does not have valid format! ({})", left);
+/// Macro for saving an image in its binary PBM represenataion to the disk. Returns an error, if
+/// the file could not be written.
+#[macro_export]
+macro_rules! try_save_pnm_p4 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::ppm::PBM::to_pnm_p4(&$img))
     };
+}
 
-    let Ok(height) = right.parse::<usize>() else {
-        panic!("Height does not have valid format! ({})", right);
+/// Macro for saving an image in its binary PGM represenataion to the disk. Returns an error, if
+/// the file could not be written.
+#[macro_export]
+macro_rules! try_save_pnm_p5 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::ppm::PGM::to_pnm_p5(&$img))
     };
+}
 
-    let Some(third_line_break) = iter.position(|&item| item == b'\n') else {
-        panic!("No max value provided");
+/// Macro for saving an image in its binary PPM represenataion to the disk. Returns an error, if
+/// the file could not be written.
+#[macro_export]
+macro_rules! try_save_pnm_p6 {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::ppm::PNM::to_pnm_p6(&$img))
     };
+}
 
-    let third_line_break = third_line_break + second_line_break + 1;
+/// Create the header of a PNM file. The max value is omitted for bitmaps.
+pub(crate) fn pnm_header(
+    magic_constant: &str,
+    cols: usize,
+    rows: usize,
+    max_value: Option<usize>,
+) -> String {
+    // add magic number
+    let mut header = format!("{}\n", magic_constant);
+    // add dimensions of the picture
+    header.push_str(&format!("{} {}\n", cols, rows));
+    // add max value
+    if let Some(max_value) = max_value {
+        header.push_str(&format!("{}\n", max_value));
+    }
+    header
+}
 
-    let max_value = &inp[second_line_break + 1..third_line_break];
+/// Check, if a luminance value is considered black (i.e., a set bit) in a bitmap.
+fn is_black(value: u8) -> bool {
+    value < 128
+}
 
-    let Ok(max_value) = std::str::from_utf8(max_value) else {
-        panic!("Max value needs to be UTF8");
-    };
+/// Encode an ASCII PNM file from its header, where `row` appends the encoded row `y` to the
+/// output.
+pub(crate) fn encode_ascii_pnm(
+    header: String,
+    rows: usize,
+    row: impl Fn(usize, &mut Vec<u8>),
+) -> String {
+    let mut pnm = header.into_bytes();
+    for y in 0..rows {
+        row(y, &mut pnm);
+    }
+    String::from_utf8(pnm).expect("ASCII PNM files only contain ASCII characters")
+}
+
+/// Append a row of samples in ASCII (P2 & P3) to `out`, where the samples are separated by spaces.
+pub(crate) fn encode_ascii_row<T: Display>(row: impl IntoIterator<Item = T>, out: &mut Vec<u8>) {
+    for (x, sample) in row.into_iter().enumerate() {
+        if x > 0 {
+            out.push(b' ');
+        }
+        out.extend_from_slice(sample.to_string().as_bytes());
+    }
+    out.push(b'\n');
+}
+
+/// Append a row of luminance values as ASCII bitmap (P1) to `out`, where dark values are
+/// represented by `1`.
+pub(crate) fn encode_p1_row(row: impl IntoIterator<Item = u8>, out: &mut Vec<u8>) {
+    encode_ascii_row(row.into_iter().map(|value| is_black(value) as u8), out);
+}
+
+/// Append a row of luminance values as binary bitmap (P4) to `out`, where the bits are packed into
+/// bytes and the row is padded to a full byte.
+pub(crate) fn encode_p4_row(row: impl IntoIterator<Item = u8>, out: &mut Vec<u8>) {
+    for (x, value) in row.into_iter().enumerate() {
+        if x % 8 == 0 {
+            out.push(0);
+        }
+        if is_black(value) {
+            if let Some(byte) = out.last_mut() {
+                *byte |= 0x80 >> (x % 8);
+            }
+        }
+    }
+}
+
+/// Header of a PNM file.
+struct Header {
+    width: usize,
+    height: usize,
+    max_value: usize,
+}
 
-    let Ok(_max_value) = max_value.parse::<usize>() else {
-        panic!("Max value does not have a valid format! ({})", max_value);
+/// Parse the header of a PNM file according to the Netpbm specification: the magic constant,
+/// the width, the height and the max value, separated by arbitrary whitespace and comments. If
+/// `with_max_value` is false (i.e., for bitmaps), no max value will be read and it defaults to 1.
+fn parse_header(
+    tokens: &mut AsciiTokens,
+    magic_constant: &[u8],
+    with_max_value: bool,
+) -> Result<Header> {
+    tokens.expect_magic_constant(magic_constant)?;
+
+    let width = tokens.next_header_number("Width")?;
+    let height = tokens.next_header_number("Height")?;
+    let max_value = if with_max_value {
+        tokens.next_header_number("Max value")?
+    } else {
+        1
     };
 
-    let after_third_line_break = third_line_break + 1;
+    Ok(Header {
+        width,
+        height,
+        max_value,
+    })
+}
+
+/// Check, that the given max value is supported.
+pub(crate) fn check_max_value(max_value: usize) -> Result<()> {
+    if max_value == 0 || max_value > u16::MAX as usize {
+        return Err(Error::InvalidHeader(format!(
+            "Max value needs to be in range 1..=65535 ({})",
+            max_value
+        )));
+    }
+    Ok(())
+}
+
+/// Check, that the data following the header contains at least the expected number of bytes.
+pub(crate) fn check_data_len(inp: &[u8], data_start: usize, expected: usize) -> Result<()> {
+    let actual = inp.len().saturating_sub(data_start);
+    if actual < expected {
+        return Err(Error::TruncatedData { expected, actual });
+    }
+    Ok(())
+}
+
+/// Scale a sample with the given max value to the range of a u8.
+pub(crate) fn scale_to_u8(sample: u16, max_value: usize) -> u8 {
+    ((sample as usize * u8::MAX as usize + max_value / 2) / max_value) as u8
+}
+
+/// Scale a sample with the given max value to the range of a u16.
+pub(crate) fn scale_to_u16(sample: u16, max_value: usize) -> u16 {
+    ((sample as usize * u16::MAX as usize + max_value / 2) / max_value) as u16
+}
+
+/// Check, that a sample does not exceed the given max value.
+pub(crate) fn check_sample(sample: usize, max_value: usize) -> Result<u16> {
+    if sample > max_value {
+        return Err(Error::InvalidData(format!(
+            "Sample exceeds max value ({} > {})",
+            sample, max_value
+        )));
+    }
+    Ok(sample as u16)
+}
+
+/// Raw samples of a PPM or PGM file.
+struct Samples {
+    width: usize,
+    height: usize,
+    max_value: usize,
+    channels: usize,
+    samples: Vec<u16>,
+}
+
+impl Samples {
+    /// Read the samples of a PPM or PGM file with the given magic constant. Binary files use one
+    /// byte per sample if the max value is less than 256 and two bytes (big endian) otherwise.
+    fn read(inp: &[u8], magic_constant: &[u8], channels: usize) -> Result<Self> {
+        let mut tokens = AsciiTokens::new(inp);
+        let Header {
+            width,
+            height,
+            max_value,
+        } = parse_header(&mut tokens, magic_constant, true)?;
+
+        check_max_value(max_value)?;
+        let count = checked_samples(width, height, channels)?;
+
+        let samples = match magic_constant {
+            b"P5" | b"P6" => {
+                let data_start = tokens.raster_start()?;
+                let bytes_per_sample = if max_value > u8::MAX as usize { 2 } else { 1 };
+                let expected = checked_samples(count, bytes_per_sample, 1)?;
+                check_data_len(inp, data_start, expected)?;
+
+                let data = &inp[data_start..data_start + expected];
+                if bytes_per_sample == 1 {
+                    data.iter()
+                        .map(|sample| check_sample(*sample as usize, max_value))
+                        .collect::<Result<Vec<_>>>()?
+                } else {
+                    data.chunks_exact(2)
+                        .map(|sample| {
+                            let sample = u16::from_be_bytes([sample[0], sample[1]]);
+                            check_sample(sample as usize, max_value)
+                        })
+                        .collect::<Result<Vec<_>>>()?
+                }
+            }
+            _ => {
+                tokens.check_remaining(count)?;
+                let mut samples = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let sample = tokens.next_number("Sample")?;
+                    samples.push(check_sample(sample, max_value)?);
+                }
+                samples
+            }
+        };
+
+        Ok(Self {
+            width,
+            height,
+            max_value,
+            channels,
+            samples,
+        })
+    }
+
+    /// Read the samples of a file, which can either be in the given ASCII or binary format.
+    fn read_any(
+        inp: &[u8],
+        ascii_magic_constant: &[u8],
+        binary_magic_constant: &[u8],
+        channels: usize,
+    ) -> Result<Self> {
+        match inp.get(0..2) {
+            Some(magic_constant) if magic_constant == ascii_magic_constant => {
+                Self::read(inp, ascii_magic_constant, channels)
+            }
+            _ => Self::read(inp, binary_magic_constant, channels),
+        }
+    }
+
+    /// Get the samples of the pixel at the specified coordinates.
+    fn pixel(&self, x: usize, y: usize) -> &[u16] {
+        let start = self.channels * (y * self.width + x);
+        &self.samples[start..start + self.channels]
+    }
+
+    /// Convert the samples into an RGB image with 8bit per channel.
+    fn into_image(self) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pixel = self.pixel(x, y);
+                let color = Color::new(
+                    scale_to_u8(pixel[0], self.max_value),
+                    scale_to_u8(pixel[1], self.max_value),
+                    scale_to_u8(pixel[2], self.max_value),
+                );
+                image.set(x, y, color);
+            }
+        }
+        image
+    }
+
+    /// Convert the samples into an RGB image with 16bit per channel.
+    fn into_image16(self) -> Image16 {
+        let mut image = Image16::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pixel = self.pixel(x, y);
+                let color = Color16::new(
+                    scale_to_u16(pixel[0], self.max_value),
+                    scale_to_u16(pixel[1], self.max_value),
+                    scale_to_u16(pixel[2], self.max_value),
+                );
+                image.set(x, y, color);
+            }
+        }
+        image
+    }
+
+    /// Convert the samples into a grayscale image with 8bit per pixel.
+    fn into_gray_image(self) -> GrayImage {
+        let mut image = GrayImage::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                image.set(x, y, scale_to_u8(self.pixel(x, y)[0], self.max_value));
+            }
+        }
+        image
+    }
+
+    /// Convert the samples into a grayscale image with 16bit per pixel.
+    fn into_gray_image16(self) -> GrayImage16 {
+        let mut image = GrayImage16::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                image.set(x, y, scale_to_u16(self.pixel(x, y)[0], self.max_value));
+            }
+        }
+        image
+    }
+}
+
+/// Parse a vector of u8 into a valid PPM6 image. Samples are rescaled to 8bit according to the
+/// max value of the file. This function will panic, if the format is not valid. See
+/// `try_parse_ppm6` for a fallible variant.
+pub fn parse_ppm6(inp: Vec<u8>) -> Image {
+    try_parse_ppm6(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PPM6 image.
+pub fn try_parse_ppm6(inp: Vec<u8>) -> Result<Image> {
+    Ok(Samples::read(&inp, b"P6", 3)?.into_image())
+}
+
+/// Parse a vector of u8 into a valid PBM4 image. Set bits (i.e., black pixels) are mapped to 0,
+/// unset bits to 255. This function will panic, if the format is not valid. See `try_parse_pbm4`
+/// for a fallible variant.
+pub fn parse_pbm4(inp: Vec<u8>) -> GrayImage {
+    try_parse_pbm4(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PBM4 image.
+pub fn try_parse_pbm4(inp: Vec<u8>) -> Result<GrayImage> {
+    let mut tokens = AsciiTokens::new(&inp);
+    let Header { width, height, .. } = parse_header(&mut tokens, b"P4", false)?;
+    let data_start = tokens.raster_start()?;
+
+    // every row is padded to a full byte
+    let row_len = width.div_ceil(8);
+    check_data_len(&inp, data_start, checked_samples(row_len, height, 1)?)?;
+
+    let mut image = GrayImage::new(width, height);
+
+    for y in 0..height {
+        let row = &inp[data_start + y * row_len..data_start + (y + 1) * row_len];
+        for x in 0..width {
+            let bit = (row[x / 8] >> (7 - x % 8)) & 1;
+            image.set(x, y, if bit == 1 { 0 } else { u8::MAX });
+        }
+    }
+    Ok(image)
+}
+
+/// Parse a vector of u8 into a valid PGM5 image. Samples are rescaled to 8bit according to the
+/// max value of the file. This function will panic, if the format is not valid. See
+/// `try_parse_pgm5` for a fallible variant.
+pub fn parse_pgm5(inp: Vec<u8>) -> GrayImage {
+    try_parse_pgm5(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PGM5 image.
+pub fn try_parse_pgm5(inp: Vec<u8>) -> Result<GrayImage> {
+    Ok(Samples::read(&inp, b"P5", 1)?.into_gray_image())
+}
+
+/// Parse a vector of u8 into a valid PPM image (either P3 or P6) with 16bit per channel. Samples
+/// are rescaled to 16bit according to the max value of the file. This function will panic, if
+/// the format is not valid. See `try_parse_ppm16` for a fallible variant.
+pub fn parse_ppm16(inp: Vec<u8>) -> Image16 {
+    try_parse_ppm16(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PPM image (either P3 or P6) with 16bit per channel.
+pub fn try_parse_ppm16(inp: Vec<u8>) -> Result<Image16> {
+    Ok(Samples::read_any(&inp, b"P3", b"P6", 3)?.into_image16())
+}
+
+/// Parse a vector of u8 into a valid PGM image (either P2 or P5) with 16bit per pixel. Samples
+/// are rescaled to 16bit according to the max value of the file. This function will panic, if
+/// the format is not valid. See `try_parse_pgm16` for a fallible variant.
+pub fn parse_pgm16(inp: Vec<u8>) -> GrayImage16 {
+    try_parse_pgm16(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PGM image (either P2 or P5) with 16bit per pixel.
+pub fn try_parse_pgm16(inp: Vec<u8>) -> Result<GrayImage16> {
+    Ok(Samples::read_any(&inp, b"P2", b"P5", 1)?.into_gray_image16())
+}
+
+/// Iterator over the whitespace separated tokens of an ASCII PNM file. Comments (i.e., everything
+/// from a `#` up to the end of the line) are skipped.
+struct AsciiTokens<'a> {
+    inp: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> AsciiTokens<'a> {
+    fn new(inp: &'a [u8]) -> Self {
+        Self { inp, pos: 0 }
+    }
+
+    /// Check the magic constant at the head of the file.
+    fn expect_magic_constant(&mut self, magic_constant: &[u8]) -> Result<()> {
+        match self.next() {
+            Some(token) if token == magic_constant => Ok(()),
+            _ => Err(Error::InvalidMagicConstant(
+                "No valid magic constant".to_string(),
+            )),
+        }
+    }
+
+    /// Get the next token of the header and parse it as a number.
+    fn next_header_number(&mut self, name: &str) -> Result<usize> {
+        self.next_number(name).map_err(|err| match err {
+            Error::InvalidData(msg) => Error::InvalidHeader(msg),
+            err => err,
+        })
+    }
+
+    /// Get the next token and parse it as a number.
+    fn next_number(&mut self, name: &str) -> Result<usize> {
+        let Some(token) = self.next() else {
+            return Err(Error::TruncatedData {
+                expected: self.pos + 1,
+                actual: self.inp.len(),
+            });
+        };
+
+        let Ok(token) = std::str::from_utf8(token) else {
+            return Err(Error::InvalidData(format!("{} needs to be UTF8", name)));
+        };
+
+        if !token.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(Error::InvalidData(format!(
+                "{} does not have a valid format! ({})",
+                name, token
+            )));
+        }
+
+        let Ok(number) = token.parse::<usize>() else {
+            return Err(Error::InvalidData(format!(
+                "{} does not have a valid format! ({})",
+                name, token
+            )));
+        };
+
+        Ok(number)
+    }
+
+    /// Get the next bit of an ASCII bitmap. Bits do not need to be separated by whitespace.
+    fn next_bit(&mut self) -> Result<bool> {
+        self.skip_whitespace();
+
+        let Some(&bit) = self.inp.get(self.pos) else {
+            return Err(Error::TruncatedData {
+                expected: self.pos + 1,
+                actual: self.inp.len(),
+            });
+        };
+        self.pos += 1;
+
+        match bit {
+            b'0' => Ok(false),
+            b'1' => Ok(true),
+            x => Err(Error::InvalidData(format!(
+                "Bit does not have a valid format! ({})",
+                x as char
+            ))),
+        }
+    }
+
+    /// Finish the header of a binary file, which is terminated by exactly one whitespace
+    /// character after the last header field. Returns the offset of the first byte of the raster.
+    fn raster_start(&mut self) -> Result<usize> {
+        match self.inp.get(self.pos) {
+            Some(byte) if byte.is_ascii_whitespace() => {
+                self.pos += 1;
+                Ok(self.pos)
+            }
+            Some(_) => Err(Error::InvalidHeader(
+                "Header needs to be terminated by a whitespace".to_string(),
+            )),
+            None => Err(Error::TruncatedData {
+                expected: self.pos + 1,
+                actual: self.inp.len(),
+            }),
+        }
+    }
+
+    /// Check, that enough input is left for the given number of samples. Every sample occupies
+    /// at least one byte, so this catches truncated files before allocating the image.
+    fn check_remaining(&self, samples: usize) -> Result<()> {
+        let actual = self.inp.len() - self.pos;
+        if actual < samples {
+            return Err(Error::TruncatedData {
+                expected: samples,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Skip all whitespace and comments in front of the next token.
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.inp.len() {
+            match self.inp[self.pos] {
+                b'#' => {
+                    while self.pos < self.inp.len() && self.inp[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for AsciiTokens<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.skip_whitespace();
+
+        if self.pos >= self.inp.len() {
+            return None;
+        }
+
+        let start = self.pos;
+        while self.pos < self.inp.len()
+            && !self.inp[self.pos].is_ascii_whitespace()
+            && self.inp[self.pos] != b'#'
+        {
+            self.pos += 1;
+        }
+
+        Some(&self.inp[start..self.pos])
+    }
+}
+
+/// Parse a vector of u8 into a valid PPM3 image. This function will panic, if the format is not
+/// valid. See `try_parse_ppm3` for a fallible variant.
+pub fn parse_ppm3(inp: Vec<u8>) -> Image {
+    try_parse_ppm3(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PPM3 image.
+pub fn try_parse_ppm3(inp: Vec<u8>) -> Result<Image> {
+    Ok(Samples::read(&inp, b"P3", 3)?.into_image())
+}
+
+/// Parse a vector of u8 into a valid PBM1 image. Set bits (i.e., black pixels) are mapped to 0,
+/// unset bits to 255. This function will panic, if the format is not valid. See `try_parse_pbm1`
+/// for a fallible variant.
+pub fn parse_pbm1(inp: Vec<u8>) -> GrayImage {
+    try_parse_pbm1(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PBM1 image.
+pub fn try_parse_pbm1(inp: Vec<u8>) -> Result<GrayImage> {
+    let mut tokens = AsciiTokens::new(&inp);
 
-    assert_eq!(inp.len() - after_third_line_break, width * height * 3);
+    let Header { width, height, .. } = parse_header(&mut tokens, b"P1", false)?;
 
-    let mut image = Image::new(width, height);
+    tokens.check_remaining(checked_samples(width, height, 1)?)?;
+
+    let mut image = GrayImage::new(width, height);
+
+    for y in 0..height {
+        for x in 0..width {
+            let value = if tokens.next_bit()? { 0 } else { u8::MAX };
+            image.set(x, y, value);
+        }
+    }
+    Ok(image)
+}
+
+/// Parse a vector of u8 into a valid PGM2 image. This function will panic, if the format is not
+/// valid. See `try_parse_pgm2` for a fallible variant.
+pub fn parse_pgm2(inp: Vec<u8>) -> GrayImage {
+    try_parse_pgm2(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid PGM2 image.
+pub fn try_parse_pgm2(inp: Vec<u8>) -> Result<GrayImage> {
+    Ok(Samples::read(&inp, b"P2", 1)?.into_gray_image())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgb;
+
+    #[test]
+    fn test_parse_ppm3() {
+        let image = parse_ppm3(b"P3\n2 1\n255\n255 0 0 0 0 255\n".to_vec());
+        assert_eq!(image.cols(), 2);
+        assert_eq!(image.rows(), 1);
+        assert_eq!(image.get(0, 0), Some(rgb!(255, 0, 0)));
+        assert_eq!(image.get(1, 0), Some(rgb!(0, 0, 255)));
+    }
+
+    #[test]
+    fn test_parse_ppm3_comments_and_whitespace() {
+        let inp = b"P3 # magic\n# a comment\n2\t2 255\n1 2\n3 # inline\n4 5 6\n7 8 9 10\n11 12";
+        let image = parse_ppm3(inp.to_vec());
+        assert_eq!(image.get(0, 0), Some(rgb!(1, 2, 3)));
+        assert_eq!(image.get(1, 0), Some(rgb!(4, 5, 6)));
+        assert_eq!(image.get(0, 1), Some(rgb!(7, 8, 9)));
+        assert_eq!(image.get(1, 1), Some(rgb!(10, 11, 12)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parse_ppm3_missing_pixels() {
+        parse_ppm3(b"P3\n2 1\n255\n255 0 0\n".to_vec());
+    }
+
+    #[test]
+    fn test_parse_pbm1() {
+        let image = parse_pbm1(b"P1\n# bitmap\n3 2\n1 0 1\n011".to_vec());
+        assert_eq!(image.cols(), 3);
+        assert_eq!(image.rows(), 2);
+        assert_eq!(image.get(0, 0), Some(0));
+        assert_eq!(image.get(1, 0), Some(255));
+        assert_eq!(image.get(2, 0), Some(0));
+        assert_eq!(image.get(0, 1), Some(255));
+        assert_eq!(image.get(1, 1), Some(0));
+        assert_eq!(image.get(2, 1), Some(0));
+    }
+
+    #[test]
+    fn test_parse_pbm4_padding() {
+        let mut inp = b"P4\n10 2\n".to_vec();
+        inp.extend_from_slice(&[0b1000_0000, 0b0100_0000, 0b0000_0001, 0b1100_0000]);
+        let image = parse_pbm4(inp);
+        assert_eq!(image.get(0, 0), Some(0));
+        assert_eq!(image.get(1, 0), Some(255));
+        assert_eq!(image.get(9, 0), Some(0));
+        assert_eq!(image.get(7, 1), Some(0));
+        assert_eq!(image.get(8, 1), Some(0));
+        assert_eq!(image.get(9, 1), Some(0));
+        assert_eq!(image.get(6, 1), Some(255));
+    }
+
+    #[test]
+    fn test_pbm_roundtrip() {
+        let mut image = GrayImage::new(11, 3);
+        image.set(0, 0, 0);
+        image.set(8, 1, 0);
+        image.set(10, 2, 0);
+        image.set(5, 2, 200);
+
+        let p1 = parse_pbm1(image.to_pnm_p1().into_bytes());
+        let p4 = parse_pbm4(image.to_pnm_p4());
+        for y in 0..3 {
+            for x in 0..11 {
+                let expected = if image.get(x, y).unwrap() < 128 {
+                    0
+                } else {
+                    255
+                };
+                assert_eq!(p1.get(x, y), Some(expected));
+                assert_eq!(p4.get(x, y), Some(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_pgm_max_value_scaling() {
+        let p2 = parse_pgm2(b"P2\n3 1\n15\n0 7 15\n".to_vec());
+        let mut inp = b"P5\n3 1\n15\n".to_vec();
+        inp.extend_from_slice(&[0, 7, 15]);
+        let p5 = parse_pgm5(inp);
+        for image in [p2, p5] {
+            assert_eq!(image.get(0, 0), Some(0));
+            assert_eq!(image.get(1, 0), Some(119));
+            assert_eq!(image.get(2, 0), Some(255));
+        }
+    }
+
+    #[test]
+    fn test_pgm_roundtrip() {
+        let mut image = GrayImage::new(4, 2);
+        image.set(1, 0, 17);
+        image.set(3, 1, 255);
+        image.set(2, 1, 128);
+
+        let p2 = parse_pgm2(image.to_pnm_p2().into_bytes());
+        let p5 = parse_pgm5(image.to_pnm_p5());
+        for y in 0..2 {
+            for x in 0..4 {
+                assert_eq!(p2.get(x, y), image.get(x, y));
+                assert_eq!(p5.get(x, y), image.get(x, y));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parse_pgm_sample_exceeds_max_value() {
+        parse_pgm2(b"P2\n1 1\n15\n16\n".to_vec());
+    }
+
+    #[test]
+    fn test_try_parse_errors() {
+        assert!(matches!(
+            try_parse_ppm3(b"P3\n2 1\n255\n255 0 0\n".to_vec()),
+            Err(Error::TruncatedData { .. })
+        ));
+        assert!(matches!(
+            try_parse_ppm3(b"P3\n2 x\n255\n".to_vec()),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            try_parse_pgm2(b"P2\n1 1\n15\n16\n".to_vec()),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            try_parse_pbm1(b"P2\n1 1\n1\n".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+        assert!(matches!(
+            try_parse_ppm6(b"P6\n2 2\n255\n\x00\x01".to_vec()),
+            Err(Error::TruncatedData {
+                expected: 12,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            try_parse_pgm5(format!("P5\n{} 2\n255\n", usize::MAX).into_bytes()),
+            Err(Error::DimensionOverflow { .. })
+        ));
+        assert!(matches!(
+            try_parse_pbm1(b"P1\n100000 100000\n0".to_vec()),
+            Err(Error::TruncatedData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_header_comments_and_whitespace() {
+        let mut inp =
+            b"P6 # created by GIMP\n# another comment\n2\t1\r\n# max value\n255\n".to_vec();
+        inp.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        let image = try_parse_ppm6(inp).unwrap();
+        assert_eq!(image.get(0, 0), Some(rgb!(1, 2, 3)));
+        assert_eq!(image.get(1, 0), Some(rgb!(4, 5, 6)));
+    }
+
+    #[test]
+    fn test_parse_header_single_line() {
+        let mut inp = b"P5 3 1 255 ".to_vec();
+        // the raster may start with bytes that look like whitespace
+        inp.extend_from_slice(b" \n#");
+        let image = try_parse_pgm5(inp).unwrap();
+        assert_eq!(image.get(0, 0), Some(b' '));
+        assert_eq!(image.get(1, 0), Some(b'\n'));
+        assert_eq!(image.get(2, 0), Some(b'#'));
+
+        let mut inp = b"P4\t8\t1\n".to_vec();
+        inp.push(0b1010_1010);
+        let image = try_parse_pbm4(inp).unwrap();
+        assert_eq!(image.get(0, 0), Some(0));
+        assert_eq!(image.get(1, 0), Some(255));
+    }
+
+    #[test]
+    fn test_parse_header_errors() {
+        assert!(matches!(
+            try_parse_ppm6(b"P6 1 1 255".to_vec()),
+            Err(Error::TruncatedData { .. })
+        ));
+        assert!(matches!(
+            try_parse_ppm6(b"P6 1 +1 255\n\x00\x00\x00".to_vec()),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            try_parse_pgm5(b"P6 1 1 255\n\x00".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_ppm_max_value_scaling() {
+        let p3 = parse_ppm3(b"P3\n2 1\n15\n15 0 7 0 15 1\n".to_vec());
+        let mut inp = b"P6\n2 1\n15\n".to_vec();
+        inp.extend_from_slice(&[15, 0, 7, 0, 15, 1]);
+        let p6 = parse_ppm6(inp);
+        for image in [p3, p6] {
+            assert_eq!(image.get(0, 0), Some(rgb!(255, 0, 119)));
+            assert_eq!(image.get(1, 0), Some(rgb!(0, 255, 17)));
+        }
+    }
+
+    #[test]
+    fn test_parse_16bit() {
+        let mut inp = b"P6\n1 1\n65535\n".to_vec();
+        inp.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x01]);
+        assert_eq!(
+            parse_ppm16(inp.clone()).get(0, 0),
+            Some(Color16::new(0xffff, 0x8000, 0x0001))
+        );
+        assert_eq!(parse_ppm6(inp).get(0, 0), Some(rgb!(255, 128, 0)));
+
+        let mut inp = b"P5\n2 1\n1023\n".to_vec();
+        inp.extend_from_slice(&[0x03, 0xff, 0x02, 0x00]);
+        let image = parse_pgm16(inp.clone());
+        assert_eq!(image.get(0, 0), Some(65535));
+        assert_eq!(image.get(1, 0), Some(32800));
+        assert_eq!(parse_pgm5(inp).get(1, 0), Some(128));
+
+        let image = parse_pgm16(b"P2\n1 1\n1000\n500\n".to_vec());
+        assert_eq!(image.get(0, 0), Some(32768));
+    }
+
+    #[test]
+    fn test_16bit_roundtrip() {
+        let mut image = Image16::new(2, 2);
+        image.set(0, 0, Color16::new(1, 256, 65535));
+        image.set(1, 1, Color16::new(12345, 0, 54321));
+
+        for parsed in [
+            parse_ppm16(image.to_pnm_p3().into_bytes()),
+            parse_ppm16(image.to_pnm_p6()),
+        ] {
+            for y in 0..2 {
+                for x in 0..2 {
+                    assert_eq!(parsed.get(x, y), image.get(x, y));
+                }
+            }
+        }
+
+        let mut gray = GrayImage16::new(3, 1);
+        gray.set(0, 0, 513);
+        gray.set(2, 0, 65535);
+        for parsed in [
+            parse_pgm16(gray.to_pnm_p2().into_bytes()),
+            parse_pgm16(gray.to_pnm_p5()),
+        ] {
+            for x in 0..3 {
+                assert_eq!(parsed.get(x, 0), gray.get(x, 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_16bit_errors() {
+        assert!(matches!(
+            try_parse_ppm16(b"P6\n1 1\n65535\n\x00\x00\x00".to_vec()),
+            Err(Error::TruncatedData {
+                expected: 6,
+                actual: 3
+            })
+        ));
+        assert!(matches!(
+            try_parse_pgm16(b"P5\n1 1\n65536\n\x00\x00".to_vec()),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            try_parse_pgm16(b"P5\n1 1\n1000\n\x03\xe9".to_vec()),
+            Err(Error::InvalidData(_))
+        ));
+    }
 
-    for x in 0..width {
-        for y in 0..height {
-            let start = 3 * (y * width + x) + after_third_line_break;
-            image.set(x, y, &Color::from_u8_array(&inp[start..start + 3]));
+    #[test]
+    fn test_parse_ppm3_matches_ppm6() {
+        let p3 = parse_ppm3(std::fs::read("assets/mona_lisa_small_2.ppm").unwrap());
+        let p6 = parse_ppm6(std::fs::read("assets/mona_lisa_small.ppm").unwrap());
+        assert_eq!(p3.cols(), p6.cols());
+        assert_eq!(p3.rows(), p6.rows());
+        for y in 0..p3.rows() {
+            for x in 0..p3.cols() {
+                assert_eq!(p3.get(x, y), p6.get(x, y));
+            }
         }
     }
-    image
 }
@@ -0,0 +1,374 @@
+//! Reading and writing images in the QOI ("Quite OK Image") format.
+
+use crate::{
+    bytes::{read_bytes, read_u32_be, read_u8},
+    color::Rgba,
+    error::{checked_samples, Error, Result},
+    image::{Image, RgbaImage},
+};
+
+const HEADER_SIZE: usize = 14;
+const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+/// Upper limit for the number of pixels of a file (as defined by the reference implementation).
+const MAX_PIXELS: usize = 400_000_000;
+
+const OP_INDEX: u8 = 0x00;
+const OP_DIFF: u8 = 0x40;
+const OP_LUMA: u8 = 0x80;
+const OP_RUN: u8 = 0xc0;
+const OP_RGB: u8 = 0xfe;
+const OP_RGBA: u8 = 0xff;
+const OP_MASK: u8 = 0xc0;
+
+/// Longest run, which can be encoded in a single operation.
+const MAX_RUN: u8 = 62;
+
+/// Trait for transforming any values into their QOI representation.
+pub trait QOI {
+    /// Convert a value into its QOI representation.
+    fn to_qoi(&self) -> Vec<u8>;
+}
+
+/// Macro for saving an image in its QOI representation to the disk.
+#[macro_export]
+macro_rules! save_qoi {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::qoi::QOI::to_qoi(&$img))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its QOI representation to the disk. Returns an error, if the
+/// file could not be written.
+#[macro_export]
+macro_rules! try_save_qoi {
+    ($file_name:expr, $img:expr) => {
+        $crate::try_write_file!($file_name, &$crate::qoi::QOI::to_qoi(&$img))
+    };
+}
+
+/// Get the position of a color in the array of previously seen colors.
+fn index_position(color: &Rgba) -> usize {
+    (color.r as usize * 3 + color.g as usize * 5 + color.b as usize * 7 + color.a as usize * 11)
+        % 64
+}
+
+/// Encode the pixels of an image (in row-major order) as QOI with the given number of channels.
+fn encode_qoi(
+    cols: usize,
+    rows: usize,
+    channels: u8,
+    pixels: impl Iterator<Item = Rgba>,
+) -> Vec<u8> {
+    let mut qoi = Vec::with_capacity(HEADER_SIZE + cols * rows + END_MARKER.len());
+    qoi.extend_from_slice(b"qoif");
+    qoi.extend_from_slice(&(cols as u32).to_be_bytes());
+    qoi.extend_from_slice(&(rows as u32).to_be_bytes());
+    // sRGB with linear alpha
+    qoi.extend_from_slice(&[channels, 0]);
+
+    let mut index = [Rgba::default(); 64];
+    let mut prev = Rgba::new(0, 0, 0, u8::MAX);
+    let mut run = 0u8;
+
+    for color in pixels {
+        if color == prev {
+            run += 1;
+            if run == MAX_RUN {
+                qoi.push(OP_RUN | (run - 1));
+                run = 0;
+            }
+            continue;
+        }
+
+        if run > 0 {
+            qoi.push(OP_RUN | (run - 1));
+            run = 0;
+        }
+
+        let position = index_position(&color);
+        if index[position] == color {
+            qoi.push(OP_INDEX | position as u8);
+        } else {
+            index[position] = color;
+
+            if color.a == prev.a {
+                let dr = color.r.wrapping_sub(prev.r) as i8;
+                let dg = color.g.wrapping_sub(prev.g) as i8;
+                let db = color.b.wrapping_sub(prev.b) as i8;
+                let dr_dg = dr.wrapping_sub(dg);
+                let db_dg = db.wrapping_sub(dg);
+
+                if (-2..=1).contains(&dr) && (-2..=1).contains(&dg) && (-2..=1).contains(&db) {
+                    qoi.push(
+                        OP_DIFF | ((dr + 2) as u8) << 4 | ((dg + 2) as u8) << 2 | (db + 2) as u8,
+                    );
+                } else if (-32..=31).contains(&dg)
+                    && (-8..=7).contains(&dr_dg)
+                    && (-8..=7).contains(&db_dg)
+                {
+                    qoi.push(OP_LUMA | (dg + 32) as u8);
+                    qoi.push(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8);
+                } else {
+                    qoi.extend_from_slice(&[OP_RGB, color.r, color.g, color.b]);
+                }
+            } else {
+                qoi.extend_from_slice(&[OP_RGBA, color.r, color.g, color.b, color.a]);
+            }
+        }
+        prev = color;
+    }
+
+    if run > 0 {
+        qoi.push(OP_RUN | (run - 1));
+    }
+    qoi.extend_from_slice(&END_MARKER);
+    qoi
+}
+
+impl QOI for Image {
+    fn to_qoi(&self) -> Vec<u8> {
+        let pixels = (0..self.rows())
+            .flat_map(|y| (0..self.cols()).map(move |x| (x, y)))
+            .map(|(x, y)| self.get(x, y).unwrap_or_default().into());
+        encode_qoi(self.cols(), self.rows(), 3, pixels)
+    }
+}
+
+impl QOI for RgbaImage {
+    fn to_qoi(&self) -> Vec<u8> {
+        let pixels = (0..self.rows())
+            .flat_map(|y| (0..self.cols()).map(move |x| (x, y)))
+            .map(|(x, y)| self.get(x, y).unwrap_or_default());
+        encode_qoi(self.cols(), self.rows(), 4, pixels)
+    }
+}
+
+/// Parse a vector of u8 into a valid QOI image. Files with 3 channels are fully opaque. This
+/// function will panic, if the format is not valid. See `try_parse_qoi` for a fallible variant.
+pub fn parse_qoi(inp: Vec<u8>) -> RgbaImage {
+    try_parse_qoi(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into a valid QOI image.
+pub fn try_parse_qoi(inp: Vec<u8>) -> Result<RgbaImage> {
+    if inp.get(0..4) != Some(b"qoif") {
+        return Err(Error::InvalidMagicConstant(
+            "No valid magic constant".to_string(),
+        ));
+    }
+
+    let width = read_u32_be(&inp, 4)? as usize;
+    let height = read_u32_be(&inp, 8)? as usize;
+    let channels = read_u8(&inp, 12)?;
+    let colorspace = read_u8(&inp, 13)?;
+
+    if width == 0 || height == 0 {
+        return Err(Error::InvalidHeader(format!(
+            "Invalid image dimensions ({}x{})",
+            width, height
+        )));
+    }
+    if checked_samples(width, height, 1)? > MAX_PIXELS {
+        return Err(Error::DimensionOverflow { width, height });
+    }
+    if channels != 3 && channels != 4 {
+        return Err(Error::InvalidHeader(format!(
+            "Invalid number of channels ({})",
+            channels
+        )));
+    }
+    if colorspace > 1 {
+        return Err(Error::InvalidHeader(format!(
+            "Invalid colorspace ({})",
+            colorspace
+        )));
+    }
+
+    let mut image = RgbaImage::new(width, height);
+    let mut index = [Rgba::default(); 64];
+    let mut color = Rgba::new(0, 0, 0, u8::MAX);
+    let mut run = 0;
+    let mut pos = HEADER_SIZE;
+
+    for i in 0..width * height {
+        if run > 0 {
+            run -= 1;
+        } else {
+            let op = read_u8(&inp, pos)?;
+            pos += 1;
+
+            match op {
+                OP_RGB => {
+                    let rgb = read_bytes(&inp, pos, 3)?;
+                    color = Rgba::new(rgb[0], rgb[1], rgb[2], color.a);
+                    pos += 3;
+                }
+                OP_RGBA => {
+                    let rgba = read_bytes(&inp, pos, 4)?;
+                    color = Rgba::new(rgba[0], rgba[1], rgba[2], rgba[3]);
+                    pos += 4;
+                }
+                _ => match op & OP_MASK {
+                    OP_INDEX => color = index[op as usize],
+                    OP_DIFF => {
+                        color.r = color.r.wrapping_add((op >> 4) & 0x03).wrapping_sub(2);
+                        color.g = color.g.wrapping_add((op >> 2) & 0x03).wrapping_sub(2);
+                        color.b = color.b.wrapping_add(op & 0x03).wrapping_sub(2);
+                    }
+                    OP_LUMA => {
+                        let second = read_u8(&inp, pos)?;
+                        pos += 1;
+                        let dg = (op & 0x3f).wrapping_sub(32);
+                        color.r = color
+                            .r
+                            .wrapping_add(dg)
+                            .wrapping_add(second >> 4)
+                            .wrapping_sub(8);
+                        color.g = color.g.wrapping_add(dg);
+                        color.b = color
+                            .b
+                            .wrapping_add(dg)
+                            .wrapping_add(second & 0x0f)
+                            .wrapping_sub(8);
+                    }
+                    _ => run = op & 0x3f,
+                },
+            }
+            index[index_position(&color)] = color;
+        }
+
+        image.set(i % width, i / width, color);
+    }
+
+    if read_bytes(&inp, pos, END_MARKER.len())? != END_MARKER {
+        return Err(Error::InvalidData("Missing end marker".to_string()));
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ppm::parse_ppm6, rgba};
+
+    #[test]
+    fn test_qoi_roundtrip_mona_lisa() {
+        let image = parse_ppm6(std::fs::read("assets/mona_lisa_small.ppm").unwrap());
+        let qoi = image.to_qoi();
+        assert_eq!(&qoi[0..4], b"qoif");
+        assert_eq!(qoi[12], 3);
+        // QOI should be considerably smaller than the raw pixel data
+        assert!(qoi.len() < image.cols() * image.rows() * 3);
+
+        let parsed = Image::from(&parse_qoi(qoi));
+        assert_eq!(parsed.cols(), image.cols());
+        assert_eq!(parsed.rows(), image.rows());
+        for y in 0..image.rows() {
+            for x in 0..image.cols() {
+                assert_eq!(parsed.get(x, y), image.get(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_qoi_roundtrip_rgba() {
+        let image = parse_ppm6(std::fs::read("assets/mona_lisa_small.ppm").unwrap());
+        let mut rgba = RgbaImage::from(&image);
+        for y in 0..rgba.rows() {
+            for x in 0..rgba.cols() {
+                let color = rgba.get(x, y).unwrap();
+                rgba.set(x, y, Rgba::new(color.r, color.g, color.b, (x ^ y) as u8));
+            }
+        }
+
+        let qoi = rgba.to_qoi();
+        assert_eq!(qoi[12], 4);
+        let parsed = parse_qoi(qoi);
+        for y in 0..rgba.rows() {
+            for x in 0..rgba.cols() {
+                assert_eq!(parsed.get(x, y), rgba.get(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_qoi_long_run() {
+        let mut image = RgbaImage::new(200, 1);
+        image.fill_with(rgba!(10, 20, 30, 40));
+        let qoi = image.to_qoi();
+        let parsed = parse_qoi(qoi);
+        for x in 0..200 {
+            assert_eq!(parsed.get(x, 0), Some(rgba!(10, 20, 30, 40)));
+        }
+    }
+
+    #[test]
+    fn test_parse_qoi_operations() {
+        let mut inp = b"qoif".to_vec();
+        inp.extend_from_slice(&6u32.to_be_bytes());
+        inp.extend_from_slice(&1u32.to_be_bytes());
+        inp.extend_from_slice(&[3, 0]);
+        inp.extend_from_slice(&[
+            OP_RGB,
+            100,
+            100,
+            100,
+            // r - 1, g + 0, b + 1
+            OP_DIFF | 1 << 4 | 2 << 2 | 3,
+            // dg = +10, dr - dg = -2, db - dg = +3
+            OP_LUMA | 42,
+            6 << 4 | 11,
+            OP_RUN | 1,
+            // index of the first color
+            OP_INDEX | index_position(&rgba!(100, 100, 100, 255)) as u8,
+        ]);
+        inp.extend_from_slice(&END_MARKER);
+
+        let image = parse_qoi(inp);
+        assert_eq!(image.get(0, 0), Some(rgba!(100, 100, 100, 255)));
+        assert_eq!(image.get(1, 0), Some(rgba!(99, 100, 101, 255)));
+        assert_eq!(image.get(2, 0), Some(rgba!(107, 110, 114, 255)));
+        assert_eq!(image.get(3, 0), Some(rgba!(107, 110, 114, 255)));
+        assert_eq!(image.get(4, 0), Some(rgba!(107, 110, 114, 255)));
+        assert_eq!(image.get(5, 0), Some(rgba!(100, 100, 100, 255)));
+    }
+
+    #[test]
+    fn test_parse_qoi_errors() {
+        assert!(matches!(
+            try_parse_qoi(b"qoix".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+
+        let header = |width: u32, height: u32, channels: u8| {
+            let mut inp = b"qoif".to_vec();
+            inp.extend_from_slice(&width.to_be_bytes());
+            inp.extend_from_slice(&height.to_be_bytes());
+            inp.extend_from_slice(&[channels, 0]);
+            inp
+        };
+
+        assert!(matches!(
+            try_parse_qoi(header(1, 1, 2)),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            try_parse_qoi(header(100_000, 100_000, 3)),
+            Err(Error::DimensionOverflow { .. })
+        ));
+        assert!(matches!(
+            try_parse_qoi(header(2, 2, 3)),
+            Err(Error::TruncatedData { .. })
+        ));
+
+        let mut missing_end = header(1, 1, 4);
+        missing_end.extend_from_slice(&[OP_RUN, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(matches!(
+            try_parse_qoi(missing_end),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}
@@ -1,8 +1,8 @@
 //! Lines in 2D.
 
-use crate::{color::Color, geometry::vec::Vec2d, image::Image};
+use crate::{color::Color, geometry::vec::Vec2d};
 
-use super::Shape;
+use super::{Canvas, Shape};
 
 /// Struct for representing a line in 2D space with a given color.
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
@@ -30,7 +30,7 @@ impl Line {
 }
 
 impl Shape for Line {
-    fn draw(&self, img: &mut Image) {
+    fn draw(&self, img: &mut dyn Canvas) {
         // Some quick algorithms for drawing fancy lines
         let p0 = self.start;
         let p1 = self.end;
@@ -54,7 +54,7 @@ impl Shape for Line {
             let mut e = 2 * dy - dx;
 
             while x != x1 || y != y1 {
-                img.set(x as usize, y as usize, &self.color);
+                img.set(x as usize, y as usize, self.color);
 
                 x += sx;
                 if e <= 0 {
@@ -68,7 +68,7 @@ impl Shape for Line {
             let mut e = 2 * dx - dy;
 
             while x != x1 || y != y1 {
-                img.set(x as usize, y as usize, &self.color);
+                img.set(x as usize, y as usize, self.color);
 
                 y += sy;
                 if e <= 0 {
@@ -79,7 +79,7 @@ impl Shape for Line {
                 }
             }
         }
-        img.set(x as usize, y as usize, &self.color);
+        img.set(x as usize, y as usize, self.color);
     }
 }
 
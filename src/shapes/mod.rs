@@ -4,7 +4,10 @@ mod line;
 mod polygon;
 mod rect;
 
-use crate::image::Image;
+use crate::{
+    color::Color,
+    image::{Image, ImageViewMut},
+};
 
 pub use self::line::*;
 pub use self::polygon::*;
@@ -12,5 +15,46 @@ pub use self::rect::*;
 
 pub trait Shape {
     /// Draw this shape.
-    fn draw(&self, img: &mut Image);
+    fn draw(&self, img: &mut dyn Canvas);
+}
+
+/// Trait for RGB images shapes can be drawn on, i.e., an `Image` or an `ImageViewMut` of one.
+pub trait Canvas {
+    /// Get the number of columns of this canvas.
+    fn cols(&self) -> usize;
+
+    /// Get the number of rows of this canvas.
+    fn rows(&self) -> usize;
+
+    /// Set the color of a pixel at the specified coordinates. Pixels outside of the canvas are
+    /// ignored.
+    fn set(&mut self, x: usize, y: usize, color: Color);
+}
+
+impl Canvas for Image {
+    fn cols(&self) -> usize {
+        self.cols()
+    }
+
+    fn rows(&self) -> usize {
+        self.rows()
+    }
+
+    fn set(&mut self, x: usize, y: usize, color: Color) {
+        self.set(x, y, color);
+    }
+}
+
+impl Canvas for ImageViewMut<'_, Color> {
+    fn cols(&self) -> usize {
+        self.cols()
+    }
+
+    fn rows(&self) -> usize {
+        self.rows()
+    }
+
+    fn set(&mut self, x: usize, y: usize, color: Color) {
+        self.set(x, y, color);
+    }
 }
@@ -1,7 +1,7 @@
 //! Module for working with polygons in 2D.
-use crate::{color::Color, geometry::vec::Vec2d, image::Image};
+use crate::{color::Color, geometry::vec::Vec2d};
 
-use super::{Line, Shape};
+use super::{Canvas, Line, Shape};
 
 /// Struct representing a polygon.
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -48,7 +48,7 @@ impl Polygon {
     }
 
     /// Fill this polygon using the scan line algorithm.
-    fn fill(&self, img: &mut Image) {
+    fn fill(&self, img: &mut dyn Canvas) {
         let mut edge_table: Vec<Vec<Edge>> = vec![vec![]; img.rows()];
 
         // iterator over all vertices
@@ -111,7 +111,7 @@ impl Polygon {
                 }
 
                 if in_shape {
-                    img.set(x, y, &self.color);
+                    img.set(x, y, self.color);
                 }
             }
         }
@@ -119,7 +119,7 @@ impl Polygon {
 }
 
 impl Shape for Polygon {
-    fn draw(&self, img: &mut Image) {
+    fn draw(&self, img: &mut dyn Canvas) {
         if self.filled {
             self.fill(img);
         }
@@ -127,7 +127,7 @@ impl Shape for Polygon {
         for (i, point) in self.points.iter().enumerate() {
             let line =
                 Line::new(*point, self.points[(i + 1) % self.points.len()]).with_color(self.color);
-            img.draw(&line);
+            line.draw(img);
         }
     }
 }
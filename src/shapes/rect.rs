@@ -1,6 +1,6 @@
-use crate::{color::Color, geometry::vec::Vec2d, image::Image};
+use crate::{color::Color, geometry::vec::Vec2d};
 
-use super::Shape;
+use super::{Canvas, Shape};
 
 /// Struct representing a rectangle with given anchor and dimensions.
 #[derive(Default, Clone, Copy, Debug)]
@@ -23,14 +23,14 @@ impl Rectangle {
 }
 
 impl Shape for Rectangle {
-    fn draw(&self, img: &mut Image) {
+    fn draw(&self, img: &mut dyn Canvas) {
         for x in 0..self.width {
             for y in 0..self.height {
                 // TODO: add proper bounds checks
                 img.set(
                     self.anchor.x as usize + x,
                     self.anchor.y as usize + y,
-                    &self.color,
+                    self.color,
                 );
             }
         }
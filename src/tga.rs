@@ -0,0 +1,581 @@
+//! Reading and writing images in the TGA (Truevision TARGA) format.
+
+use std::collections::HashMap;
+
+use crate::{
+    bytes::{read_bytes, read_u16_le, read_u8},
+    color::Rgba,
+    error::{checked_samples, Error, Result},
+    gif::{add_to_histogram, build_palette, map_to_palette},
+    image::{GrayImage, Image, RgbaImage},
+};
+
+const HEADER_SIZE: usize = 18;
+const SIGNATURE: &[u8] = b"TRUEVISION-XFILE.\0";
+
+const TYPE_COLOR_MAPPED: u8 = 1;
+const TYPE_TRUE_COLOR: u8 = 2;
+const TYPE_GRAY: u8 = 3;
+/// Offset of the image types with run-length encoding.
+const TYPE_RLE: u8 = 8;
+
+const DESCRIPTOR_RIGHT_TO_LEFT: u8 = 0x10;
+const DESCRIPTOR_TOP_TO_BOTTOM: u8 = 0x20;
+
+/// Longest run or literal sequence, which can be encoded in a single packet.
+const MAX_PACKET: usize = 128;
+
+/// Corner of the image, at which the first pixel is stored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TgaOrigin {
+    BottomLeft,
+    TopLeft,
+}
+
+/// Options for writing TGA files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TgaOptions {
+    /// Compress the pixels with run-length encoding.
+    pub rle: bool,
+    pub origin: TgaOrigin,
+    /// Store RGB images as indices into a color map with at most 256 colors. Images with more
+    /// colors are quantized. Does not apply to grayscale and RGBA images.
+    pub color_mapped: bool,
+}
+
+impl Default for TgaOptions {
+    fn default() -> Self {
+        Self {
+            rle: false,
+            origin: TgaOrigin::BottomLeft,
+            color_mapped: false,
+        }
+    }
+}
+
+/// Trait for transforming any values into their TGA representation.
+pub trait TGA {
+    /// Convert a value into its TGA representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the width or height of the image exceeds 65535 pixels. See `try_to_tga` for a
+    /// fallible variant.
+    fn to_tga(&self, options: &TgaOptions) -> Vec<u8>;
+
+    /// Try to convert a value into its TGA representation. Returns an error, if the width or
+    /// height of the image exceeds 65535 pixels. The default implementation calls `to_tga`.
+    fn try_to_tga(&self, options: &TgaOptions) -> Result<Vec<u8>> {
+        Ok(self.to_tga(options))
+    }
+}
+
+/// Macro for saving an image in its TGA representation to the disk.
+#[macro_export]
+macro_rules! save_tga {
+    ($file_name:expr, $img:expr, $options:expr) => {
+        $crate::try_write_file!($file_name, &$crate::tga::TGA::to_tga(&$img, &$options))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image in its TGA representation to the disk. Returns an error, if the file
+/// could not be written.
+#[macro_export]
+macro_rules! try_save_tga {
+    ($file_name:expr, $img:expr, $options:expr) => {
+        $crate::tga::TGA::try_to_tga(&$img, &$options)
+            .and_then(|tga| $crate::try_write_file!($file_name, &tga))
+    };
+}
+
+/// Layout of the pixels of a TGA file, which is written.
+struct Layout<'a> {
+    image_type: u8,
+    depth: u8,
+    alpha_bits: u8,
+    /// Color map with its entry size in bits.
+    color_map: Option<(&'a [u8], u8)>,
+}
+
+/// Compress a row of pixels with `bpp` bytes each with run-length encoding.
+fn rle_encode(row: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    let pixels: Vec<&[u8]> = row.chunks_exact(bpp).collect();
+    let mut i = 0;
+    while i < pixels.len() {
+        let mut run = 1;
+        while i + run < pixels.len() && run < MAX_PACKET && pixels[i + run] == pixels[i] {
+            run += 1;
+        }
+
+        if run >= 2 {
+            out.push(0x80 | (run - 1) as u8);
+            out.extend_from_slice(pixels[i]);
+            i += run;
+        } else {
+            // copy literally until the next run
+            let start = i;
+            while i < pixels.len()
+                && i - start < MAX_PACKET
+                && !(i + 1 < pixels.len() && pixels[i] == pixels[i + 1])
+            {
+                i += 1;
+            }
+            out.push((i - start - 1) as u8);
+            for pixel in &pixels[start..i] {
+                out.extend_from_slice(pixel);
+            }
+        }
+    }
+}
+
+/// Encode an image as TGA, where `row` provides the pixel data of each row (from top to bottom).
+fn encode_tga(
+    width: usize,
+    height: usize,
+    layout: Layout,
+    options: &TgaOptions,
+    row: impl Fn(usize) -> Vec<u8>,
+) -> Result<Vec<u8>> {
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(Error::DimensionOverflow { width, height });
+    }
+
+    let mut tga = vec![0, layout.color_map.is_some() as u8, layout.image_type];
+    if options.rle {
+        tga[2] += TYPE_RLE;
+    }
+    match layout.color_map {
+        Some((map, entry_size)) => {
+            let entries = map.len() * 8 / entry_size as usize;
+            tga.extend_from_slice(&[0, 0]);
+            tga.extend_from_slice(&(entries as u16).to_le_bytes());
+            tga.push(entry_size);
+        }
+        None => tga.extend_from_slice(&[0; 5]),
+    }
+    tga.extend_from_slice(&[0; 4]);
+    tga.extend_from_slice(&(width as u16).to_le_bytes());
+    tga.extend_from_slice(&(height as u16).to_le_bytes());
+    tga.push(layout.depth);
+    tga.push(match options.origin {
+        TgaOrigin::BottomLeft => layout.alpha_bits,
+        TgaOrigin::TopLeft => layout.alpha_bits | DESCRIPTOR_TOP_TO_BOTTOM,
+    });
+
+    if let Some((map, _)) = layout.color_map {
+        tga.extend_from_slice(map);
+    }
+
+    let bpp = layout.depth as usize / 8;
+    for i in 0..height {
+        let y = match options.origin {
+            TgaOrigin::BottomLeft => height - 1 - i,
+            TgaOrigin::TopLeft => i,
+        };
+        let pixels = row(y);
+        if options.rle {
+            rle_encode(&pixels, bpp, &mut tga);
+        } else {
+            tga.extend_from_slice(&pixels);
+        }
+    }
+
+    // footer without extension and developer area
+    tga.extend_from_slice(&[0; 8]);
+    tga.extend_from_slice(SIGNATURE);
+    Ok(tga)
+}
+
+impl TGA for Image {
+    fn to_tga(&self, options: &TgaOptions) -> Vec<u8> {
+        self.try_to_tga(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_tga(&self, options: &TgaOptions) -> Result<Vec<u8>> {
+        let bgr = |x: usize, y: usize| {
+            let color = self.get(x, y).unwrap_or_default();
+            [color.b, color.g, color.r]
+        };
+
+        if !options.color_mapped {
+            let layout = Layout {
+                image_type: TYPE_TRUE_COLOR,
+                depth: 24,
+                alpha_bits: 0,
+                color_map: None,
+            };
+            return encode_tga(self.cols(), self.rows(), layout, options, |y| {
+                (0..self.cols()).flat_map(|x| bgr(x, y)).collect()
+            });
+        }
+
+        let mut histogram = HashMap::new();
+        add_to_histogram(&mut histogram, self);
+        let palette = build_palette(&histogram, 256);
+        let indices = map_to_palette(self, &palette);
+        let map: Vec<u8> = palette.iter().flat_map(|c| [c.b, c.g, c.r]).collect();
+        let layout = Layout {
+            image_type: TYPE_COLOR_MAPPED,
+            depth: 8,
+            alpha_bits: 0,
+            color_map: Some((&map, 24)),
+        };
+        encode_tga(self.cols(), self.rows(), layout, options, |y| {
+            indices[y * self.cols()..(y + 1) * self.cols()].to_vec()
+        })
+    }
+}
+
+impl TGA for RgbaImage {
+    fn to_tga(&self, options: &TgaOptions) -> Vec<u8> {
+        self.try_to_tga(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_tga(&self, options: &TgaOptions) -> Result<Vec<u8>> {
+        let layout = Layout {
+            image_type: TYPE_TRUE_COLOR,
+            depth: 32,
+            alpha_bits: 8,
+            color_map: None,
+        };
+        encode_tga(self.cols(), self.rows(), layout, options, |y| {
+            (0..self.cols())
+                .flat_map(|x| {
+                    let color = self.get(x, y).unwrap_or_default();
+                    [color.b, color.g, color.r, color.a]
+                })
+                .collect()
+        })
+    }
+}
+
+impl TGA for GrayImage {
+    fn to_tga(&self, options: &TgaOptions) -> Vec<u8> {
+        self.try_to_tga(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_tga(&self, options: &TgaOptions) -> Result<Vec<u8>> {
+        let layout = Layout {
+            image_type: TYPE_GRAY,
+            depth: 8,
+            alpha_bits: 0,
+            color_map: None,
+        };
+        encode_tga(self.cols(), self.rows(), layout, options, |y| {
+            (0..self.cols())
+                .map(|x| self.get(x, y).unwrap_or_default())
+                .collect()
+        })
+    }
+}
+
+/// Check, whether the given data looks like a TGA file. Since TGA files have no magic constant,
+/// either the signature of the footer or a plausible header is required.
+pub(crate) fn is_tga(inp: &[u8]) -> bool {
+    if inp.ends_with(SIGNATURE) {
+        return true;
+    }
+    if inp.len() < HEADER_SIZE {
+        return false;
+    }
+
+    let (color_map_type, image_type) = (inp[1], inp[2]);
+    let color_mapped = matches!(image_type, 1 | 9);
+    matches!(image_type, 1..=3 | 9..=11)
+        && matches!(inp[16], 8 | 15 | 16 | 24 | 32)
+        && match color_map_type {
+            0 => !color_mapped,
+            1 => matches!(inp[7], 15 | 16 | 24 | 32),
+            _ => false,
+        }
+        && inp[17] & 0xc0 == 0
+}
+
+/// Convert a true color pixel (or color map entry) with 15, 16, 24 or 32 bits into a color.
+fn decode_color(bytes: &[u8], alpha: bool) -> Rgba {
+    match bytes {
+        [lo, hi] => {
+            let val = u16::from_le_bytes([*lo, *hi]);
+            let scale = |c: u16| {
+                let c = (c & 0x1f) as u8;
+                (c << 3) | (c >> 2)
+            };
+            let a = if alpha && val & 0x8000 == 0 { 0 } else { 255 };
+            Rgba::new(scale(val >> 10), scale(val >> 5), scale(val), a)
+        }
+        [b, g, r] => Rgba::new(*r, *g, *b, 255),
+        [b, g, r, a, ..] => Rgba::new(*r, *g, *b, if alpha { *a } else { 255 }),
+        _ => Rgba::default(),
+    }
+}
+
+/// Read `count` pixels with `bpp` bytes each, which are optionally run-length encoded.
+fn read_pixels(inp: &[u8], pos: usize, count: usize, bpp: usize, rle: bool) -> Result<Vec<u8>> {
+    let len = checked_samples(count, bpp, 1)?;
+    if !rle {
+        return Ok(read_bytes(inp, pos, len)?.to_vec());
+    }
+
+    // the output grows with the packets, which are actually present, since `len` is taken from
+    // the untrusted header
+    let mut out = vec![];
+    let mut pos = pos;
+    while out.len() < len {
+        let header = read_u8(inp, pos)?;
+        let packet = (header & 0x7f) as usize + 1;
+        pos += 1;
+        if header & 0x80 != 0 {
+            let pixel = read_bytes(inp, pos, bpp)?;
+            for _ in 0..packet {
+                out.extend_from_slice(pixel);
+            }
+            pos += bpp;
+        } else {
+            out.extend_from_slice(read_bytes(inp, pos, packet * bpp)?);
+            pos += packet * bpp;
+        }
+    }
+    out.truncate(len);
+    Ok(out)
+}
+
+/// Parse a vector of u8 into an image in TGA format. This function will panic, if the format is
+/// not valid. See `try_parse_tga` for a fallible variant.
+pub fn parse_tga(inp: Vec<u8>) -> RgbaImage {
+    try_parse_tga(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into an image in TGA format.
+pub fn try_parse_tga(inp: Vec<u8>) -> Result<RgbaImage> {
+    let header = read_bytes(&inp, 0, HEADER_SIZE)?;
+    let id_length = header[0] as usize;
+    let color_map_type = header[1];
+    let image_type = header[2];
+    let first_entry = read_u16_le(header, 3)? as usize;
+    let map_length = read_u16_le(header, 5)? as usize;
+    let entry_size = header[7];
+    let width = read_u16_le(header, 12)? as usize;
+    let height = read_u16_le(header, 14)? as usize;
+    let depth = header[16];
+    let descriptor = header[17];
+    let alpha = descriptor & 0x0f != 0;
+
+    let rle = image_type > TYPE_RLE;
+    let base_type = image_type & !TYPE_RLE;
+    if !matches!(base_type, TYPE_COLOR_MAPPED..=TYPE_GRAY) || color_map_type > 1 {
+        return Err(Error::InvalidHeader(format!(
+            "Unsupported image type ({})",
+            image_type
+        )));
+    }
+    let valid_depth = match base_type {
+        TYPE_COLOR_MAPPED => matches!(depth, 8 | 16) && color_map_type == 1,
+        TYPE_TRUE_COLOR => matches!(depth, 15 | 16 | 24 | 32),
+        _ => matches!(depth, 8 | 16),
+    };
+    if !valid_depth {
+        return Err(Error::InvalidHeader(format!(
+            "Unsupported pixel depth ({}) for image type {}",
+            depth, image_type
+        )));
+    }
+
+    let mut pos = HEADER_SIZE + id_length;
+    let mut color_map = vec![];
+    if color_map_type == 1 {
+        if !matches!(entry_size, 15 | 16 | 24 | 32) {
+            return Err(Error::InvalidHeader(format!(
+                "Unsupported color map entry size ({})",
+                entry_size
+            )));
+        }
+        let entry_bytes = entry_size.div_ceil(8) as usize;
+        color_map = read_bytes(&inp, pos, map_length * entry_bytes)?
+            .chunks_exact(entry_bytes)
+            .map(|entry| decode_color(entry, alpha))
+            .collect();
+        pos += map_length * entry_bytes;
+    }
+
+    let bpp = depth.div_ceil(8) as usize;
+    let pixels = read_pixels(&inp, pos, checked_samples(width, height, 1)?, bpp, rle)?;
+
+    let mut image = RgbaImage::new(width, height);
+    for (i, pixel) in pixels.chunks_exact(bpp).enumerate() {
+        let color = match (base_type, pixel) {
+            (TYPE_COLOR_MAPPED, _) => {
+                let index = match pixel {
+                    [lo, hi] => u16::from_le_bytes([*lo, *hi]) as usize,
+                    _ => pixel[0] as usize,
+                };
+                *index
+                    .checked_sub(first_entry)
+                    .and_then(|index| color_map.get(index))
+                    .ok_or_else(|| {
+                        Error::InvalidData(format!("Color index {} out of bounds", index))
+                    })?
+            }
+            (TYPE_TRUE_COLOR, _) => decode_color(pixel, alpha),
+            (_, [gray, a]) => Rgba::new(*gray, *gray, *gray, if alpha { *a } else { 255 }),
+            (_, _) => Rgba::new(pixel[0], pixel[0], pixel[0], 255),
+        };
+
+        let (col, row) = (i % width, i / width);
+        let x = match descriptor & DESCRIPTOR_RIGHT_TO_LEFT {
+            0 => col,
+            _ => width - 1 - col,
+        };
+        let y = match descriptor & DESCRIPTOR_TOP_TO_BOTTOM {
+            0 => height - 1 - row,
+            _ => row,
+        };
+        image.set(x, y, color);
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rgb, rgba};
+
+    fn gradient(cols: usize, rows: usize) -> Image {
+        let mut image = Image::new(cols, rows);
+        for y in 0..rows {
+            for x in 0..cols {
+                image.set(x, y, rgb!((x / 3 * 20) as u8, (y * 9) as u8, 77));
+            }
+        }
+        image
+    }
+
+    fn assert_images_eq(a: &RgbaImage, b: &RgbaImage) {
+        assert_eq!((a.cols(), a.rows()), (b.cols(), b.rows()));
+        for y in 0..a.rows() {
+            for x in 0..a.cols() {
+                assert_eq!(a.get(x, y), b.get(x, y), "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tga_roundtrip() {
+        let image = gradient(300, 7);
+        let mut transparent = RgbaImage::from(&image);
+        transparent.set(3, 4, rgba!(1, 2, 3, 4));
+
+        for rle in [false, true] {
+            for origin in [TgaOrigin::BottomLeft, TgaOrigin::TopLeft] {
+                let options = TgaOptions {
+                    rle,
+                    origin,
+                    ..Default::default()
+                };
+
+                let parsed = parse_tga(image.to_tga(&options));
+                assert_images_eq(&parsed, &RgbaImage::from(&image));
+
+                let parsed = parse_tga(transparent.to_tga(&options));
+                assert_images_eq(&parsed, &transparent);
+
+                let gray = GrayImage::from(&image);
+                let parsed = parse_tga(gray.to_tga(&options));
+                assert_images_eq(&parsed, &RgbaImage::from(&Image::from(&gray)));
+
+                // few enough colors to be stored without quantization
+                let small = gradient(30, 7);
+                let color_mapped = small.to_tga(&TgaOptions {
+                    color_mapped: true,
+                    ..options
+                });
+                assert_eq!(color_mapped[1..3], [1, if rle { 9 } else { 1 }]);
+                assert_images_eq(&parse_tga(color_mapped), &RgbaImage::from(&small));
+            }
+        }
+    }
+
+    #[test]
+    fn test_tga_rle_compresses() {
+        let mut image = Image::new(200, 10);
+        image.fill_with(rgb!(10, 20, 30));
+        let raw = image.to_tga(&TgaOptions::default());
+        let rle = image.to_tga(&TgaOptions {
+            rle: true,
+            ..Default::default()
+        });
+        assert!(rle.len() * 10 < raw.len());
+        assert!(is_tga(&rle));
+    }
+
+    #[test]
+    fn test_parse_tga_16bit_right_to_left() {
+        // 2x1 true color image with 16 bit pixels, stored from the top right corner
+        let mut tga = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1, 0, 16, 0x31];
+        // red with alpha, then blue without alpha
+        tga.extend_from_slice(&0xfc00u16.to_le_bytes());
+        tga.extend_from_slice(&0x001fu16.to_le_bytes());
+        assert!(is_tga(&tga));
+
+        let image = parse_tga(tga);
+        assert_eq!(image.get(0, 0), Some(rgba!(0, 0, 255, 0)));
+        assert_eq!(image.get(1, 0), Some(rgba!(255, 0, 0, 255)));
+    }
+
+    #[test]
+    fn test_parse_tga_rle_across_rows() {
+        // color mapped 3x2 image with a 16 bit color map starting at index 5, a single run covers
+        // the bottom row and the first pixel of the top row
+        let mut tga = vec![0, 1, 9, 5, 0, 2, 0, 16, 0, 0, 0, 0, 3, 0, 2, 0, 8, 0];
+        tga.extend_from_slice(&0x7c00u16.to_le_bytes());
+        tga.extend_from_slice(&0x03e0u16.to_le_bytes());
+        tga.extend_from_slice(&[0x83, 5, 0x01, 6, 5]);
+
+        let image = parse_tga(tga);
+        assert_eq!(image.get(0, 1), Some(rgba!(255, 0, 0, 255)));
+        assert_eq!(image.get(2, 1), Some(rgba!(255, 0, 0, 255)));
+        assert_eq!(image.get(0, 0), Some(rgba!(255, 0, 0, 255)));
+        assert_eq!(image.get(1, 0), Some(rgba!(0, 255, 0, 255)));
+        assert_eq!(image.get(2, 0), Some(rgba!(255, 0, 0, 255)));
+    }
+
+    #[test]
+    fn test_parse_tga_errors() {
+        let tga = gradient(4, 4).to_tga(&TgaOptions::default());
+        assert!(matches!(
+            try_parse_tga(tga[..30].to_vec()),
+            Err(Error::TruncatedData { .. })
+        ));
+
+        let mut invalid = tga.clone();
+        invalid[2] = 4;
+        assert!(matches!(
+            try_parse_tga(invalid),
+            Err(Error::InvalidHeader(_))
+        ));
+
+        // color index without a color map entry
+        let tga = vec![
+            0, 1, 1, 0, 0, 1, 0, 24, 0, 0, 0, 0, 1, 0, 1, 0, 8, 0, 1, 2, 3, 1,
+        ];
+        assert!(matches!(try_parse_tga(tga), Err(Error::InvalidData(_))));
+
+        assert!(!is_tga(b"not an image at all"));
+    }
+
+    #[test]
+    fn test_parse_tga_rle_huge_dimensions() {
+        // RLE true color image with 65535x65535 pixels, but a single packet
+        let tga = vec![
+            0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 24, 0, 0xff, 1, 2, 3,
+        ];
+        assert!(matches!(
+            try_parse_tga(tga),
+            Err(Error::TruncatedData { .. })
+        ));
+    }
+}
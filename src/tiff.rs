@@ -0,0 +1,1023 @@
+//! Reading and writing images in the TIFF format.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    bytes::{read_bytes, read_u16_be, read_u16_le, read_u32_be, read_u32_le, read_u8},
+    color::{u8_to_u16, Color16},
+    error::{checked_samples, Error, Result},
+    image::{GrayImage, GrayImage16, Image, Image16},
+};
+
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+const TAG_BITS_PER_SAMPLE: u16 = 258;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_PHOTOMETRIC: u16 = 262;
+const TAG_STRIP_OFFSETS: u16 = 273;
+const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+const TAG_ROWS_PER_STRIP: u16 = 278;
+const TAG_STRIP_BYTE_COUNTS: u16 = 279;
+const TAG_X_RESOLUTION: u16 = 282;
+const TAG_Y_RESOLUTION: u16 = 283;
+const TAG_PLANAR_CONFIGURATION: u16 = 284;
+const TAG_RESOLUTION_UNIT: u16 = 296;
+const TAG_PREDICTOR: u16 = 317;
+const TAG_TILE_WIDTH: u16 = 322;
+const TAG_TILE_LENGTH: u16 = 323;
+const TAG_TILE_OFFSETS: u16 = 324;
+const TAG_TILE_BYTE_COUNTS: u16 = 325;
+
+const TYPE_BYTE: u16 = 1;
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_RATIONAL: u16 = 5;
+
+const COMPRESSION_NONE: u32 = 1;
+const COMPRESSION_PACKBITS: u32 = 32773;
+
+const PHOTOMETRIC_WHITE_IS_ZERO: u32 = 0;
+const PHOTOMETRIC_BLACK_IS_ZERO: u32 = 1;
+const PHOTOMETRIC_RGB: u32 = 2;
+
+/// Approximate size of the strips in bytes when writing images.
+const STRIP_SIZE: usize = 8192;
+
+/// Byte order of the integers in a TIFF file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+impl ByteOrder {
+    fn u16(self, val: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::LittleEndian => val.to_le_bytes(),
+            ByteOrder::BigEndian => val.to_be_bytes(),
+        }
+    }
+
+    fn u32(self, val: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::LittleEndian => val.to_le_bytes(),
+            ByteOrder::BigEndian => val.to_be_bytes(),
+        }
+    }
+
+    fn read_u16(self, inp: &[u8], offset: usize) -> Result<u16> {
+        match self {
+            ByteOrder::LittleEndian => read_u16_le(inp, offset),
+            ByteOrder::BigEndian => read_u16_be(inp, offset),
+        }
+    }
+
+    fn read_u32(self, inp: &[u8], offset: usize) -> Result<u32> {
+        match self {
+            ByteOrder::LittleEndian => read_u32_le(inp, offset),
+            ByteOrder::BigEndian => read_u32_be(inp, offset),
+        }
+    }
+}
+
+/// Compression of the image data in a TIFF file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TiffCompression {
+    None,
+    PackBits,
+}
+
+/// Options for writing TIFF files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TiffOptions {
+    pub byte_order: ByteOrder,
+    pub compression: TiffCompression,
+}
+
+impl Default for TiffOptions {
+    fn default() -> Self {
+        Self {
+            byte_order: ByteOrder::LittleEndian,
+            compression: TiffCompression::None,
+        }
+    }
+}
+
+/// Trait for transforming any values into their TIFF representation.
+pub trait TIFF {
+    /// Convert a value into its TIFF representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the file would exceed the 4 GiB addressable by TIFF. See `try_to_tiff` for a
+    /// fallible variant.
+    fn to_tiff(&self, options: &TiffOptions) -> Vec<u8>;
+
+    /// Try to convert a value into its TIFF representation. Returns an error, if the file would
+    /// exceed the 4 GiB addressable by TIFF. The default implementation calls `to_tiff`.
+    fn try_to_tiff(&self, options: &TiffOptions) -> Result<Vec<u8>> {
+        Ok(self.to_tiff(options))
+    }
+}
+
+/// Macro for saving an image or a sequence of pages in its TIFF representation to the disk.
+#[macro_export]
+macro_rules! save_tiff {
+    ($file_name:expr, $img:expr, $options:expr) => {
+        $crate::try_write_file!($file_name, &$crate::tiff::TIFF::to_tiff(&$img, &$options))
+            .expect("Could not write to file");
+    };
+}
+
+/// Macro for saving an image or a sequence of pages in its TIFF representation to the disk.
+/// Returns an error, if the file could not be written.
+#[macro_export]
+macro_rules! try_save_tiff {
+    ($file_name:expr, $img:expr, $options:expr) => {
+        $crate::tiff::TIFF::try_to_tiff(&$img, &$options)
+            .and_then(|tiff| $crate::try_write_file!($file_name, &tiff))
+    };
+}
+
+/// Samples of a single page, which is written to a TIFF file.
+struct Page {
+    width: usize,
+    height: usize,
+    channels: usize,
+    bits: usize,
+    samples: Vec<u16>,
+}
+
+impl From<&Image> for Page {
+    fn from(image: &Image) -> Self {
+        let mut samples = Vec::with_capacity(image.cols() * image.rows() * 3);
+        for y in 0..image.rows() {
+            for x in 0..image.cols() {
+                let color = image.get(x, y).unwrap_or_default();
+                samples.extend_from_slice(&[color.r as u16, color.g as u16, color.b as u16]);
+            }
+        }
+        Self {
+            width: image.cols(),
+            height: image.rows(),
+            channels: 3,
+            bits: 8,
+            samples,
+        }
+    }
+}
+
+impl From<&GrayImage> for Page {
+    fn from(image: &GrayImage) -> Self {
+        let mut samples = Vec::with_capacity(image.cols() * image.rows());
+        for y in 0..image.rows() {
+            for x in 0..image.cols() {
+                samples.push(image.get(x, y).unwrap_or_default() as u16);
+            }
+        }
+        Self {
+            width: image.cols(),
+            height: image.rows(),
+            channels: 1,
+            bits: 8,
+            samples,
+        }
+    }
+}
+
+impl From<&Image16> for Page {
+    fn from(image: &Image16) -> Self {
+        let mut samples = Vec::with_capacity(image.cols() * image.rows() * 3);
+        for y in 0..image.rows() {
+            for x in 0..image.cols() {
+                let color = image.get(x, y).unwrap_or_default();
+                samples.extend_from_slice(&[color.r, color.g, color.b]);
+            }
+        }
+        Self {
+            width: image.cols(),
+            height: image.rows(),
+            channels: 3,
+            bits: 16,
+            samples,
+        }
+    }
+}
+
+impl From<&GrayImage16> for Page {
+    fn from(image: &GrayImage16) -> Self {
+        let mut samples = Vec::with_capacity(image.cols() * image.rows());
+        for y in 0..image.rows() {
+            for x in 0..image.cols() {
+                samples.push(image.get(x, y).unwrap_or_default());
+            }
+        }
+        Self {
+            width: image.cols(),
+            height: image.rows(),
+            channels: 1,
+            bits: 16,
+            samples,
+        }
+    }
+}
+
+/// Compress a row with the PackBits run-length encoding.
+fn packbits_encode(row: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < row.len() {
+        let mut run = 1;
+        while i + run < row.len() && run < 128 && row[i + run] == row[i] {
+            run += 1;
+        }
+
+        if run >= 3 {
+            out.push((257 - run) as u8);
+            out.push(row[i]);
+            i += run;
+        } else {
+            // copy literally until the next run of at least 3 bytes
+            let start = i;
+            while i < row.len() && i - start < 128 {
+                if i + 2 < row.len() && row[i] == row[i + 1] && row[i] == row[i + 2] {
+                    break;
+                }
+                i += 1;
+            }
+            out.push((i - start - 1) as u8);
+            out.extend_from_slice(&row[start..i]);
+        }
+    }
+}
+
+/// Decompress PackBits encoded data until `expected` bytes are produced.
+fn packbits_decode(inp: &[u8], expected: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(expected);
+    let mut pos = 0;
+    while out.len() < expected {
+        let Some(header) = inp.get(pos) else {
+            return Err(Error::TruncatedData {
+                expected,
+                actual: out.len(),
+            });
+        };
+        pos += 1;
+        match *header as i8 {
+            -128 => {}
+            n @ 0.. => {
+                let len = n as usize + 1;
+                out.extend_from_slice(read_bytes(inp, pos, len)?);
+                pos += len;
+            }
+            n => {
+                let byte = read_u8(inp, pos)?;
+                pos += 1;
+                out.resize(out.len() + (1 - n as isize) as usize, byte);
+            }
+        }
+    }
+    out.truncate(expected);
+    Ok(out)
+}
+
+/// Convert a value into u32, which is used for all offsets and sizes in TIFF files. Returns
+/// `None`, if the value does not fit.
+fn to_u32(val: usize) -> Option<u32> {
+    u32::try_from(val).ok()
+}
+
+/// Entry of an image file directory: tag, field type, number of values and the encoded values.
+type Entry = (u16, u16, usize, Vec<u8>);
+
+/// Append an image file directory, where values, which do not fit into an entry, are stored
+/// directly after it. Returns the offset of the field pointing to the next directory or `None`,
+/// if an offset or count does not fit into 32 bits.
+fn write_ifd(tiff: &mut Vec<u8>, order: ByteOrder, entries: &[Entry]) -> Option<usize> {
+    let extra_start = tiff.len() + 2 + entries.len() * 12 + 4;
+    let mut extra = vec![];
+
+    tiff.extend_from_slice(&order.u16(entries.len() as u16));
+    for (tag, field_type, count, value) in entries {
+        tiff.extend_from_slice(&order.u16(*tag));
+        tiff.extend_from_slice(&order.u16(*field_type));
+        tiff.extend_from_slice(&order.u32(to_u32(*count)?));
+        if value.len() <= 4 {
+            tiff.extend_from_slice(value);
+            tiff.extend_from_slice(&[0; 4][value.len()..]);
+        } else {
+            tiff.extend_from_slice(&order.u32(to_u32(extra_start + extra.len())?));
+            extra.extend_from_slice(value);
+            if extra.len() % 2 == 1 {
+                extra.push(0);
+            }
+        }
+    }
+
+    let next = tiff.len();
+    tiff.extend_from_slice(&[0; 4]);
+    tiff.extend_from_slice(&extra);
+    Some(next)
+}
+
+/// Encode pages as TIFF, where every page is stored in strips.
+fn encode_tiff(pages: &[Page], options: &TiffOptions) -> Result<Vec<u8>> {
+    let order = options.byte_order;
+    let shorts =
+        |values: &[u16]| -> Vec<u8> { values.iter().flat_map(|v| order.u16(*v)).collect() };
+    let longs = |values: &[u32]| -> Vec<u8> { values.iter().flat_map(|v| order.u32(*v)).collect() };
+
+    let mut tiff = match order {
+        ByteOrder::LittleEndian => b"II".to_vec(),
+        ByteOrder::BigEndian => b"MM".to_vec(),
+    };
+    tiff.extend_from_slice(&order.u16(42));
+    let mut next_ifd = tiff.len();
+    tiff.extend_from_slice(&[0; 4]);
+
+    for page in pages {
+        let overflow = || Error::DimensionOverflow {
+            width: page.width,
+            height: page.height,
+        };
+        let row_samples = page.width * page.channels;
+        let row_bytes = row_samples * page.bits / 8;
+        let rows_per_strip = (STRIP_SIZE / row_bytes.max(1)).clamp(1, page.height.max(1));
+
+        let mut offsets = vec![];
+        let mut counts = vec![];
+        for strip in page.samples.chunks((rows_per_strip * row_samples).max(1)) {
+            let start = tiff.len();
+            for row in strip.chunks(row_samples.max(1)) {
+                let bytes: Vec<u8> = match page.bits {
+                    8 => row.iter().map(|s| *s as u8).collect(),
+                    _ => row.iter().flat_map(|s| order.u16(*s)).collect(),
+                };
+                match options.compression {
+                    TiffCompression::None => tiff.extend_from_slice(&bytes),
+                    TiffCompression::PackBits => packbits_encode(&bytes, &mut tiff),
+                }
+            }
+            offsets.push(to_u32(start).ok_or_else(overflow)?);
+            counts.push(to_u32(tiff.len() - start).ok_or_else(overflow)?);
+        }
+        if tiff.len() % 2 == 1 {
+            tiff.push(0);
+        }
+
+        let ifd = to_u32(tiff.len()).ok_or_else(overflow)?;
+        tiff[next_ifd..next_ifd + 4].copy_from_slice(&order.u32(ifd));
+
+        let compression = match options.compression {
+            TiffCompression::None => COMPRESSION_NONE,
+            TiffCompression::PackBits => COMPRESSION_PACKBITS,
+        };
+        let photometric = match page.channels {
+            1 => PHOTOMETRIC_BLACK_IS_ZERO,
+            _ => PHOTOMETRIC_RGB,
+        };
+        let resolution = longs(&[72, 1]);
+        let entries: Vec<Entry> = vec![
+            (
+                TAG_IMAGE_WIDTH,
+                TYPE_LONG,
+                1,
+                longs(&[to_u32(page.width).ok_or_else(overflow)?]),
+            ),
+            (
+                TAG_IMAGE_LENGTH,
+                TYPE_LONG,
+                1,
+                longs(&[to_u32(page.height).ok_or_else(overflow)?]),
+            ),
+            (
+                TAG_BITS_PER_SAMPLE,
+                TYPE_SHORT,
+                page.channels,
+                shorts(&vec![page.bits as u16; page.channels]),
+            ),
+            (
+                TAG_COMPRESSION,
+                TYPE_SHORT,
+                1,
+                shorts(&[compression as u16]),
+            ),
+            (
+                TAG_PHOTOMETRIC,
+                TYPE_SHORT,
+                1,
+                shorts(&[photometric as u16]),
+            ),
+            (TAG_STRIP_OFFSETS, TYPE_LONG, offsets.len(), longs(&offsets)),
+            (
+                TAG_SAMPLES_PER_PIXEL,
+                TYPE_SHORT,
+                1,
+                shorts(&[page.channels as u16]),
+            ),
+            (
+                TAG_ROWS_PER_STRIP,
+                TYPE_LONG,
+                1,
+                longs(&[to_u32(rows_per_strip).ok_or_else(overflow)?]),
+            ),
+            (
+                TAG_STRIP_BYTE_COUNTS,
+                TYPE_LONG,
+                counts.len(),
+                longs(&counts),
+            ),
+            (TAG_X_RESOLUTION, TYPE_RATIONAL, 1, resolution.clone()),
+            (TAG_Y_RESOLUTION, TYPE_RATIONAL, 1, resolution),
+            (TAG_PLANAR_CONFIGURATION, TYPE_SHORT, 1, shorts(&[1])),
+            // resolution in dots per inch
+            (TAG_RESOLUTION_UNIT, TYPE_SHORT, 1, shorts(&[2])),
+        ];
+        next_ifd = write_ifd(&mut tiff, order, &entries).ok_or_else(overflow)?;
+    }
+
+    Ok(tiff)
+}
+
+impl TIFF for Image {
+    fn to_tiff(&self, options: &TiffOptions) -> Vec<u8> {
+        self.try_to_tiff(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_tiff(&self, options: &TiffOptions) -> Result<Vec<u8>> {
+        encode_tiff(&[Page::from(self)], options)
+    }
+}
+
+impl TIFF for GrayImage {
+    fn to_tiff(&self, options: &TiffOptions) -> Vec<u8> {
+        self.try_to_tiff(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_tiff(&self, options: &TiffOptions) -> Result<Vec<u8>> {
+        encode_tiff(&[Page::from(self)], options)
+    }
+}
+
+impl TIFF for Image16 {
+    fn to_tiff(&self, options: &TiffOptions) -> Vec<u8> {
+        self.try_to_tiff(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_tiff(&self, options: &TiffOptions) -> Result<Vec<u8>> {
+        encode_tiff(&[Page::from(self)], options)
+    }
+}
+
+impl TIFF for GrayImage16 {
+    fn to_tiff(&self, options: &TiffOptions) -> Vec<u8> {
+        self.try_to_tiff(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_tiff(&self, options: &TiffOptions) -> Result<Vec<u8>> {
+        encode_tiff(&[Page::from(self)], options)
+    }
+}
+
+impl TIFF for [Image] {
+    fn to_tiff(&self, options: &TiffOptions) -> Vec<u8> {
+        self.try_to_tiff(options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_to_tiff(&self, options: &TiffOptions) -> Result<Vec<u8>> {
+        let pages: Vec<Page> = self.iter().map(Page::from).collect();
+        encode_tiff(&pages, options)
+    }
+}
+
+impl TIFF for Vec<Image> {
+    fn to_tiff(&self, options: &TiffOptions) -> Vec<u8> {
+        self.as_slice().to_tiff(options)
+    }
+
+    fn try_to_tiff(&self, options: &TiffOptions) -> Result<Vec<u8>> {
+        self.as_slice().try_to_tiff(options)
+    }
+}
+
+/// Read the entries of an image file directory. Only entries with integer values are kept.
+/// Returns the entries and the offset of the next directory.
+fn read_ifd(inp: &[u8], order: ByteOrder, offset: usize) -> Result<(HashMap<u16, Vec<u32>>, u32)> {
+    let count = order.read_u16(inp, offset)? as usize;
+    let mut entries = HashMap::new();
+
+    for i in 0..count {
+        let entry = offset + 2 + i * 12;
+        let tag = order.read_u16(inp, entry)?;
+        let size = match order.read_u16(inp, entry + 2)? {
+            TYPE_BYTE => 1,
+            TYPE_SHORT => 2,
+            TYPE_LONG => 4,
+            _ => continue,
+        };
+        let count = order.read_u32(inp, entry + 4)? as usize;
+        let len = count
+            .checked_mul(size)
+            .ok_or_else(|| Error::InvalidHeader(format!("Too many values for tag {}", tag)))?;
+
+        let value_offset = if len <= 4 {
+            entry + 8
+        } else {
+            order.read_u32(inp, entry + 8)? as usize
+        };
+        let values = read_bytes(inp, value_offset, len)?
+            .chunks_exact(size)
+            .map(|bytes| match size {
+                1 => Ok(bytes[0] as u32),
+                2 => order.read_u16(bytes, 0).map(u32::from),
+                _ => order.read_u32(bytes, 0),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        entries.insert(tag, values);
+    }
+
+    let next = order.read_u32(inp, offset + 2 + count * 12)?;
+    Ok((entries, next))
+}
+
+/// Decode the image described by the entries of an image file directory.
+fn decode_page(inp: &[u8], order: ByteOrder, entries: &HashMap<u16, Vec<u32>>) -> Result<Image16> {
+    let value = |tag: u16| entries.get(&tag).and_then(|values| values.first()).copied();
+    let required = |tag: u16, name: &str| {
+        value(tag).ok_or_else(|| Error::InvalidHeader(format!("Missing {}", name)))
+    };
+
+    let width = required(TAG_IMAGE_WIDTH, "image width")? as usize;
+    let height = required(TAG_IMAGE_LENGTH, "image length")? as usize;
+    let channels = value(TAG_SAMPLES_PER_PIXEL).unwrap_or(1) as usize;
+
+    let bits = entries
+        .get(&TAG_BITS_PER_SAMPLE)
+        .cloned()
+        .unwrap_or_else(|| vec![1]);
+    if !matches!(bits[..], [8 | 16, ..]) || bits.iter().any(|b| *b != bits[0]) {
+        return Err(Error::InvalidHeader(format!(
+            "Unsupported bits per sample ({:?})",
+            bits
+        )));
+    }
+    let bytes_per_sample = bits[0] as usize / 8;
+
+    let compression = value(TAG_COMPRESSION).unwrap_or(COMPRESSION_NONE);
+    if compression != COMPRESSION_NONE && compression != COMPRESSION_PACKBITS {
+        return Err(Error::InvalidHeader(format!(
+            "Unsupported compression ({})",
+            compression
+        )));
+    }
+
+    let photometric = required(TAG_PHOTOMETRIC, "photometric interpretation")?;
+    let color_channels = match photometric {
+        PHOTOMETRIC_WHITE_IS_ZERO | PHOTOMETRIC_BLACK_IS_ZERO => 1,
+        PHOTOMETRIC_RGB => 3,
+        _ => {
+            return Err(Error::InvalidHeader(format!(
+                "Unsupported photometric interpretation ({})",
+                photometric
+            )))
+        }
+    };
+    if channels < color_channels {
+        return Err(Error::InvalidHeader(format!(
+            "Invalid number of samples per pixel ({})",
+            channels
+        )));
+    }
+    if value(TAG_PLANAR_CONFIGURATION).unwrap_or(1) != 1 {
+        return Err(Error::InvalidHeader(
+            "Unsupported planar configuration".to_string(),
+        ));
+    }
+    if value(TAG_PREDICTOR).unwrap_or(1) != 1 {
+        return Err(Error::InvalidHeader("Unsupported predictor".to_string()));
+    }
+
+    let pixel_bytes = channels * bytes_per_sample;
+    let row_bytes = checked_samples(width, 1, pixel_bytes)?;
+
+    // collect the offset, byte count and decompressed size of every strip or tile
+    let mut segments = vec![];
+    let tiles = match (value(TAG_TILE_WIDTH), value(TAG_TILE_LENGTH)) {
+        (Some(tile_width), Some(tile_height)) => {
+            let (tile_width, tile_height) = (tile_width as usize, tile_height as usize);
+            if tile_width == 0 || tile_height == 0 {
+                return Err(Error::InvalidHeader("Invalid tile size".to_string()));
+            }
+            let offsets = entries
+                .get(&TAG_TILE_OFFSETS)
+                .ok_or_else(|| Error::InvalidHeader("Missing tile offsets".to_string()))?;
+            let counts = entries.get(&TAG_TILE_BYTE_COUNTS);
+            let across = width.div_ceil(tile_width);
+            let down = height.div_ceil(tile_height);
+            if offsets.len() < checked_samples(across, down, 1)? {
+                return Err(Error::InvalidHeader("Missing tile offsets".to_string()));
+            }
+            let tile_size = checked_samples(tile_width, tile_height, pixel_bytes)?;
+            for (i, offset) in offsets.iter().take(across * down).enumerate() {
+                let count = counts.and_then(|c| c.get(i)).copied();
+                segments.push((*offset, count, tile_size));
+            }
+            Some((tile_width, tile_height, across))
+        }
+        _ => {
+            let offsets = entries
+                .get(&TAG_STRIP_OFFSETS)
+                .ok_or_else(|| Error::InvalidHeader("Missing strip offsets".to_string()))?;
+            let counts = entries.get(&TAG_STRIP_BYTE_COUNTS);
+            let rows_per_strip =
+                (value(TAG_ROWS_PER_STRIP).unwrap_or(u32::MAX) as usize).clamp(1, height.max(1));
+            let strips = if row_bytes == 0 {
+                0
+            } else {
+                height.div_ceil(rows_per_strip)
+            };
+            if offsets.len() < strips {
+                return Err(Error::InvalidHeader("Missing strip offsets".to_string()));
+            }
+            for (i, offset) in offsets.iter().take(strips).enumerate() {
+                let rows = rows_per_strip.min(height - i * rows_per_strip);
+                let count = counts.and_then(|c| c.get(i)).copied();
+                segments.push((*offset, count, rows * row_bytes));
+            }
+            None
+        }
+    };
+
+    // check that every segment can provide its samples before allocating anything, so that a
+    // small file cannot claim huge dimensions
+    for &(offset, count, expected) in &segments {
+        let available = inp.len().saturating_sub(offset as usize);
+        let capacity = match compression {
+            COMPRESSION_NONE => available,
+            _ => {
+                let count =
+                    count.ok_or_else(|| Error::InvalidHeader("Missing byte counts".to_string()))?;
+                // every two bytes of PackBits data produce at most 128 bytes
+                (count as usize).min(available).saturating_mul(64)
+            }
+        };
+        if capacity < expected {
+            return Err(Error::TruncatedData {
+                expected,
+                actual: capacity,
+            });
+        }
+    }
+
+    let decompress = |offset: u32, count: Option<u32>, expected: usize| -> Result<Vec<u8>> {
+        match compression {
+            COMPRESSION_NONE => Ok(read_bytes(inp, offset as usize, expected)?.to_vec()),
+            _ => {
+                let count =
+                    count.ok_or_else(|| Error::InvalidHeader("Missing byte counts".to_string()))?;
+                packbits_decode(read_bytes(inp, offset as usize, count as usize)?, expected)
+            }
+        }
+    };
+
+    let mut data = vec![0; checked_samples(row_bytes, height, 1)?];
+    match tiles {
+        Some((tile_width, tile_height, across)) => {
+            let tile_row = tile_width * pixel_bytes;
+            for (i, &(offset, count, expected)) in segments.iter().enumerate() {
+                let (tx, ty) = (i % across, i / across);
+                let tile = decompress(offset, count, expected)?;
+                // tiles at the right and bottom border are cut off
+                let len = tile_width.min(width - tx * tile_width) * pixel_bytes;
+                for y in 0..tile_height.min(height - ty * tile_height) {
+                    let dst = (ty * tile_height + y) * row_bytes + tx * tile_row;
+                    data[dst..dst + len].copy_from_slice(&tile[y * tile_row..y * tile_row + len]);
+                }
+            }
+        }
+        None => {
+            let mut dst = 0;
+            for &(offset, count, expected) in &segments {
+                data[dst..dst + expected].copy_from_slice(&decompress(offset, count, expected)?);
+                dst += expected;
+            }
+        }
+    }
+
+    let sample = |i: usize| -> Result<u16> {
+        match bytes_per_sample {
+            1 => Ok(u8_to_u16(data[i])),
+            _ => order.read_u16(&data, i * 2),
+        }
+    };
+    let mut image = Image16::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * width + x) * channels;
+            let color = match photometric {
+                PHOTOMETRIC_WHITE_IS_ZERO => Color16::splat(u16::MAX - sample(i)?),
+                PHOTOMETRIC_BLACK_IS_ZERO => Color16::splat(sample(i)?),
+                _ => Color16::new(sample(i)?, sample(i + 1)?, sample(i + 2)?),
+            };
+            image.set(x, y, color);
+        }
+    }
+    Ok(image)
+}
+
+/// Parse a vector of u8 into the pages of a TIFF. 16 bit samples are reduced to 8 bit, see
+/// `parse_tiff16` for keeping them. This function will panic, if the format is not valid. See
+/// `try_parse_tiff` for a fallible variant.
+pub fn parse_tiff(inp: Vec<u8>) -> Vec<Image> {
+    try_parse_tiff(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into the pages of a TIFF. 16 bit samples are reduced to 8 bit, see
+/// `try_parse_tiff16` for keeping them.
+pub fn try_parse_tiff(inp: Vec<u8>) -> Result<Vec<Image>> {
+    Ok(try_parse_tiff16(inp)?.iter().map(Image::from).collect())
+}
+
+/// Parse a vector of u8 into the pages of a TIFF with 16 bit samples. 8 bit samples are scaled to
+/// the full range and grayscale pages are converted to RGB. This function will panic, if the
+/// format is not valid. See `try_parse_tiff16` for a fallible variant.
+pub fn parse_tiff16(inp: Vec<u8>) -> Vec<Image16> {
+    try_parse_tiff16(inp).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Try to parse a vector of u8 into the pages of a TIFF with 16 bit samples. 8 bit samples are
+/// scaled to the full range and grayscale pages are converted to RGB.
+pub fn try_parse_tiff16(inp: Vec<u8>) -> Result<Vec<Image16>> {
+    let order = match inp.get(0..4) {
+        Some(b"II*\0") => ByteOrder::LittleEndian,
+        Some(b"MM\0*") => ByteOrder::BigEndian,
+        _ => {
+            return Err(Error::InvalidMagicConstant(
+                "No valid magic constant".to_string(),
+            ))
+        }
+    };
+
+    let mut pages = vec![];
+    let mut visited = HashSet::new();
+    let mut offset = order.read_u32(&inp, 4)?;
+    while offset != 0 {
+        if !visited.insert(offset) {
+            return Err(Error::InvalidData(
+                "Image file directories form a cycle".to_string(),
+            ));
+        }
+        let (entries, next) = read_ifd(&inp, order, offset as usize)?;
+        pages.push(decode_page(&inp, order, &entries)?);
+        offset = next;
+    }
+
+    if pages.is_empty() {
+        return Err(Error::InvalidData("No image data".to_string()));
+    }
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::{u16_to_u8, Color},
+        rgb,
+    };
+
+    fn gradient(cols: usize, rows: usize) -> Image {
+        let mut image = Image::new(cols, rows);
+        for y in 0..rows {
+            for x in 0..cols {
+                // long runs for PackBits as well as literal sections
+                image.set(x, y, rgb!((x / 4 * 30) as u8, (y * 7) as u8, (x * y) as u8));
+            }
+        }
+        image
+    }
+
+    fn assert_images_eq(a: &Image, b: &Image) {
+        assert_eq!((a.cols(), a.rows()), (b.cols(), b.rows()));
+        for y in 0..a.rows() {
+            for x in 0..a.cols() {
+                assert_eq!(a.get(x, y), b.get(x, y), "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_packbits() {
+        // example of the TIFF specification
+        let packed = [
+            0xfe, 0xaa, 0x02, 0x80, 0x00, 0x2a, 0xfd, 0xaa, 0x03, 0x80, 0x00, 0x2a, 0x22, 0xf7,
+            0xaa,
+        ];
+        let unpacked = [
+            0xaa, 0xaa, 0xaa, 0x80, 0x00, 0x2a, 0xaa, 0xaa, 0xaa, 0xaa, 0x80, 0x00, 0x2a, 0x22,
+            0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+        ];
+        assert_eq!(packbits_decode(&packed, unpacked.len()).unwrap(), unpacked);
+
+        let mut encoded = vec![];
+        packbits_encode(&unpacked, &mut encoded);
+        assert_eq!(encoded, packed);
+
+        let long: Vec<u8> = (0..1000)
+            .map(|i| if i < 500 { 7 } else { i as u8 })
+            .collect();
+        let mut encoded = vec![];
+        packbits_encode(&long, &mut encoded);
+        assert_eq!(packbits_decode(&encoded, long.len()).unwrap(), long);
+        assert!(packbits_decode(&encoded[..10], long.len()).is_err());
+    }
+
+    #[test]
+    fn test_tiff_roundtrip() {
+        let image = gradient(67, 150);
+        for byte_order in [ByteOrder::LittleEndian, ByteOrder::BigEndian] {
+            for compression in [TiffCompression::None, TiffCompression::PackBits] {
+                let tiff = image.to_tiff(&TiffOptions {
+                    byte_order,
+                    compression,
+                });
+                let pages = parse_tiff(tiff);
+                assert_eq!(pages.len(), 1);
+                assert_images_eq(&pages[0], &image);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tiff_gray_and_16bit() {
+        let image = gradient(13, 9);
+        let gray = GrayImage::from(&image);
+        let options = TiffOptions {
+            byte_order: ByteOrder::BigEndian,
+            compression: TiffCompression::PackBits,
+        };
+
+        let expected = Image::from(&gray);
+        assert_images_eq(&parse_tiff(gray.to_tiff(&options))[0], &expected);
+
+        let deep = Image16::from(&image);
+        assert_images_eq(&parse_tiff(deep.to_tiff(&options))[0], &image);
+
+        let mut gray16 = GrayImage16::new(2, 1);
+        gray16.set(0, 0, 0x1234);
+        gray16.set(1, 0, u8_to_u16(200));
+        let parsed = &parse_tiff(gray16.to_tiff(&TiffOptions::default()))[0];
+        assert_eq!(parsed.get(0, 0), Some(Color::splat(u16_to_u8(0x1234))));
+        assert_eq!(parsed.get(1, 0), Some(Color::splat(200)));
+
+        let mut color16 = Image16::new(1, 1);
+        color16.set(0, 0, Color16::new(0xffff, 0, 0x8080));
+        let parsed = &parse_tiff(color16.to_tiff(&options))[0];
+        assert_eq!(parsed.get(0, 0), Some(rgb!(255, 0, 128)));
+    }
+
+    #[test]
+    fn test_tiff16_roundtrip() {
+        let mut color16 = Image16::new(3, 2);
+        color16.set(0, 0, Color16::new(0x1234, 0xfffe, 1));
+        color16.set(2, 1, Color16::new(0x8001, 0x0102, 0xabcd));
+        for compression in [TiffCompression::None, TiffCompression::PackBits] {
+            let options = TiffOptions {
+                byte_order: ByteOrder::BigEndian,
+                compression,
+            };
+            assert_eq!(
+                parse_tiff16(color16.to_tiff(&options)),
+                vec![color16.clone()]
+            );
+        }
+
+        let mut gray16 = GrayImage16::new(2, 1);
+        gray16.set(0, 0, 0x1234);
+        let parsed = &parse_tiff16(gray16.to_tiff(&TiffOptions::default()))[0];
+        assert_eq!(parsed, &Image16::from(&gray16));
+
+        // 8 bit samples are scaled to the full range
+        let image = gradient(4, 3);
+        let parsed = &parse_tiff16(image.to_tiff(&TiffOptions::default()))[0];
+        assert_eq!(parsed, &Image16::from(&image));
+    }
+
+    #[test]
+    fn test_tiff_multi_page() {
+        let pages = vec![gradient(5, 4), gradient(3, 8), Image::new(1, 1)];
+        let tiff = pages.to_tiff(&TiffOptions {
+            byte_order: ByteOrder::BigEndian,
+            compression: TiffCompression::None,
+        });
+        assert_eq!(&tiff[0..4], b"MM\0*");
+
+        let parsed = parse_tiff(tiff);
+        assert_eq!(parsed.len(), 3);
+        for (page, original) in parsed.iter().zip(&pages) {
+            assert_images_eq(page, original);
+        }
+    }
+
+    #[test]
+    fn test_parse_tiled_tiff() {
+        // 3x3 gray image with white-is-zero in 2x2 tiles
+        let order = ByteOrder::LittleEndian;
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend_from_slice(&order.u32(8));
+        let tiles: [[u8; 4]; 4] = [[0, 1, 3, 4], [2, 0, 5, 0], [6, 7, 0, 0], [8, 0, 0, 0]];
+        let mut offsets = vec![];
+        for tile in tiles {
+            offsets.push(tiff.len() as u32);
+            tiff.extend_from_slice(&tile);
+        }
+
+        let longs =
+            |values: &[u32]| -> Vec<u8> { values.iter().flat_map(|v| order.u32(*v)).collect() };
+        let shorts =
+            |values: &[u16]| -> Vec<u8> { values.iter().flat_map(|v| order.u16(*v)).collect() };
+        let ifd = tiff.len() as u32;
+        tiff[4..8].copy_from_slice(&order.u32(ifd));
+        write_ifd(
+            &mut tiff,
+            order,
+            &[
+                (TAG_IMAGE_WIDTH, TYPE_SHORT, 1, shorts(&[3])),
+                (TAG_IMAGE_LENGTH, TYPE_SHORT, 1, shorts(&[3])),
+                (TAG_BITS_PER_SAMPLE, TYPE_SHORT, 1, shorts(&[8])),
+                (TAG_PHOTOMETRIC, TYPE_SHORT, 1, shorts(&[0])),
+                (TAG_TILE_WIDTH, TYPE_SHORT, 1, shorts(&[2])),
+                (TAG_TILE_LENGTH, TYPE_SHORT, 1, shorts(&[2])),
+                (TAG_TILE_OFFSETS, TYPE_LONG, 4, longs(&offsets)),
+                (TAG_TILE_BYTE_COUNTS, TYPE_LONG, 4, longs(&[4; 4])),
+            ],
+        );
+
+        let image = &parse_tiff(tiff)[0];
+        for y in 0..3 {
+            for x in 0..3 {
+                let expected = 255 - (y * 3 + x) as u8;
+                assert_eq!(image.get(x, y), Some(Color::splat(expected)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_tiff_errors() {
+        assert!(matches!(
+            try_parse_tiff(b"II+\0\x08\0\0\0".to_vec()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+
+        let mut tiff = gradient(4, 4).to_tiff(&TiffOptions::default());
+        let truncated = tiff[..tiff.len() - 20].to_vec();
+        assert!(matches!(
+            try_parse_tiff(truncated),
+            Err(Error::TruncatedData { .. })
+        ));
+
+        // point the first directory at itself as the next one
+        let ifd = read_u32_le(&tiff, 4).unwrap() as usize;
+        let count = read_u16_le(&tiff, ifd).unwrap() as usize;
+        let next = ifd + 2 + count * 12;
+        tiff[next..next + 4].copy_from_slice(&(ifd as u32).to_le_bytes());
+        assert!(matches!(try_parse_tiff(tiff), Err(Error::InvalidData(_))));
+    }
+
+    #[test]
+    fn test_parse_tiff_huge_dimensions() {
+        // a single strip of a 65535x65535 RGB image, which only provides a few bytes
+        let order = ByteOrder::LittleEndian;
+        let longs =
+            |values: &[u32]| -> Vec<u8> { values.iter().flat_map(|v| order.u32(*v)).collect() };
+        let shorts =
+            |values: &[u16]| -> Vec<u8> { values.iter().flat_map(|v| order.u16(*v)).collect() };
+        for (compression, count) in [(1, 4), (COMPRESSION_PACKBITS as u16, 4)] {
+            let mut tiff = b"II*\0".to_vec();
+            tiff.extend_from_slice(&order.u32(12));
+            tiff.extend_from_slice(&[0xfd, 0xff, 0xfd, 0xff]);
+            write_ifd(
+                &mut tiff,
+                order,
+                &[
+                    (TAG_IMAGE_WIDTH, TYPE_SHORT, 1, shorts(&[65535])),
+                    (TAG_IMAGE_LENGTH, TYPE_SHORT, 1, shorts(&[65535])),
+                    (TAG_BITS_PER_SAMPLE, TYPE_SHORT, 1, shorts(&[8])),
+                    (TAG_COMPRESSION, TYPE_SHORT, 1, shorts(&[compression])),
+                    (TAG_PHOTOMETRIC, TYPE_SHORT, 1, shorts(&[2])),
+                    (TAG_SAMPLES_PER_PIXEL, TYPE_SHORT, 1, shorts(&[3])),
+                    (TAG_STRIP_OFFSETS, TYPE_LONG, 1, longs(&[8])),
+                    (TAG_STRIP_BYTE_COUNTS, TYPE_LONG, 1, longs(&[count])),
+                ],
+            );
+            assert!(matches!(
+                try_parse_tiff(tiff),
+                Err(Error::TruncatedData { .. })
+            ));
+        }
+    }
+}
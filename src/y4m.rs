@@ -0,0 +1,676 @@
+//! Streaming frames of uncompressed videos in the Y4M (YUV4MPEG2) format.
+
+use std::io::{Read, Write};
+
+use crate::{
+    color::Color,
+    error::{checked_samples, Error, Result},
+    image::Image,
+};
+
+const MAGIC: &[u8] = b"YUV4MPEG2";
+const FRAME: &[u8] = b"FRAME";
+
+/// Longest header (or frame header) line, which is accepted.
+const MAX_LINE: usize = 4096;
+
+/// Layout of the chroma planes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChromaMode {
+    /// Chroma subsampled in both directions and centered between the luma samples.
+    Yuv420Jpeg,
+    /// Chroma subsampled in both directions, co-sited horizontally and centered vertically.
+    Yuv420Mpeg2,
+    /// Chroma subsampled horizontally and co-sited with the luma samples.
+    Yuv422,
+    /// Chroma without subsampling.
+    Yuv444,
+}
+
+impl ChromaMode {
+    /// Get the subsampling factors and the position of the first chroma sample (in luma samples).
+    fn siting(self) -> (usize, usize, f32, f32) {
+        match self {
+            ChromaMode::Yuv420Jpeg => (2, 2, 0.5, 0.5),
+            ChromaMode::Yuv420Mpeg2 => (2, 2, 0.0, 0.5),
+            ChromaMode::Yuv422 => (2, 1, 0.0, 0.0),
+            ChromaMode::Yuv444 => (1, 1, 0.0, 0.0),
+        }
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            ChromaMode::Yuv420Jpeg => "420jpeg",
+            ChromaMode::Yuv420Mpeg2 => "420mpeg2",
+            ChromaMode::Yuv422 => "422",
+            ChromaMode::Yuv444 => "444",
+        }
+    }
+}
+
+/// Interlacing of the frames.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interlacing {
+    Progressive,
+    TopFieldFirst,
+    BottomFieldFirst,
+    /// The interlacing is specified per frame.
+    Mixed,
+    Unknown,
+}
+
+impl Interlacing {
+    fn tag(self) -> char {
+        match self {
+            Interlacing::Progressive => 'p',
+            Interlacing::TopFieldFirst => 't',
+            Interlacing::BottomFieldFirst => 'b',
+            Interlacing::Mixed => 'm',
+            Interlacing::Unknown => '?',
+        }
+    }
+}
+
+/// Range of the luma and chroma samples.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorRange {
+    /// Luma from 16 to 235 and chroma from 16 to 240 (the default of Y4M).
+    Limited,
+    /// Luma and chroma from 0 to 255.
+    Full,
+}
+
+/// Metadata of a Y4M stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Y4mHeader {
+    pub width: usize,
+    pub height: usize,
+    /// Frame rate as numerator and denominator.
+    pub frame_rate: (u32, u32),
+    /// Pixel aspect ratio as numerator and denominator, where `(0, 0)` is unknown.
+    pub aspect: (u32, u32),
+    pub interlacing: Interlacing,
+    pub chroma: ChromaMode,
+    pub color_range: ColorRange,
+    /// Additional (application specific) parameters, which start with an `X`.
+    pub extensions: Vec<String>,
+}
+
+impl Y4mHeader {
+    /// Create a header for progressive frames with the given size and frame rate, square pixels,
+    /// 420jpeg chroma and limited range.
+    pub fn new(width: usize, height: usize, frame_rate: (u32, u32)) -> Self {
+        Self {
+            width,
+            height,
+            frame_rate,
+            aspect: (1, 1),
+            interlacing: Interlacing::Progressive,
+            chroma: ChromaMode::Yuv420Jpeg,
+            color_range: ColorRange::Limited,
+            extensions: vec![],
+        }
+    }
+
+    /// Get the size of a chroma plane.
+    fn chroma_size(&self) -> (usize, usize) {
+        let (sub_x, sub_y, _, _) = self.chroma.siting();
+        (self.width.div_ceil(sub_x), self.height.div_ceil(sub_y))
+    }
+
+    /// Get the number of bytes of a frame (without the frame header).
+    fn frame_size(&self) -> Result<usize> {
+        let (cols, rows) = self.chroma_size();
+        let luma = checked_samples(self.width, self.height, 1)?;
+        checked_samples(cols, rows, 2)?
+            .checked_add(luma)
+            .ok_or(Error::DimensionOverflow {
+                width: self.width,
+                height: self.height,
+            })
+    }
+
+    fn parse(line: &str) -> Result<Self> {
+        let ratio = |value: &str| {
+            let (num, den) = value.split_once(':')?;
+            Some((num.parse().ok()?, den.parse().ok()?))
+        };
+        let invalid = |token: &str| Error::InvalidHeader(format!("Invalid parameter ({})", token));
+
+        let mut header = Y4mHeader::new(0, 0, (0, 0));
+        let (mut width, mut height, mut frame_rate) = (None, None, None);
+        for token in line.split(' ').filter(|token| !token.is_empty()) {
+            let Some((tag, value)) = token.split_at_checked(1) else {
+                return Err(invalid(token));
+            };
+            match tag {
+                "W" => width = Some(value.parse().map_err(|_| invalid(token))?),
+                "H" => height = Some(value.parse().map_err(|_| invalid(token))?),
+                "F" => frame_rate = Some(ratio(value).ok_or_else(|| invalid(token))?),
+                "A" => header.aspect = ratio(value).ok_or_else(|| invalid(token))?,
+                "I" => {
+                    header.interlacing = match value {
+                        "p" => Interlacing::Progressive,
+                        "t" => Interlacing::TopFieldFirst,
+                        "b" => Interlacing::BottomFieldFirst,
+                        "m" => Interlacing::Mixed,
+                        "?" => Interlacing::Unknown,
+                        _ => return Err(invalid(token)),
+                    }
+                }
+                "C" => {
+                    header.chroma = match value {
+                        "420jpeg" | "420" => ChromaMode::Yuv420Jpeg,
+                        "420mpeg2" => ChromaMode::Yuv420Mpeg2,
+                        "422" => ChromaMode::Yuv422,
+                        "444" => ChromaMode::Yuv444,
+                        _ => {
+                            return Err(Error::InvalidHeader(format!(
+                                "Unsupported chroma mode ({})",
+                                value
+                            )))
+                        }
+                    }
+                }
+                "X" => match value {
+                    "COLORRANGE=FULL" => header.color_range = ColorRange::Full,
+                    "COLORRANGE=LIMITED" => header.color_range = ColorRange::Limited,
+                    _ => header.extensions.push(value.to_string()),
+                },
+                // unknown parameters are ignored
+                _ => {}
+            }
+        }
+
+        let (Some(width), Some(height), Some(frame_rate)) = (width, height, frame_rate) else {
+            return Err(Error::InvalidHeader(
+                "Missing width, height or frame rate".to_string(),
+            ));
+        };
+        header.width = width;
+        header.height = height;
+        header.frame_rate = frame_rate;
+        Ok(header)
+    }
+
+    fn to_line(&self) -> String {
+        let mut line = format!(
+            "YUV4MPEG2 W{} H{} F{}:{} I{} A{}:{} C{}",
+            self.width,
+            self.height,
+            self.frame_rate.0,
+            self.frame_rate.1,
+            self.interlacing.tag(),
+            self.aspect.0,
+            self.aspect.1,
+            self.chroma.tag()
+        );
+        if self.color_range == ColorRange::Full {
+            line.push_str(" XCOLORRANGE=FULL");
+        }
+        for extension in &self.extensions {
+            line.push_str(" X");
+            line.push_str(extension);
+        }
+        line.push('\n');
+        line
+    }
+}
+
+/// Read into the buffer until it is full or the reader is exhausted. Returns the number of bytes
+/// read.
+fn read_fully(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(len)
+}
+
+/// Read a line (without the line break). Returns `None`, if the reader is exhausted before the
+/// first byte.
+fn read_line(reader: &mut impl Read) -> Result<Option<Vec<u8>>> {
+    let mut line = vec![];
+    let mut byte = [0];
+    loop {
+        if read_fully(reader, &mut byte)? == 0 {
+            if line.is_empty() {
+                return Ok(None);
+            }
+            return Err(Error::TruncatedData {
+                expected: line.len() + 1,
+                actual: line.len(),
+            });
+        }
+        if byte[0] == b'\n' {
+            return Ok(Some(line));
+        }
+        if line.len() == MAX_LINE {
+            return Err(Error::InvalidHeader("Header line is too long".to_string()));
+        }
+        line.push(byte[0]);
+    }
+}
+
+/// Convert a color into luma and chroma (ITU-R BT.601) in the range from 0 to 255.
+fn rgb_to_ycbcr(color: &Color) -> [f32; 3] {
+    let (r, g, b) = (color.r as f32, color.g as f32, color.b as f32);
+    [
+        0.299 * r + 0.587 * g + 0.114 * b,
+        128.0 - 0.168_736 * r - 0.331_264 * g + 0.5 * b,
+        128.0 + 0.5 * r - 0.418_688 * g - 0.081_312 * b,
+    ]
+}
+
+/// Convert luma and chroma (ITU-R BT.601) in the range from 0 to 255 into a color.
+fn ycbcr_to_rgb(y: f32, cb: f32, cr: f32) -> Color {
+    let (cb, cr) = (cb - 128.0, cr - 128.0);
+    let clamp = |val: f32| val.round().clamp(0.0, 255.0) as u8;
+    Color::new(
+        clamp(y + 1.402 * cr),
+        clamp(y - 0.344_136 * cb - 0.714_136 * cr),
+        clamp(y + 1.772 * cb),
+    )
+}
+
+/// Convert a full range luma or chroma sample into the stored sample.
+fn encode_sample(val: f32, range: ColorRange, chroma: bool) -> u8 {
+    let val = match (range, chroma) {
+        (ColorRange::Full, _) => val,
+        (ColorRange::Limited, false) => 16.0 + val * 219.0 / 255.0,
+        (ColorRange::Limited, true) => 128.0 + (val - 128.0) * 224.0 / 255.0,
+    };
+    val.round().clamp(0.0, 255.0) as u8
+}
+
+/// Convert a stored sample into a full range luma or chroma sample.
+fn decode_sample(val: u8, range: ColorRange, chroma: bool) -> f32 {
+    let val = val as f32;
+    match (range, chroma) {
+        (ColorRange::Full, _) => val,
+        (ColorRange::Limited, false) => (val - 16.0) * 255.0 / 219.0,
+        (ColorRange::Limited, true) => 128.0 + (val - 128.0) * 255.0 / 224.0,
+    }
+}
+
+/// Weights of the luma samples contributing to a chroma sample at the given position (tent
+/// filter with the width of the subsampling factor).
+fn tent_weights(center: f32, sub: usize, len: usize) -> Vec<(usize, f32)> {
+    let radius = sub as f32;
+    let first = (center - radius).floor().max(0.0) as usize;
+    let last = ((center + radius).ceil() as usize).min(len.saturating_sub(1));
+    let weights: Vec<(usize, f32)> = (first..=last)
+        .map(|i| (i, 1.0 - (i as f32 - center).abs() / radius))
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect();
+    let sum: f32 = weights.iter().map(|(_, weight)| weight).sum();
+    weights.into_iter().map(|(i, w)| (i, w / sum)).collect()
+}
+
+/// Streaming reader for the frames of a Y4M video.
+pub struct Y4mReader<R: Read> {
+    reader: R,
+    header: Y4mHeader,
+}
+
+impl<R: Read> Y4mReader<R> {
+    /// Create a new reader and parse the stream header. For better performance, the given reader
+    /// should be buffered.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let line = read_line(&mut reader)?.unwrap_or_default();
+        let Some(params) = line.strip_prefix(MAGIC) else {
+            return Err(Error::InvalidMagicConstant(
+                "No valid magic constant".to_string(),
+            ));
+        };
+        let header = Y4mHeader::parse(&String::from_utf8_lossy(params))?;
+        Ok(Self { reader, header })
+    }
+
+    /// Get the metadata of the stream.
+    pub fn header(&self) -> &Y4mHeader {
+        &self.header
+    }
+
+    /// Read the next frame. Returns `None` at the end of the stream.
+    pub fn read_frame(&mut self) -> Result<Option<Image>> {
+        let Some(line) = read_line(&mut self.reader)? else {
+            return Ok(None);
+        };
+        if !line.starts_with(FRAME) {
+            return Err(Error::InvalidData("Missing frame header".to_string()));
+        }
+
+        // the buffer grows with the data, which is actually read, since the size of the frame is
+        // taken from the untrusted header
+        let size = self.header.frame_size()?;
+        let mut data = vec![];
+        (&mut self.reader)
+            .take(size as u64)
+            .read_to_end(&mut data)?;
+        if data.len() < size {
+            return Err(Error::TruncatedData {
+                expected: size,
+                actual: data.len(),
+            });
+        }
+
+        let Y4mHeader {
+            width,
+            height,
+            color_range,
+            ..
+        } = self.header;
+        let (cols, rows) = self.header.chroma_size();
+        let (sub_x, sub_y, offset_x, offset_y) = self.header.chroma.siting();
+        let (luma, chroma) = data.split_at(width * height);
+        let (cb, cr) = chroma.split_at(cols * rows);
+
+        // bilinear interpolation of the chroma planes
+        let position = |i: usize, sub: usize, offset: f32, len: usize| {
+            let pos = ((i as f32 - offset) / sub as f32).clamp(0.0, (len - 1) as f32);
+            let first = pos.floor() as usize;
+            (first, (first + 1).min(len - 1), pos - first as f32)
+        };
+        let sample = |plane: &[u8], x: usize, y: usize| {
+            decode_sample(plane[y * cols + x], color_range, true)
+        };
+        let interpolate =
+            |plane: &[u8], (x0, x1, fx): (usize, usize, f32), (y0, y1, fy): (usize, usize, f32)| {
+                let top = sample(plane, x0, y0) * (1.0 - fx) + sample(plane, x1, y0) * fx;
+                let bottom = sample(plane, x0, y1) * (1.0 - fx) + sample(plane, x1, y1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            };
+
+        let mut image = Image::new(width, height);
+        for y in 0..height {
+            let pos_y = position(y, sub_y, offset_y, rows);
+            for x in 0..width {
+                let pos_x = position(x, sub_x, offset_x, cols);
+                let color = ycbcr_to_rgb(
+                    decode_sample(luma[y * width + x], color_range, false),
+                    interpolate(cb, pos_x, pos_y),
+                    interpolate(cr, pos_x, pos_y),
+                );
+                image.set(x, y, color);
+            }
+        }
+        Ok(Some(image))
+    }
+}
+
+impl<R: Read> Iterator for Y4mReader<R> {
+    type Item = Result<Image>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+/// Streaming writer for the frames of a Y4M video.
+pub struct Y4mWriter<W: Write> {
+    writer: W,
+    header: Y4mHeader,
+}
+
+impl<W: Write> Y4mWriter<W> {
+    /// Create a new writer and write the stream header.
+    pub fn new(mut writer: W, header: Y4mHeader) -> Result<Self> {
+        writer.write_all(header.to_line().as_bytes())?;
+        Ok(Self { writer, header })
+    }
+
+    /// Get the metadata of the stream.
+    pub fn header(&self) -> &Y4mHeader {
+        &self.header
+    }
+
+    /// Write a single frame, which must have the size given in the header.
+    pub fn write_frame(&mut self, image: &Image) -> Result<()> {
+        let Y4mHeader {
+            width,
+            height,
+            color_range,
+            ..
+        } = self.header;
+        if image.cols() != width || image.rows() != height {
+            return Err(Error::InvalidData(format!(
+                "Frame size {}x{} does not match the stream size {}x{}",
+                image.cols(),
+                image.rows(),
+                width,
+                height
+            )));
+        }
+
+        let mut luma = Vec::with_capacity(width * height);
+        let mut planes = [vec![0.0; width * height], vec![0.0; width * height]];
+        for y in 0..height {
+            for x in 0..width {
+                let [l, cb, cr] = rgb_to_ycbcr(&image.get(x, y).unwrap_or_default());
+                luma.push(encode_sample(l, color_range, false));
+                planes[0][y * width + x] = cb;
+                planes[1][y * width + x] = cr;
+            }
+        }
+
+        // subsample the chroma planes with separable tent filters
+        let (cols, rows) = self.header.chroma_size();
+        let (sub_x, sub_y, offset_x, offset_y) = self.header.chroma.siting();
+        let weights_x: Vec<_> = (0..cols)
+            .map(|i| tent_weights((i * sub_x) as f32 + offset_x, sub_x, width))
+            .collect();
+        let weights_y: Vec<_> = (0..rows)
+            .map(|j| tent_weights((j * sub_y) as f32 + offset_y, sub_y, height))
+            .collect();
+
+        self.writer.write_all(b"FRAME\n")?;
+        self.writer.write_all(&luma)?;
+        for plane in &planes {
+            let mut subsampled = Vec::with_capacity(cols * rows);
+            for weights_y in &weights_y {
+                for weights_x in &weights_x {
+                    let mut val = 0.0;
+                    for (y, wy) in weights_y {
+                        for (x, wx) in weights_x {
+                            val += plane[y * width + x] * wx * wy;
+                        }
+                    }
+                    subsampled.push(encode_sample(val, color_range, true));
+                }
+            }
+            self.writer.write_all(&subsampled)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Get the underlying writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgb;
+
+    fn frame(cols: usize, rows: usize, offset: u8) -> Image {
+        let mut image = Image::new(cols, rows);
+        for y in 0..rows {
+            for x in 0..cols {
+                let val = if x < cols / 2 { 40 } else { 200 };
+                image.set(
+                    x,
+                    y,
+                    rgb!(val, offset.wrapping_add(y as u8 * 10), 255 - val),
+                );
+            }
+        }
+        image
+    }
+
+    fn max_error(a: &Image, b: &Image) -> u8 {
+        let mut max = 0;
+        for y in 0..a.rows() {
+            for x in 0..a.cols() {
+                let (a, b) = (a.get(x, y).unwrap(), b.get(x, y).unwrap());
+                max = max
+                    .max(a.r.abs_diff(b.r))
+                    .max(a.g.abs_diff(b.g))
+                    .max(a.b.abs_diff(b.b));
+            }
+        }
+        max
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let line = "YUV4MPEG2 W640 H480 F30000:1001 It A128:117 C420mpeg2 XYSCSS=420MPEG2 XCOLORRANGE=FULL\n";
+        let reader = Y4mReader::new(line.as_bytes()).unwrap();
+        let header = reader.header().clone();
+        assert_eq!((header.width, header.height), (640, 480));
+        assert_eq!(header.frame_rate, (30000, 1001));
+        assert_eq!(header.aspect, (128, 117));
+        assert_eq!(header.interlacing, Interlacing::TopFieldFirst);
+        assert_eq!(header.chroma, ChromaMode::Yuv420Mpeg2);
+        assert_eq!(header.color_range, ColorRange::Full);
+        assert_eq!(header.extensions, vec!["YSCSS=420MPEG2".to_string()]);
+
+        let writer = Y4mWriter::new(vec![], header.clone()).unwrap();
+        let written = writer.into_inner();
+        assert_eq!(
+            Y4mReader::new(written.as_slice()).unwrap().header(),
+            &header
+        );
+    }
+
+    #[test]
+    fn test_y4m_roundtrip() {
+        let frames = [frame(9, 7, 0), frame(9, 7, 100)];
+        for chroma in [
+            ChromaMode::Yuv420Jpeg,
+            ChromaMode::Yuv420Mpeg2,
+            ChromaMode::Yuv422,
+            ChromaMode::Yuv444,
+        ] {
+            for color_range in [ColorRange::Limited, ColorRange::Full] {
+                let header = Y4mHeader {
+                    chroma,
+                    color_range,
+                    ..Y4mHeader::new(9, 7, (25, 1))
+                };
+                let mut writer = Y4mWriter::new(vec![], header).unwrap();
+                for frame in &frames {
+                    writer.write_frame(frame).unwrap();
+                }
+                let data = writer.into_inner();
+
+                let parsed: Vec<Image> = Y4mReader::new(data.as_slice())
+                    .unwrap()
+                    .collect::<Result<_>>()
+                    .unwrap();
+                assert_eq!(parsed.len(), 2);
+                for (parsed, original) in parsed.iter().zip(&frames) {
+                    let tolerance = if chroma == ChromaMode::Yuv444 { 2 } else { 90 };
+                    assert!(max_error(parsed, original) <= tolerance, "{:?}", chroma);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_y4m_flat_colors() {
+        // subsampling must not change areas of constant color
+        let mut image = Image::new(6, 4);
+        image.fill_with(rgb!(30, 160, 90));
+        for chroma in [
+            ChromaMode::Yuv420Jpeg,
+            ChromaMode::Yuv420Mpeg2,
+            ChromaMode::Yuv422,
+        ] {
+            let header = Y4mHeader {
+                chroma,
+                ..Y4mHeader::new(6, 4, (24, 1))
+            };
+            let mut writer = Y4mWriter::new(vec![], header).unwrap();
+            writer.write_frame(&image).unwrap();
+            let data = writer.into_inner();
+
+            let parsed = Y4mReader::new(data.as_slice())
+                .unwrap()
+                .read_frame()
+                .unwrap()
+                .unwrap();
+            assert!(max_error(&parsed, &image) <= 2);
+        }
+    }
+
+    #[test]
+    fn test_parse_y4m_frame() {
+        // 2x2 frame in full range 444 with white, black, red and gray
+        let mut data = b"YUV4MPEG2 W2 H2 F25:1 C444 XCOLORRANGE=FULL\nFRAME\n".to_vec();
+        data.extend_from_slice(&[255, 0, 76, 128]);
+        data.extend_from_slice(&[128, 128, 85, 128]);
+        data.extend_from_slice(&[128, 128, 255, 128]);
+
+        let mut reader = Y4mReader::new(data.as_slice()).unwrap();
+        let image = reader.read_frame().unwrap().unwrap();
+        assert_eq!(image.get(0, 0), Some(rgb!(255)));
+        assert_eq!(image.get(1, 0), Some(rgb!(0)));
+        assert_eq!(image.get(0, 1), Some(rgb!(254, 0, 0)));
+        assert_eq!(image.get(1, 1), Some(rgb!(128)));
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_y4m_errors() {
+        assert!(matches!(
+            Y4mReader::new("YUV4MPEG W1 H1 F1:1\n".as_bytes()),
+            Err(Error::InvalidMagicConstant(_))
+        ));
+        assert!(matches!(
+            Y4mReader::new("YUV4MPEG2 W1 F1:1\n".as_bytes()),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            Y4mReader::new("YUV4MPEG2 W1 H1 F1:1 Cmono\n".as_bytes()),
+            Err(Error::InvalidHeader(_))
+        ));
+
+        let data = b"YUV4MPEG2 W4 H4 F1:1\nFRAME\n\x10\x10".to_vec();
+        let mut reader = Y4mReader::new(data.as_slice()).unwrap();
+        assert!(matches!(
+            reader.read_frame(),
+            Err(Error::TruncatedData {
+                expected: 24,
+                actual: 2
+            })
+        ));
+
+        let mut writer = Y4mWriter::new(vec![], Y4mHeader::new(4, 4, (1, 1))).unwrap();
+        assert!(writer.write_frame(&Image::new(3, 4)).is_err());
+    }
+
+    #[test]
+    fn test_y4m_huge_dimensions() {
+        for header in ["W3000000000 H3000000000", "W100000 H100000"] {
+            let data = format!("YUV4MPEG2 {} F25:1\nFRAME\n\x10", header);
+            let mut reader = Y4mReader::new(data.as_bytes()).unwrap();
+            assert!(matches!(
+                reader.read_frame(),
+                Err(Error::TruncatedData { actual: 1, .. })
+            ));
+        }
+    }
+}